@@ -1,10 +1,44 @@
-use crate::{log_wrapper::Logger, todo::ToDo};
+use crate::{
+    config,
+    date::{self, Date},
+    filter::Filter,
+    log_wrapper::{Logger, Style},
+    output::{self, Json, OutputMode},
+    template::Template,
+    todo::ToDo,
+};
+use std::collections::BTreeSet;
+use std::env;
 use std::fmt::{self, Display};
 use std::io::{self, stdout, Write};
 
-use self::database::Database;
+use self::{
+    database::{Database, Integrity},
+    listing::{ListFormat, ListOptions},
+    table::Table,
+};
 
+mod archive;
+mod backup;
+mod csv;
 pub mod database;
+mod doctor;
+mod encryption;
+mod events;
+mod export;
+mod history;
+mod ical;
+mod import;
+mod journal;
+pub mod listing;
+mod lists;
+mod sync;
+pub mod table;
+mod taskwarrior;
+#[cfg(test)]
+mod test_database;
+mod todotxt;
+mod trash;
 
 #[derive(PartialEq, Debug)]
 pub enum ActionType {
@@ -14,6 +48,28 @@ pub enum ActionType {
     Done(bool),
     Undone(bool),
     Delete(bool),
+    Config(bool),
+    Lists(bool),
+    ListCreate(bool),
+    ListRename(bool),
+    ListDelete(bool),
+    Use(bool),
+    Move(bool),
+    Init(bool),
+    Undo(bool),
+    Redo(bool),
+    Archive(bool),
+    Unarchive(bool),
+    Trash(bool),
+    Restore(bool),
+    Backup(bool),
+    History(bool),
+    Doctor(bool),
+    Encrypt(bool),
+    ChangePassphrase(bool),
+    Import(bool),
+    Export(bool),
+    Sync(bool),
 }
 
 impl ActionType {
@@ -25,10 +81,80 @@ impl ActionType {
             "done" => Ok(ActionType::Done(true)),
             "undone" => Ok(ActionType::Undone(true)),
             "delete" => Ok(ActionType::Delete(true)),
+            "config" => Ok(ActionType::Config(true)),
+            "lists" => Ok(ActionType::Lists(false)),
+            "list-create" => Ok(ActionType::ListCreate(true)),
+            "list-rename" => Ok(ActionType::ListRename(true)),
+            "list-delete" => Ok(ActionType::ListDelete(true)),
+            "use" => Ok(ActionType::Use(true)),
+            "move" => Ok(ActionType::Move(true)),
+            "init" => Ok(ActionType::Init(false)),
+            "undo" => Ok(ActionType::Undo(false)),
+            "redo" => Ok(ActionType::Redo(false)),
+            "archive" => Ok(ActionType::Archive(false)),
+            "unarchive" => Ok(ActionType::Unarchive(true)),
+            "trash" => Ok(ActionType::Trash(true)),
+            "restore" => Ok(ActionType::Restore(true)),
+            "backup" => Ok(ActionType::Backup(true)),
+            "history" => Ok(ActionType::History(true)),
+            "doctor" => Ok(ActionType::Doctor(false)),
+            "encrypt" => Ok(ActionType::Encrypt(false)),
+            "change-passphrase" => Ok(ActionType::ChangePassphrase(false)),
+            "import" => Ok(ActionType::Import(true)),
+            "export" => Ok(ActionType::Export(true)),
+            "sync" => Ok(ActionType::Sync(true)),
             _ => Err("Action is not valid"),
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActionType::Create(_) => "create",
+            ActionType::List(_) => "list",
+            ActionType::Edit(_) => "edit",
+            ActionType::Done(_) => "done",
+            ActionType::Undone(_) => "undone",
+            ActionType::Delete(_) => "delete",
+            ActionType::Config(_) => "config",
+            ActionType::Lists(_) => "lists",
+            ActionType::ListCreate(_) => "list-create",
+            ActionType::ListRename(_) => "list-rename",
+            ActionType::ListDelete(_) => "list-delete",
+            ActionType::Use(_) => "use",
+            ActionType::Move(_) => "move",
+            ActionType::Init(_) => "init",
+            ActionType::Undo(_) => "undo",
+            ActionType::Redo(_) => "redo",
+            ActionType::Archive(_) => "archive",
+            ActionType::Unarchive(_) => "unarchive",
+            ActionType::Trash(_) => "trash",
+            ActionType::Restore(_) => "restore",
+            ActionType::Backup(_) => "backup",
+            ActionType::History(_) => "history",
+            ActionType::Doctor(_) => "doctor",
+            ActionType::Encrypt(_) => "encrypt",
+            ActionType::ChangePassphrase(_) => "change-passphrase",
+            ActionType::Import(_) => "import",
+            ActionType::Export(_) => "export",
+            ActionType::Sync(_) => "sync",
+        }
+    }
+
+    /// Whether the action makes use of optional arguments, like the filter
+    /// expression of `list`.
+    pub fn accepts_arguments(&self) -> bool {
+        !matches!(
+            self,
+            ActionType::Create(_)
+                | ActionType::Lists(_)
+                | ActionType::Init(_)
+                | ActionType::Undo(_)
+                | ActionType::Redo(_)
+                | ActionType::Encrypt(_)
+                | ActionType::ChangePassphrase(_)
+        )
+    }
+
     pub fn requires_arguments(&self) -> bool {
         match self {
             ActionType::Create(req_args) => *req_args,
@@ -37,6 +163,60 @@ impl ActionType {
             ActionType::Done(req_args) => *req_args,
             ActionType::Undone(req_args) => *req_args,
             ActionType::Delete(req_args) => *req_args,
+            ActionType::Config(req_args) => *req_args,
+            ActionType::Lists(req_args) => *req_args,
+            ActionType::ListCreate(req_args) => *req_args,
+            ActionType::ListRename(req_args) => *req_args,
+            ActionType::ListDelete(req_args) => *req_args,
+            ActionType::Use(req_args) => *req_args,
+            ActionType::Move(req_args) => *req_args,
+            ActionType::Init(req_args) => *req_args,
+            ActionType::Undo(req_args) => *req_args,
+            ActionType::Redo(req_args) => *req_args,
+            ActionType::Archive(req_args) => *req_args,
+            ActionType::Unarchive(req_args) => *req_args,
+            ActionType::Trash(req_args) => *req_args,
+            ActionType::Restore(req_args) => *req_args,
+            ActionType::Backup(req_args) => *req_args,
+            ActionType::History(req_args) => *req_args,
+            ActionType::Doctor(req_args) => *req_args,
+            ActionType::Encrypt(req_args) => *req_args,
+            ActionType::ChangePassphrase(req_args) => *req_args,
+            ActionType::Import(req_args) => *req_args,
+            ActionType::Export(req_args) => *req_args,
+            ActionType::Sync(req_args) => *req_args,
+        }
+    }
+}
+
+/// When `delete` asks before removing items, from the `confirm` setting:
+/// never, only when more than one item is selected, or always.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Confirm {
+    Never,
+    Bulk,
+    Always,
+}
+
+impl Confirm {
+    pub fn new(confirm_name: &str) -> Result<Self, &'static str> {
+        match confirm_name {
+            "never" => Ok(Confirm::Never),
+            "bulk" => Ok(Confirm::Bulk),
+            "always" => Ok(Confirm::Always),
+            _ => Err("Confirm can only be never, bulk or always"),
+        }
+    }
+
+    pub fn configured() -> Result<Self, &'static str> {
+        Confirm::new(config::current().get("confirm").unwrap_or("never"))
+    }
+
+    pub fn asks(&self, item_count: usize) -> bool {
+        match self {
+            Confirm::Never => false,
+            Confirm::Bulk => item_count > 1,
+            Confirm::Always => true,
         }
     }
 }
@@ -47,6 +227,7 @@ pub struct Action<'a> {
     pub arguments: Vec<String>,
     pub logger: Option<&'a mut dyn Logger>,
     pub database: Option<&'a dyn Database>,
+    pub output: OutputMode,
 }
 
 impl<'a> fmt::Debug for Action<'a> {
@@ -84,10 +265,21 @@ impl<'a> Action<'a> {
             arguments: vec![],
             logger: Some(logger),
             database: Some(database),
+            output: OutputMode::Text,
         })
     }
 
     pub fn execute_action(&mut self) -> Result<(), &'static str> {
+        let result = self.run_action();
+        self.log_warnings();
+        result
+    }
+
+    fn run_action(&mut self) -> Result<(), &'static str> {
+        if self.rewrites_list() {
+            self.confirm_unverified_list()?;
+        }
+
         match self.action_type {
             ActionType::Create(_) => self.create(),
             ActionType::List(_) => self.list(),
@@ -95,63 +287,226 @@ impl<'a> Action<'a> {
             ActionType::Done(_) => self.done(),
             ActionType::Undone(_) => self.undone(),
             ActionType::Delete(_) => self.delete(),
+            ActionType::Config(_) => self.config(),
+            ActionType::Lists(_) => self.lists(),
+            ActionType::ListCreate(_) => self.list_create(),
+            ActionType::ListRename(_) => self.list_rename(),
+            ActionType::ListDelete(_) => self.list_delete(),
+            ActionType::Use(_) => self.use_list(),
+            ActionType::Move(_) => self.move_items(),
+            ActionType::Init(_) => self.init(),
+            ActionType::Undo(_) => self.undo(),
+            ActionType::Redo(_) => self.redo(),
+            ActionType::Archive(_) => self.archive(),
+            ActionType::Unarchive(_) => self.unarchive(),
+            ActionType::Trash(_) => self.trash(),
+            ActionType::Restore(_) => self.restore(),
+            ActionType::Backup(_) => self.backup(),
+            ActionType::History(_) => self.history(),
+            ActionType::Doctor(_) => self.doctor(),
+            ActionType::Encrypt(_) => self.encrypt(),
+            ActionType::ChangePassphrase(_) => self.change_passphrase(),
+            ActionType::Import(_) => self.import(),
+            ActionType::Export(_) => self.export(),
+            ActionType::Sync(_) => self.sync(),
+        }
+    }
+
+    /// Whether the action writes over the list in use, which is more than
+    /// adding to it like `create` does.
+    fn rewrites_list(&self) -> bool {
+        let view = self.arguments.first().map(String::as_str);
+
+        match self.action_type {
+            ActionType::Edit(_)
+            | ActionType::Done(_)
+            | ActionType::Undone(_)
+            | ActionType::Delete(_)
+            | ActionType::Move(_)
+            | ActionType::Undo(_)
+            | ActionType::Redo(_)
+            | ActionType::Unarchive(_)
+            | ActionType::Restore(_)
+            | ActionType::Import(_)
+            | ActionType::Sync(_) => true,
+            ActionType::Archive(_) => !matches!(view, Some("list" | "search")),
+            ActionType::Backup(_) => view == Some("restore"),
+            _ => false,
+        }
+    }
+
+    /// Asks before writing over a list that is not the way terminal-todo
+    /// last wrote it, as what changed since would be lost.
+    fn confirm_unverified_list(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+
+        let question = match database.integrity()? {
+            Integrity::Verified => return Ok(()),
+            Integrity::Modified => {
+                "The list was changed outside of terminal-todo, write over it anyway?"
+            }
+            Integrity::Truncated => "The list looks cut off, write over it anyway?",
+        };
+
+        match self.confirm(question) {
+            true => Ok(()),
+            false => Err("Nothing was changed, the list could not be verified"),
         }
     }
 
     fn list(&mut self) -> Result<(), &'static str> {
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&"Printing all ToDo items.")
-            .unwrap();
+        self.archive_old_items()?;
 
-        let todos: Vec<ToDo> = self
-            .database
-            .expect("Database could not be found")
-            .read_items()?;
+        let arguments = self.arguments.clone();
+        self.print_items(&arguments, "Printing all ToDo items.", |database| {
+            database.read_items()
+        })
+    }
 
-        todos.iter().enumerate().for_each(|(index, todo)| {
-            self.logger
-                .as_mut()
-                .unwrap()
-                .log_stdln(&format!(
-                    "===============\n# {}\n{todo}\n===============\n",
-                    index + 1
-                ))
-                .unwrap();
-        });
+    /// Prints the items given by `read_items` that match the filter in
+    /// `arguments`, laid out with the listing options among them.
+    fn print_items<F>(
+        &mut self,
+        arguments: &[String],
+        heading: &str,
+        read_items: F,
+    ) -> Result<(), &'static str>
+    where
+        F: FnOnce(&dyn Database) -> Result<Vec<ToDo>, &'static str>,
+    {
+        let (list_options, filter_arguments) =
+            ListOptions::parse(arguments, ListFormat::configured()?)?;
+
+        let filter: Filter = match Filter::parse(&filter_arguments.join(" ")) {
+            Ok(filter) => filter,
+            Err(err) => {
+                self.log_error_detail(&format!("Invalid filter: {err}"));
+                return Err("Could not parse the list filter");
+            }
+        };
+
+        let template: Template = match Template::parse(&list_options.template()) {
+            Ok(template) => template,
+            Err(err) => {
+                self.log_error_detail(&format!("Invalid template: {err}"));
+                return Err("Could not parse the list template");
+            }
+        };
+
+        if let Some(location) = self.database.and_then(|database| database.location()) {
+            self.log_text(&location);
+        }
+        self.log_text(heading);
+
+        let todos: Vec<ToDo> = read_items(self.database.expect("Database could not be found"))?;
+
+        // Items keep the number of their position in the list, so whatever the
+        // order they are printed in, that number can be given to other actions
+        let mut matching_todos: Vec<(usize, &ToDo)> = todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| filter.matches(todo))
+            .collect();
+
+        list_options.sort(&mut matching_todos);
+
+        if self.output.is_json() {
+            let items: Vec<Json> = list_options
+                .group(matching_todos, Date::today())
+                .into_iter()
+                .flat_map(|(group, group_todos)| {
+                    group_todos.into_iter().map(move |(index, todo)| {
+                        let mut item = output::todo_to_json(index + 1, todo);
+                        if let (Json::Object(fields), Some(group)) = (&mut item, &group) {
+                            let key = group.key.as_deref().map_or(Json::Null, Json::string);
+                            fields.push(("group".into(), key));
+                        }
+                        item
+                    })
+                })
+                .collect();
+
+            return self.log_json(items, Json::Array);
+        }
+
+        let today = Date::today();
+        let theme = list_options.theme()?;
+
+        let table: Option<Table> = match list_options.format {
+            ListFormat::Table => {
+                let table = Table::new(
+                    &matching_todos,
+                    list_options.description,
+                    table::terminal_width(),
+                    theme.clone(),
+                );
+                self.logger
+                    .as_mut()
+                    .unwrap()
+                    .log_styled_stdln(&table.header_parts())
+                    .unwrap();
+                Some(table)
+            }
+            ListFormat::Long => None,
+        };
+
+        for (group, group_todos) in list_options.group(matching_todos, today) {
+            if let Some(group) = group {
+                let mut group_header = vec![(
+                    format!("## {} ({})", group.label(), group_todos.len()),
+                    theme.header,
+                )];
+                if table.is_none() {
+                    group_header.push(("\n".into(), Style::PLAIN));
+                }
+                self.logger
+                    .as_mut()
+                    .unwrap()
+                    .log_styled_stdln(&group_header)
+                    .unwrap();
+            }
+
+            for (index, todo) in group_todos {
+                let item = match &table {
+                    Some(table) => table.row_parts(index, todo, today),
+                    None => template.render(index, todo, &theme, today),
+                };
+                self.logger
+                    .as_mut()
+                    .unwrap()
+                    .log_styled_stdln(&item)
+                    .unwrap();
+            }
+        }
 
         Ok(())
     }
 
     fn create(&mut self) -> Result<(), &'static str> {
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&"Creating a ToDo item")
-            .unwrap();
+        self.log_text("Creating a ToDo item");
 
-        self.logger.as_mut().unwrap().log_std(&"Title: ").unwrap();
-        stdout().flush().unwrap();
-        let mut title = String::new();
-        io::stdin().read_line(&mut title).unwrap();
+        let title = self.prompt("Title: ");
+        let description = self.prompt("Description: ");
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_std(&"Description: ")
-            .unwrap();
-        stdout().flush().unwrap();
-        let mut description = String::new();
-        io::stdin().read_line(&mut description).unwrap();
+        let mut todo: ToDo = ToDo::new(title, description);
+        let now = date::now();
+        todo.created = Some(now);
+        todo.touch(now);
 
-        let todo: ToDo = ToDo::new(title, description);
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
 
-        self.database
-            .expect("Database could not be found")
-            .store_item(todo)?;
+        // The JSON result tells where the item ended up in the list
+        let created = output::todo_to_json(todos.len() + 1, &todo);
 
-        Ok(())
+        let mut after = todos.clone();
+        after.push(todo.clone());
+
+        database.store_item(todo)?;
+        self.record_change(todos, &after)?;
+
+        self.log_text("Item added to the database");
+        self.log_changes("create", vec![(None, Some(created))])
     }
 
     fn edit(&mut self) -> Result<(), &'static str> {
@@ -162,135 +517,449 @@ impl<'a> Action<'a> {
             .expect("Database could not be found")
             .read_items()?;
 
-        let mut edit_todo = match todos.get_mut(item_index) {
+        let original = todos.clone();
+
+        let edit_todo = match todos.get_mut(item_index) {
             Some(todo) => todo,
             None => return Err("Given ToDo Item index is wrong"),
         };
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&format!("Editing #{} ToDo item", item_index + 1))
-            .unwrap();
+        let before = output::todo_to_json(item_index + 1, edit_todo);
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&format!("{}", edit_todo))
-            .unwrap();
+        self.log_text(&format!("Editing #{} ToDo item", item_index + 1));
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_std(&"New Title: ")
-            .unwrap();
+        // Field assignments like `due:2026-11-01` are applied without prompting
+        let field_assignments = &self.arguments[1..];
 
-        stdout().flush().unwrap();
-        let mut new_title = String::new();
-        io::stdin().read_line(&mut new_title).unwrap();
+        if !field_assignments.is_empty() {
+            for assignment in field_assignments {
+                edit_todo.set_field(assignment)?;
+            }
+            edit_todo.touch(date::now());
+        } else {
+            let current = format!("{}", edit_todo);
+            self.log_text(&current);
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_std(&"New Description: ")
-            .unwrap();
-        stdout().flush().unwrap();
-        let mut new_description = String::new();
-        io::stdin().read_line(&mut new_description).unwrap();
+            let new_title = self.prompt("New Title: ");
+            let new_description = self.prompt("New Description: ");
 
-        if new_title.len() > 1 {
-            edit_todo.title = new_title;
-        }
+            let edit_todo = &mut todos[item_index];
+
+            if new_title.len() > 1 || new_description.len() > 1 {
+                edit_todo.touch(date::now());
+            }
+
+            if new_title.len() > 1 {
+                edit_todo.title = new_title;
+            }
 
-        if new_description.len() > 1 {
-            edit_todo.description = new_description;
+            if new_description.len() > 1 {
+                edit_todo.description = new_description;
+            }
         }
 
+        let after = output::todo_to_json(item_index + 1, &todos[item_index]);
+
         self.database
             .expect("Database could not be found")
-            .store_existing_items(todos)?;
+            .store_existing_items(todos.clone())?;
+        self.record_change(original, &todos)?;
 
-        Ok(())
+        self.log_changes("edit", vec![(Some(before), Some(after))])
     }
 
-    fn update_todo_status(&mut self, status: String) -> Result<(), &'static str> {
-        let item_index: usize = self.get_item_index_arg()?;
-
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&format!("Editing #{} ToDo item", item_index + 1))
-            .unwrap();
-
+    fn update_todo_status(
+        &mut self,
+        action_name: &str,
+        status: String,
+    ) -> Result<(), &'static str> {
         let mut todos: Vec<ToDo> = self
             .database
             .expect("Database could not be found")
             .read_items()?;
 
-        let mut edit_todo = match todos.get_mut(item_index) {
-            Some(todo) => todo,
-            None => return Err("Given ToDo Item index is wrong"),
-        };
+        let item_indexes: Vec<usize> =
+            self.get_item_indexes_arg(&todos, "Given ToDo Item index is wrong")?;
+
+        if item_indexes.is_empty() {
+            self.log_text("No ToDo items matched the given selection");
+            return self.log_changes(action_name, vec![]);
+        }
+
+        let original = todos.clone();
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
 
-        edit_todo.done = status;
+        for item_index in item_indexes {
+            self.log_text(&format!("Editing #{} ToDo item", item_index + 1));
+
+            let before = output::todo_to_json(item_index + 1, &todos[item_index]);
+            todos[item_index].done = status.clone();
+            todos[item_index].touch(date::now());
+            let after = output::todo_to_json(item_index + 1, &todos[item_index]);
+
+            changes.push((Some(before), Some(after)));
+        }
 
         self.database
             .expect("Database could not be found")
-            .store_existing_items(todos)?;
+            .store_existing_items(todos.clone())?;
+        self.record_change(original, &todos)?;
 
-        Ok(())
+        self.log_changes(action_name, changes)
     }
 
     fn done(&mut self) -> Result<(), &'static str> {
-        self.update_todo_status("✅".into())?;
+        self.update_todo_status("done", "✅".into())?;
         Ok(())
     }
 
     fn undone(&mut self) -> Result<(), &'static str> {
-        self.update_todo_status("❌".into())?;
+        self.update_todo_status("undone", "❌".into())?;
         Ok(())
     }
 
+    /// Moves the selected items to the trash, or removes them for good with
+    /// `--purge`.
     fn delete(&mut self) -> Result<(), &'static str> {
-        let item_index: usize = self.get_item_index_arg()?;
-
-        let mut todos: Vec<ToDo> = self
+        let todos: Vec<ToDo> = self
             .database
             .expect("Database could not be found")
             .read_items()?;
 
-        if item_index >= todos.len() {
-            return Err("Given item index is wrong");
+        let purge = self.arguments.iter().any(|arg| arg == "--purge");
+        self.arguments.retain(|arg| arg != "--purge");
+
+        let item_indexes: Vec<usize> =
+            self.get_item_indexes_arg(&todos, "Given item index is wrong")?;
+
+        if item_indexes.is_empty() {
+            self.log_text("No ToDo items matched the given selection");
+            return self.log_changes("delete", vec![]);
         }
 
-        self.logger
-            .as_mut()
-            .unwrap()
-            .log_stdln(&format!("Deleting #{} ToDo item", item_index + 1))
-            .unwrap();
+        if Confirm::configured()?.asks(item_indexes.len())
+            && !self.confirm(&format!(
+                "Delete {} ToDo item{}?",
+                item_indexes.len(),
+                if item_indexes.len() == 1 { "" } else { "s" }
+            ))
+        {
+            self.log_text("Nothing was deleted");
+            return self.log_changes("delete", vec![]);
+        }
+
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
 
-        todos.remove(item_index);
+        for item_index in item_indexes.iter() {
+            self.log_text(&format!("Deleting #{} ToDo item", item_index + 1));
+
+            let before = output::todo_to_json(item_index + 1, &todos[*item_index]);
+            changes.push((Some(before), None));
+        }
+
+        // Positions refer to the list as it was before the deletion, so the
+        // remaining items are picked in one pass instead of removing one by one.
+        let remaining_todos: Vec<ToDo> = todos
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| item_indexes.binary_search(index).is_err())
+            .map(|(_, todo)| todo.clone())
+            .collect();
+
+        // Into the trash before leaving the list, so nothing is lost if storing
+        // fails half way
+        if !purge {
+            self.trash_items(&todos, &item_indexes)?;
+        }
 
         self.database
             .expect("Database could not be found")
-            .store_existing_items(todos)?;
+            .store_existing_items(remaining_todos.clone())?;
 
-        Ok(())
+        // Journalled with the flag, so that redoing it does not trash the items
+        if purge {
+            self.arguments.push("--purge".into());
+        }
+        self.record_change(todos, &remaining_todos)?;
+
+        self.log_changes("delete", changes)
     }
 
-    fn get_item_index_arg(&self) -> Result<usize, &'static str> {
-        match self.arguments.get(0) {
-            Some(arg) => match arg.parse::<usize>() {
-                Ok(index) => {
-                    if index == 0 {
-                        return Err("Given argument should be a positive number");
+    fn config(&mut self) -> Result<(), &'static str> {
+        let arguments: Vec<String> = self.arguments.clone();
+        let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+        match arguments.as_slice() {
+            ["list"] => {
+                let entries = config::current().entries();
+
+                if !self.output.is_json() {
+                    for (key, value, source) in entries.iter() {
+                        self.log_text(&format!("{key} = {value} ({source})"));
                     }
-                    return Ok(index - 1);
                 }
-                Err(_) => return Err("Given argument should be a positive number"),
-            },
-            None => return Err("Could not get passed argument"),
+
+                let entries: Vec<Json> = entries
+                    .into_iter()
+                    .map(|(key, value, source)| setting_to_json(&key, value, source))
+                    .collect();
+                self.log_json(entries, Json::Array)
+            }
+            ["get", key] => {
+                if !config::is_known(key) {
+                    return Err("Unknown setting");
+                }
+
+                let (value, source) = match config::current().lookup(key) {
+                    Some(value_source) => value_source,
+                    None => return Err("Setting has no value"),
+                };
+
+                self.log_text(value);
+                self.log_json_value(setting_to_json(key, value, source))
+            }
+            ["set", key, value] | ["set", key, value, "--project"] | ["set", "--project", key, value] => {
+                config::validate(key, value)?;
+                if *key == "database.path" && arguments.contains(&"--project") {
+                    config::validate_project_path(value)?;
+                }
+
+                let path = match arguments.contains(&"--project") {
+                    true => config::project_config_path()
+                        .or_else(|| {
+                            env::current_dir()
+                                .ok()
+                                .map(|dir| dir.join(config::PROJECT_CONFIG_NAME))
+                        }),
+                    false => config::user_config_path(),
+                };
+                let path = match path {
+                    Some(path) => path,
+                    None => return Err("Could not find where to put the config file"),
+                };
+
+                config::set_in_file(&path, key, value)?;
+
+                self.log_text(&format!("Set {key} in {}", path.display()));
+                self.log_json_value(setting_to_json(key, value, &config::Source::File(path)))
+            }
+            _ => Err("Config should be used as config list, config get <key> or config set <key> <value> [--project]"),
+        }
+    }
+
+    /// Creates a project database in the current directory, which is then
+    /// used from there and every directory below it.
+    fn init(&mut self) -> Result<(), &'static str> {
+        let current_dir = match env::current_dir() {
+            Ok(current_dir) => current_dir,
+            Err(_) => return Err("Could not find the current directory"),
+        };
+
+        let path = database::init_project_database(&current_dir)?;
+
+        self.log_text(&format!("Created a project database at {}", path.display()));
+        self.log_json_value(Json::object(vec![
+            ("action", Json::string("init")),
+            ("path", Json::string(&path.display().to_string())),
+        ]))
+    }
+
+    /// Logs a human readable message, which is left out of JSON output.
+    fn log_text(&mut self, msg: &str) {
+        if !self.output.is_json() {
+            self.logger.as_mut().unwrap().log_stdln(msg).unwrap();
+        }
+    }
+
+    /// Logs details about an error to stderr, as `{"detail": ...}` with JSON
+    /// output.
+    fn log_error_detail(&mut self, msg: &str) {
+        let msg = match self.output.is_json() {
+            true => Json::object(vec![("detail", Json::string(msg))]).to_string(),
+            false => msg.to_string(),
         };
+        self.logger.as_mut().unwrap().log_errln(&msg).unwrap();
+    }
+
+    /// Logs the warnings the database gathered to stderr, as
+    /// `{"warning": ...}` with JSON output.
+    fn log_warnings(&mut self) {
+        let warnings = match self.database {
+            Some(database) => database.take_warnings(),
+            None => return,
+        };
+
+        for warning in warnings {
+            let msg = match self.output.is_json() {
+                true => Json::object(vec![("warning", Json::string(&warning))]).to_string(),
+                false => warning,
+            };
+            self.logger.as_mut().unwrap().log_errln(&msg).unwrap();
+        }
+    }
+
+    /// Asks for a line of input, after the warnings gathered so far. With JSON
+    /// output the prompt goes to stderr so that stdout only carries the result.
+    fn prompt(&mut self, prompt: &str) -> String {
+        self.log_warnings();
+        let logger = self.logger.as_mut().unwrap();
+
+        match self.output.is_json() {
+            true => logger.log_err(prompt).unwrap(),
+            false => logger.log_std(prompt).unwrap(),
+        }
+        stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        input
+    }
+
+    /// Asks a yes or no `question`, anything but yes counting as no.
+    fn confirm(&mut self, question: &str) -> bool {
+        let answer = self.prompt(&format!("{question} [y/N] "));
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    }
+
+    /// Prints JSON values as a single document made by `document`, or one
+    /// per line for NDJSON output.
+    fn log_json<F>(&mut self, values: Vec<Json>, document: F) -> Result<(), &'static str>
+    where
+        F: FnOnce(Vec<Json>) -> Json,
+    {
+        let logger = self.logger.as_mut().unwrap();
+
+        match self.output {
+            OutputMode::Text => {}
+            OutputMode::Json => logger.log_stdln(&document(values).to_string()).unwrap(),
+            OutputMode::Ndjson => {
+                for value in values {
+                    logger.log_stdln(&value.to_string()).unwrap();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints a single JSON value, the same way for JSON and NDJSON output.
+    fn log_json_value(&mut self, value: Json) -> Result<(), &'static str> {
+        self.log_json(vec![value], |mut values| values.remove(0))
+    }
+
+    /// Reports what a mutating action changed as `before`/`after` pairs of
+    /// items, `null` standing for an item that was created or deleted.
+    fn log_changes(
+        &mut self,
+        action_name: &str,
+        changes: Vec<(Option<Json>, Option<Json>)>,
+    ) -> Result<(), &'static str> {
+        let changes: Vec<Json> = changes
+            .into_iter()
+            .map(|(before, after)| {
+                Json::object(vec![
+                    ("before", before.unwrap_or(Json::Null)),
+                    ("after", after.unwrap_or(Json::Null)),
+                ])
+            })
+            .collect();
+
+        match self.output {
+            OutputMode::Ndjson => {
+                let changes: Vec<Json> = changes
+                    .into_iter()
+                    .map(|change| match change {
+                        Json::Object(mut fields) => {
+                            fields.insert(0, ("action".into(), Json::string(action_name)));
+                            Json::Object(fields)
+                        }
+                        change => change,
+                    })
+                    .collect();
+                self.log_json(changes, Json::Array)
+            }
+            _ => self.log_json(changes, |changes| {
+                Json::object(vec![
+                    ("action", Json::string(action_name)),
+                    ("changes", Json::Array(changes)),
+                ])
+            }),
+        }
+    }
+
+    fn get_item_index_arg(&self) -> Result<usize, &'static str> {
+        match self.arguments.first() {
+            Some(arg) => parse_item_position(arg),
+            None => Err("Could not get passed argument"),
+        }
+    }
+
+    /// Resolves every argument (positions, ranges like `2-5`, or the `all` and
+    /// `completed` selectors) against `todos` into sorted, de-duplicated indexes.
+    fn get_item_indexes_arg(
+        &self,
+        todos: &[ToDo],
+        out_of_range_err: &'static str,
+    ) -> Result<Vec<usize>, &'static str> {
+        if self.arguments.is_empty() {
+            return Err("Could not get passed argument");
+        }
+
+        let mut item_indexes: BTreeSet<usize> = BTreeSet::new();
+
+        for arg in self.arguments.iter() {
+            match arg.as_str() {
+                "all" => item_indexes.extend(0..todos.len()),
+                "completed" => item_indexes.extend(
+                    todos
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, todo)| todo.is_done())
+                        .map(|(index, _)| index),
+                ),
+                _ => match arg.split_once('-') {
+                    Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+                        let from = parse_item_position(from)?;
+                        let to = parse_item_position(to)?;
+
+                        if from > to {
+                            return Err("Given range should go from a lower to a higher number");
+                        }
+                        if to >= todos.len() {
+                            return Err(out_of_range_err);
+                        }
+
+                        item_indexes.extend(from..=to);
+                    }
+                    _ => {
+                        let index = parse_item_position(arg)?;
+
+                        if index >= todos.len() {
+                            return Err(out_of_range_err);
+                        }
+
+                        item_indexes.insert(index);
+                    }
+                },
+            }
+        }
+
+        Ok(item_indexes.into_iter().collect())
+    }
+}
+
+fn setting_to_json(key: &str, value: &str, source: &config::Source) -> Json {
+    Json::object(vec![
+        ("key", Json::string(key)),
+        ("value", Json::string(value)),
+        ("source", Json::string(&source.to_string())),
+    ])
+}
+
+fn parse_item_position(arg: &str) -> Result<usize, &'static str> {
+    match arg.parse::<usize>() {
+        Ok(0) | Err(_) => Err("Given argument should be a positive number"),
+        Ok(position) => Ok(position - 1),
     }
 }
 
@@ -309,41 +978,43 @@ mod tests {
     use super::*;
     use crate::log_wrapper::{LogWrapper, Logger};
     use std::{
+        cell::RefCell,
         error::Error,
-        io::{ErrorKind, Stderr, Stdout},
+        io::{Stderr, Stdout},
     };
+    use trash::Trashed;
 
     #[test]
     fn can_create_new_action_type() {
-        let action_type: ActionType = ActionType::new(&"create").unwrap();
+        let action_type: ActionType = ActionType::new("create").unwrap();
         assert_eq!(action_type, ActionType::Create(false));
     }
 
     #[test]
     fn should_return_error_if_action_type_is_wrong() {
-        let action_type: Result<ActionType, &'static str> = ActionType::new(&"wrong");
+        let action_type: Result<ActionType, &'static str> = ActionType::new("wrong");
         assert_eq!(action_type, Err("Action is not valid"));
     }
 
     #[test]
     fn should_return_correct_value_for_required_args() {
-        let action_type = ActionType::new(&"create").unwrap();
-        assert_eq!(action_type.requires_arguments(), false);
+        let action_type = ActionType::new("create").unwrap();
+        assert!(!action_type.requires_arguments());
 
-        let action_type = ActionType::new(&"list").unwrap();
-        assert_eq!(action_type.requires_arguments(), false);
+        let action_type = ActionType::new("list").unwrap();
+        assert!(!action_type.requires_arguments());
 
-        let action_type = ActionType::new(&"edit").unwrap();
-        assert_eq!(action_type.requires_arguments(), true);
+        let action_type = ActionType::new("edit").unwrap();
+        assert!(action_type.requires_arguments());
 
-        let action_type = ActionType::new(&"done").unwrap();
-        assert_eq!(action_type.requires_arguments(), true);
+        let action_type = ActionType::new("done").unwrap();
+        assert!(action_type.requires_arguments());
 
-        let action_type = ActionType::new(&"undone").unwrap();
-        assert_eq!(action_type.requires_arguments(), true);
+        let action_type = ActionType::new("undone").unwrap();
+        assert!(action_type.requires_arguments());
 
-        let action_type = ActionType::new(&"delete").unwrap();
-        assert_eq!(action_type.requires_arguments(), true);
+        let action_type = ActionType::new("delete").unwrap();
+        assert!(action_type.requires_arguments());
     }
 
     #[derive(PartialEq, Clone)]
@@ -352,20 +1023,20 @@ mod tests {
     }
 
     impl Logger for MockErrorLogger {
-        fn log_errln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn std::error::Error>> {
-            if msg == "" {
-                return Err(Box::new(io::Error::new(ErrorKind::Other, "oh no!")));
+        fn log_errln(&mut self, msg: &str) -> Result<(), Box<dyn std::error::Error>> {
+            if msg.is_empty() {
+                return Err(Box::new(io::Error::other("oh no!")));
             }
             self.was_called = true;
             Ok(())
         }
-        fn log_stdln<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_stdln(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
-        fn log_err<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_err(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
-        fn log_std<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_std(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
 
@@ -389,6 +1060,70 @@ mod tests {
         fn store_item(&self, _: ToDo) -> Result<(), &'static str> {
             todo!()
         }
+
+        fn read_trash(&self) -> Result<Vec<Trashed>, &'static str> {
+            Ok(vec![])
+        }
+
+        fn store_trash(&self, _: Vec<Trashed>) -> Result<(), &'static str> {
+            Ok(())
+        }
+    }
+
+    struct MockListDatabase {
+        todos: Vec<ToDo>,
+        stored: RefCell<Option<Vec<ToDo>>>,
+        warnings: RefCell<Vec<String>>,
+    }
+
+    impl MockListDatabase {
+        fn new(titles: &[&str]) -> Self {
+            MockListDatabase {
+                todos: titles
+                    .iter()
+                    .map(|title| ToDo::new(title.to_string(), "description".into()))
+                    .collect(),
+                stored: RefCell::new(None),
+                warnings: RefCell::new(vec![]),
+            }
+        }
+
+        fn stored_titles(&self) -> Vec<String> {
+            self.stored
+                .borrow()
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|todo| todo.title.clone())
+                .collect()
+        }
+    }
+
+    impl Database for MockListDatabase {
+        fn read_items(&self) -> Result<Vec<ToDo>, &'static str> {
+            Ok(self.todos.clone())
+        }
+
+        fn store_existing_items(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+            *self.stored.borrow_mut() = Some(todos);
+            Ok(())
+        }
+
+        fn store_item(&self, _: ToDo) -> Result<(), &'static str> {
+            todo!()
+        }
+
+        fn read_trash(&self) -> Result<Vec<Trashed>, &'static str> {
+            Ok(vec![])
+        }
+
+        fn store_trash(&self, _: Vec<Trashed>) -> Result<(), &'static str> {
+            Ok(())
+        }
+
+        fn take_warnings(&self) -> Vec<String> {
+            self.warnings.take()
+        }
     }
 
     #[test]
@@ -399,7 +1134,7 @@ mod tests {
             LogWrapper::new(io::stderr(), io::stdout());
         let database = MockDatabase {};
 
-        let action = Action::new(&"create", &mut mock_logger, &database);
+        let action = Action::new("create", &mut mock_logger, &database);
 
         let expected = Action {
             action_type: ActionType::Create(false),
@@ -407,6 +1142,7 @@ mod tests {
             arguments: vec![],
             logger: Some(&mut other_mock_logger),
             database: None,
+            output: OutputMode::Text,
         };
         assert_eq!(action, Ok(expected));
 
@@ -416,6 +1152,7 @@ mod tests {
             arguments: vec![],
             logger: Some(&mut log_wrapper),
             database: None,
+            output: OutputMode::Text,
         };
         assert_ne!(action, Ok(expected));
     }
@@ -426,7 +1163,7 @@ mod tests {
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("list".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
         let res = list_action.execute_action();
         assert_eq!(res, Ok(()));
         let logs = "Printing all ToDo items.
@@ -446,7 +1183,7 @@ Done: ❌
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("done".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("done", &mut logger, &database).unwrap();
         list_action.arguments = vec!["1".into()];
         let res = list_action.execute_action();
         assert_eq!(res, Ok(()));
@@ -460,7 +1197,7 @@ Done: ❌
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("undone".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("undone", &mut logger, &database).unwrap();
         list_action.arguments = vec!["1".into()];
         let res = list_action.execute_action();
         assert_eq!(res, Ok(()));
@@ -474,7 +1211,7 @@ Done: ❌
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("delete".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("delete", &mut logger, &database).unwrap();
         list_action.arguments = vec!["1".into()];
         let res = list_action.execute_action();
         assert_eq!(res, Ok(()));
@@ -488,7 +1225,7 @@ Done: ❌
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("delete".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("delete", &mut logger, &database).unwrap();
         list_action.arguments = vec!["-1".into()];
         let res = list_action.execute_action();
         assert_eq!(res, Err("Given argument should be a positive number"));
@@ -500,9 +1237,244 @@ Done: ❌
         let mock_logger_std: Vec<u8> = Vec::new();
         let mut logger = LogWrapper::new(mock_logger_err, mock_logger_std);
         let database = MockDatabase {};
-        let mut list_action = Action::new("delete".into(), &mut logger, &database).unwrap();
+        let mut list_action = Action::new("delete", &mut logger, &database).unwrap();
         list_action.arguments = vec!["100".into()];
         let res = list_action.execute_action();
         assert_eq!(res, Err("Given item index is wrong"));
     }
+
+    #[test]
+    fn should_mark_multiple_todos_and_ranges_as_done() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b", "c", "d", "e"]);
+        let mut done_action = Action::new("done", &mut logger, &database).unwrap();
+        done_action.arguments = vec!["4-5".into(), "1".into(), "5".into()];
+        let res = done_action.execute_action();
+        assert_eq!(res, Ok(()));
+
+        let stored = database.stored.borrow();
+        let statuses: Vec<&str> = stored
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|todo| todo.done.as_str())
+            .collect();
+        assert_eq!(statuses, vec!["✅", "❌", "❌", "✅", "✅"]);
+
+        let logs = "Editing #1 ToDo item\nEditing #4 ToDo item\nEditing #5 ToDo item\n";
+        assert_eq!(logger.std_writer, logs.as_bytes());
+    }
+
+    #[test]
+    fn should_delete_positions_as_they_were_before_the_operation() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b", "c", "d"]);
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["1".into(), "2".into(), "4".into()];
+        let res = delete_action.execute_action();
+        assert_eq!(res, Ok(()));
+        assert_eq!(database.stored_titles(), vec!["c"]);
+    }
+
+    #[test]
+    fn should_delete_completed_todos_only() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut database = MockListDatabase::new(&["a", "b", "c"]);
+        database.todos[1].done = "✅".into();
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["completed".into()];
+        let res = delete_action.execute_action();
+        assert_eq!(res, Ok(()));
+        assert_eq!(database.stored_titles(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn should_not_store_anything_if_one_of_the_targets_is_wrong() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b", "c"]);
+        let mut done_action = Action::new("done", &mut logger, &database).unwrap();
+        done_action.arguments = vec!["1".into(), "2-7".into()];
+        let res = done_action.execute_action();
+        assert_eq!(res, Err("Given ToDo Item index is wrong"));
+        assert!(database.stored.borrow().is_none());
+
+        let mut done_action = Action::new("done", &mut logger, &database).unwrap();
+        done_action.arguments = vec!["3-1".into()];
+        let res = done_action.execute_action();
+        assert_eq!(
+            res,
+            Err("Given range should go from a lower to a higher number")
+        );
+        assert!(database.stored.borrow().is_none());
+    }
+
+    #[test]
+    fn should_list_only_todos_matching_the_filter_with_their_original_numbers() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut database = MockListDatabase::new(&["fix login", "write docs", "login audit"]);
+        database.todos[2].done = "✅".into();
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.arguments = vec!["status:open".into(), "login".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = "Printing all ToDo items.
+===============
+# 1
+Title: fix login
+Description: description
+Done: ❌
+===============\n\n";
+        assert_eq!(logger.std_writer, logs.as_bytes());
+    }
+
+    #[test]
+    fn should_report_invalid_list_filters() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a"]);
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.arguments = vec!["colour:red".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Err("Could not parse the list filter"));
+        let logs = "Invalid filter: Unknown field 'colour' at position 1, fields are status, title, description, tag, project, priority and due\n";
+        assert_eq!(logger.err_writer, logs.as_bytes());
+    }
+
+    #[test]
+    fn should_edit_fields_without_prompting() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b"]);
+        let mut edit_action = Action::new("edit", &mut logger, &database).unwrap();
+        edit_action.arguments = vec!["2".into(), "due:2026-11-01".into(), "tags:bug".into()];
+        let res = edit_action.execute_action();
+        assert_eq!(res, Ok(()));
+
+        let stored = database.stored.borrow();
+        let edited = &stored.as_ref().unwrap()[1];
+        assert_eq!(edited.due.unwrap().to_string(), "2026-11-01");
+        assert_eq!(edited.tags, vec!["bug"]);
+    }
+
+    #[test]
+    fn should_print_group_headers_with_counts() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut database = MockListDatabase::new(&["a", "b"]);
+        database.todos[0].done = "✅".into();
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.arguments = vec!["--group-by".into(), "status".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = String::from_utf8(logger.std_writer.clone()).unwrap();
+        let headers: Vec<&str> = logs.lines().filter(|line| line.starts_with("##")).collect();
+        assert_eq!(headers, vec!["## Open (1)", "## Done (1)"]);
+        assert!(logs.find("# 2").unwrap() < logs.find("# 1").unwrap());
+    }
+
+    #[test]
+    fn should_list_todos_as_a_table() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut database = MockListDatabase::new(&["fix login", "docs"]);
+        database.todos[1].done = "✅".into();
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.arguments = vec!["--format".into(), "table".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = "Printing all ToDo items.
+#  Done  Title
+1  ❌    fix login
+2  ✅    docs\n";
+        assert_eq!(logger.std_writer, logs.as_bytes());
+    }
+
+    #[test]
+    fn should_list_todos_as_json_and_ndjson() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b"]);
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.output = OutputMode::Json;
+        list_action.arguments = vec!["b".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = r#"[{"number":2,"title":"b","description":"description","done":false,"priority":null,"due":null,"tags":[],"project":null,"created":null,"updated":null}]
+"#;
+        assert_eq!(String::from_utf8(logger.std_writer).unwrap(), logs);
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.output = OutputMode::Ndjson;
+        let res = list_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = String::from_utf8(logger.std_writer).unwrap();
+        let lines: Vec<&str> = logs.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"number":1,"title":"a""#));
+    }
+
+    #[test]
+    fn should_describe_what_changed_as_json() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a", "b"]);
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.output = OutputMode::Json;
+        delete_action.arguments = vec!["2".into()];
+        let res = delete_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = r#"{"action":"delete","changes":[{"before":{"number":2,"title":"b","description":"description","done":false,"priority":null,"due":null,"tags":[],"project":null,"created":null,"updated":null},"after":null}]}
+"#;
+        assert_eq!(String::from_utf8(logger.std_writer).unwrap(), logs);
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut done_action = Action::new("done", &mut logger, &database).unwrap();
+        done_action.output = OutputMode::Ndjson;
+        done_action.arguments = vec!["1".into()];
+        let res = done_action.execute_action();
+        assert_eq!(res, Ok(()));
+        let logs = String::from_utf8(logger.std_writer).unwrap();
+        assert!(logs.starts_with(r#"{"action":"done","before":{"number":1"#));
+        assert!(logs.contains(
+            r#""after":{"number":1,"title":"a","description":"description","done":true"#
+        ));
+        assert!(logger.err_writer.is_empty());
+    }
+
+    #[test]
+    fn should_report_error_details_as_json() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a"]);
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.output = OutputMode::Json;
+        list_action.arguments = vec!["(".into()];
+        let res = list_action.execute_action();
+        assert_eq!(res, Err("Could not parse the list filter"));
+        let logs =
+            "{\"detail\":\"Invalid filter: Expected a condition at the end of the filter\"}\n";
+        assert_eq!(String::from_utf8(logger.err_writer).unwrap(), logs);
+        assert!(logger.std_writer.is_empty());
+    }
+
+    #[test]
+    fn should_log_database_warnings_to_stderr() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = MockListDatabase::new(&["a"]);
+        database
+            .warnings
+            .borrow_mut()
+            .push("Skipping line 2".into());
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        assert_eq!(list_action.execute_action(), Ok(()));
+        assert_eq!(logger.err_writer, b"Skipping line 2\n");
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        database
+            .warnings
+            .borrow_mut()
+            .push("Skipping line 2".into());
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        list_action.output = OutputMode::Json;
+        assert_eq!(list_action.execute_action(), Ok(()));
+        assert_eq!(
+            String::from_utf8(logger.err_writer).unwrap(),
+            "{\"warning\":\"Skipping line 2\"}\n"
+        );
+        assert!(database.warnings.borrow().is_empty());
+    }
 }
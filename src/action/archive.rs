@@ -0,0 +1,385 @@
+use crate::{
+    config, date,
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::{database::Database, journal::ItemChange, Action};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// After how many days completed items are archived when listing, from the
+/// `archive.after_days` setting. Nothing is archived automatically without it.
+pub fn configured_after_days() -> Result<Option<u64>, &'static str> {
+    match config::current().get("archive.after_days") {
+        Some(days) => match days.parse::<u64>() {
+            Ok(days) => Ok(Some(days)),
+            Err(_) => Err("archive.after_days should be a number of days"),
+        },
+        None => Ok(None),
+    }
+}
+
+impl Action<'_> {
+    /// `archive [index...]` moves completed items out of the list, all of
+    /// them when none are given, while `archive list` and `archive search`
+    /// print what was archived.
+    pub(super) fn archive(&mut self) -> Result<(), &'static str> {
+        let arguments = self.arguments.clone();
+
+        match arguments.split_first() {
+            Some((view, rest)) if view == "list" => self.print_archive(rest),
+            Some((view, rest)) if view == "search" => {
+                if rest.is_empty() {
+                    return Err("archive search needs something to search for");
+                }
+                self.print_archive(rest)
+            }
+            _ => {
+                let database = self.database.expect("Database could not be found");
+                let todos: Vec<ToDo> = database.read_items()?;
+
+                if self.arguments.is_empty() {
+                    self.arguments = vec!["completed".into()];
+                }
+                let item_indexes: Vec<usize> =
+                    self.get_item_indexes_arg(&todos, "Given item index is wrong")?;
+
+                if item_indexes.iter().any(|index| !todos[*index].is_done()) {
+                    return Err("Only completed items can be archived");
+                }
+
+                self.archive_items(todos, item_indexes)
+            }
+        }
+    }
+
+    /// Puts archived items back at the end of the list.
+    pub(super) fn unarchive(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let archived: Vec<ToDo> = database.read_archive()?;
+        let mut todos: Vec<ToDo> = database.read_items()?;
+        let original = todos.clone();
+
+        let item_indexes: Vec<usize> =
+            self.get_item_indexes_arg(&archived, "Given archived item index is wrong")?;
+
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for item_index in item_indexes.iter() {
+            self.log_text(&format!("Restoring #{} archived ToDo item", item_index + 1));
+
+            let mut todo = archived[*item_index].clone();
+            todo.touch(date::now());
+            todos.push(todo);
+
+            changes.push((
+                Some(output::todo_to_json(item_index + 1, &archived[*item_index])),
+                Some(output::todo_to_json(todos.len(), todos.last().unwrap())),
+            ));
+        }
+
+        let archived: Vec<ToDo> = without(archived, &item_indexes);
+
+        // Back in the list before leaving the archive, so nothing is lost if
+        // storing fails half way
+        database.store_existing_items(todos.clone())?;
+        database.store_archive(archived)?;
+        self.record_change(original, &todos)?;
+
+        self.log_changes("unarchive", changes)
+    }
+
+    /// Archives the completed items that were done more than
+    /// `archive.after_days` days ago, judging by when they were last updated.
+    pub(super) fn archive_old_items(&mut self) -> Result<(), &'static str> {
+        match configured_after_days()? {
+            Some(after_days) => self.archive_items_done_before(after_days),
+            None => Ok(()),
+        }
+    }
+
+    fn archive_items_done_before(&mut self, after_days: u64) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let now = date::now();
+
+        let item_indexes: Vec<usize> = todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| {
+                todo.is_done()
+                    && todo.updated.is_some_and(|updated| {
+                        now.saturating_sub(updated) / SECONDS_PER_DAY >= after_days
+                    })
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        if item_indexes.is_empty() {
+            return Ok(());
+        }
+
+        // Listing does not write over a list changed outside of
+        // terminal-todo unless told to
+        if self.confirm_unverified_list().is_err() {
+            self.log_text("Nothing was archived, the list could not be verified");
+            return Ok(());
+        }
+
+        let item_count = item_indexes.len();
+
+        let mut archived: Vec<ToDo> = database.read_archive()?;
+        for index in item_indexes.iter() {
+            add_to_archive(&mut archived, &todos[*index]);
+        }
+        let remaining = without(todos.clone(), &item_indexes);
+        store_archived(database, archived, remaining.clone())?;
+        self.record_change_as(
+            format!("archive done more than {after_days} days ago"),
+            todos,
+            &remaining,
+        )?;
+
+        self.log_text(&format!(
+            "Archived {item_count} ToDo item{} done more than {after_days} days ago",
+            if item_count == 1 { "" } else { "s" }
+        ));
+
+        Ok(())
+    }
+
+    fn archive_items(
+        &mut self,
+        todos: Vec<ToDo>,
+        item_indexes: Vec<usize>,
+    ) -> Result<(), &'static str> {
+        if item_indexes.is_empty() {
+            self.log_text("No completed ToDo items to archive");
+            return self.log_changes("archive", vec![]);
+        }
+
+        let database = self.database.expect("Database could not be found");
+        let mut archived: Vec<ToDo> = database.read_archive()?;
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for item_index in item_indexes.iter() {
+            self.log_text(&format!("Archiving #{} ToDo item", item_index + 1));
+
+            let position = add_to_archive(&mut archived, &todos[*item_index]);
+
+            changes.push((
+                Some(output::todo_to_json(item_index + 1, &todos[*item_index])),
+                Some(output::todo_to_json(position + 1, &archived[position])),
+            ));
+        }
+
+        let remaining = without(todos.clone(), &item_indexes);
+        store_archived(database, archived, remaining.clone())?;
+        self.record_change(todos, &remaining)?;
+
+        self.log_changes("archive", changes)
+    }
+
+    /// Moves the items an undone or redone change took out of the list into
+    /// the archive, and those it brings back out of it.
+    pub(super) fn replay_archive(&mut self, changes: &[ItemChange]) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let mut archived: Vec<ToDo> = database.read_archive()?;
+
+        for change in changes {
+            match change {
+                (Some((_, todo)), None) => {
+                    add_to_archive(&mut archived, todo);
+                }
+                (None, Some((_, todo))) => {
+                    let serialised = todo.serialise();
+                    if let Some(position) = archived
+                        .iter()
+                        .rposition(|other| other.serialise() == serialised)
+                    {
+                        archived.remove(position);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        database.store_archive(archived)
+    }
+
+    fn print_archive(&mut self, arguments: &[String]) -> Result<(), &'static str> {
+        self.print_items(arguments, "Printing archived ToDo items.", |database| {
+            database.read_archive()
+        })
+    }
+}
+
+/// Adds `todo` to the archive unless it is there already, left in the list
+/// by an archiving that stored the archive but not the list, giving its
+/// position in the archive.
+fn add_to_archive(archived: &mut Vec<ToDo>, todo: &ToDo) -> usize {
+    let serialised = todo.serialise();
+
+    match archived
+        .iter()
+        .position(|other| other.serialise() == serialised)
+    {
+        Some(position) => position,
+        None => {
+            archived.push(todo.clone());
+            archived.len() - 1
+        }
+    }
+}
+
+/// Stores the archive before the list, so that nothing is lost if storing
+/// fails half way.
+fn store_archived(
+    database: &dyn Database,
+    archived: Vec<ToDo>,
+    todos: Vec<ToDo>,
+) -> Result<(), &'static str> {
+    database.store_archive(archived)?;
+    database.store_existing_items(todos)
+}
+
+/// The items left once those at the sorted `item_indexes` are taken out.
+fn without(todos: Vec<ToDo>, item_indexes: &[usize]) -> Vec<ToDo> {
+    todos
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| item_indexes.binary_search(index).is_err())
+        .map(|(_, todo)| todo)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::test_database::{todo, TestDatabase},
+        log_wrapper::LogWrapper,
+    };
+
+    #[test]
+    fn should_archive_completed_items_and_restore_them() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database =
+            TestDatabase::with_items(vec![todo("a", true), todo("b", false), todo("c", true)]);
+
+        let mut archive_action = Action::new("archive", &mut logger, &database).unwrap();
+        assert_eq!(archive_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+        assert_eq!(database.archived_titles(), vec!["a", "c"]);
+
+        let mut unarchive_action = Action::new("unarchive", &mut logger, &database).unwrap();
+        unarchive_action.arguments = vec!["2".into()];
+        assert_eq!(unarchive_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b", "c"]);
+        assert_eq!(database.archived_titles(), vec!["a"]);
+        assert_eq!(
+            logger.std_writer,
+            "Archiving #1 ToDo item\nArchiving #3 ToDo item\nRestoring #2 archived ToDo item\n"
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn should_only_archive_completed_items() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::with_items(vec![todo("a", true), todo("b", false)]);
+
+        let mut archive_action = Action::new("archive", &mut logger, &database).unwrap();
+        archive_action.arguments = vec!["1-2".into()];
+        assert_eq!(
+            archive_action.execute_action(),
+            Err("Only completed items can be archived")
+        );
+        assert_eq!(database.titles(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn should_archive_old_items_once() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut old = todo("a", true);
+        old.touch(date::now() - 40 * SECONDS_PER_DAY);
+        let mut recent = todo("b", true);
+        recent.touch(date::now());
+        let database = TestDatabase::with_items(vec![old.clone(), recent, todo("c", false)]);
+        // Archived by a run that did not get to store the list
+        *database.archived.borrow_mut() = vec![old];
+
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        assert_eq!(list_action.archive_items_done_before(30), Ok(()));
+        assert_eq!(database.titles(), vec!["b", "c"]);
+        assert_eq!(database.archived_titles(), vec!["a"]);
+        assert_eq!(
+            logger.std_writer,
+            "Archived 1 ToDo item done more than 30 days ago\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn should_undo_and_redo_archiving() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database =
+            TestDatabase::with_items(vec![todo("a", true), todo("b", false), todo("c", true)]);
+
+        let mut archive_action = Action::new("archive", &mut logger, &database).unwrap();
+        assert_eq!(archive_action.execute_action(), Ok(()));
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["a", "b", "c"]);
+        assert!(database.archived_titles().is_empty());
+
+        let mut redo_action = Action::new("redo", &mut logger, &database).unwrap();
+        assert_eq!(redo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+        assert_eq!(database.archived_titles(), vec!["a", "c"]);
+
+        let mut unarchive_action = Action::new("unarchive", &mut logger, &database).unwrap();
+        unarchive_action.arguments = vec!["1".into()];
+        assert_eq!(unarchive_action.execute_action(), Ok(()));
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+        assert_eq!(database.archived_titles(), vec!["c", "a"]);
+
+        // Archiving old items while listing can be undone too
+        let mut old = todo("d", true);
+        old.touch(date::now() - 40 * SECONDS_PER_DAY);
+        database.todos.borrow_mut().push(old);
+        let mut list_action = Action::new("list", &mut logger, &database).unwrap();
+        assert_eq!(list_action.archive_items_done_before(30), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b", "d"]);
+        assert_eq!(database.archived_titles(), vec!["c", "a"]);
+        assert!(String::from_utf8(logger.std_writer)
+            .unwrap()
+            .starts_with("Undoing `archive done more than 30 days ago` from "));
+    }
+
+    #[test]
+    fn should_search_the_archive() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::default();
+        *database.archived.borrow_mut() = vec![
+            ToDo::new("login page".into(), "description".into()),
+            ToDo::new("logout".into(), "description".into()),
+        ];
+
+        let mut archive_action = Action::new("archive", &mut logger, &database).unwrap();
+        archive_action.arguments = vec!["search".into(), "login".into()];
+        assert_eq!(archive_action.execute_action(), Ok(()));
+
+        let output = String::from_utf8(logger.std_writer).unwrap();
+        assert!(output.starts_with("Printing archived ToDo items.\n"));
+        assert!(output.contains("login page"));
+        assert!(!output.contains("logout"));
+    }
+}
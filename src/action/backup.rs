@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    config,
+    date::{self, Date},
+    output::Json,
+    todo::ToDo,
+};
+
+use super::{Action, Confirm};
+
+/// How many of the latest backups are kept and for how many days the last
+/// backup of the day is kept on top of those, from the `backup.keep` and
+/// `backup.daily` settings.
+pub fn configured_retention() -> Result<(usize, usize), &'static str> {
+    let setting = |key: &str, default: &str| {
+        config::current()
+            .get(key)
+            .unwrap_or(default)
+            .parse::<usize>()
+            .map_err(|_| "backup.keep and backup.daily should be numbers")
+    };
+
+    Ok((setting("backup.keep", "10")?, setting("backup.daily", "7")?))
+}
+
+/// The backups, given by their timestamps, that the retention policy lets go:
+/// all but the `keep` latest ones and the last one of each of the `daily`
+/// latest days.
+pub fn expired_backups(timestamps: &[u64], keep: usize, daily: usize) -> Vec<u64> {
+    let mut newest_first: Vec<u64> = timestamps.to_vec();
+    newest_first.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut kept: BTreeSet<u64> = newest_first.iter().take(keep).copied().collect();
+    let mut days: Vec<Date> = vec![];
+
+    for timestamp in newest_first.iter() {
+        let day = Date::from_timestamp(*timestamp);
+
+        if !days.contains(&day) {
+            if days.len() == daily {
+                break;
+            }
+            days.push(day);
+            kept.insert(*timestamp);
+        }
+    }
+
+    newest_first
+        .into_iter()
+        .filter(|timestamp| !kept.contains(timestamp))
+        .collect()
+}
+
+impl Action<'_> {
+    /// `backup list` shows the copies kept of the list in use and
+    /// `backup restore <timestamp>` puts one of them back.
+    pub(super) fn backup(&mut self) -> Result<(), &'static str> {
+        let arguments = self.arguments.clone();
+        let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+        match arguments.as_slice() {
+            ["list"] => self.backup_list(),
+            ["restore", timestamp] => match timestamp.parse::<u64>() {
+                Ok(timestamp) => self.backup_restore(timestamp),
+                Err(_) => Err("Backup timestamp should be one of those given by backup list"),
+            },
+            _ => Err("Backup should be used as backup list or backup restore <timestamp>"),
+        }
+    }
+
+    fn backup_list(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let timestamps: Vec<u64> = database.backups()?;
+
+        if timestamps.is_empty() {
+            self.log_text("There are no backups yet");
+        }
+
+        let mut backups: Vec<Json> = vec![];
+
+        for timestamp in timestamps {
+            let item_count = database.read_backup(timestamp)?.len();
+            let date = Date::from_timestamp(date::local(timestamp));
+
+            self.log_text(&format!(
+                "{timestamp}  {date}  {item_count} item{}",
+                if item_count == 1 { "" } else { "s" }
+            ));
+
+            backups.push(Json::object(vec![
+                ("timestamp", Json::Number(timestamp as i64)),
+                ("date", Json::string(&date.to_string())),
+                ("items", Json::Number(item_count as i64)),
+            ]));
+        }
+
+        self.log_json(backups, Json::Array)
+    }
+
+    /// Shows what putting the backup back would change to the list, then
+    /// does it once confirmed. The list as it was is backed up in turn, so a
+    /// restore can be undone like any other change.
+    fn backup_restore(&mut self, timestamp: u64) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+
+        if !database.backups()?.contains(&timestamp) {
+            return Err("There is no backup with that timestamp, see backup list");
+        }
+
+        let todos: Vec<ToDo> = database.read_items()?;
+        let backup: Vec<ToDo> = database.read_backup(timestamp)?;
+
+        self.log_text(&format!(
+            "Restoring the backup from {}",
+            Date::from_timestamp(date::local(timestamp))
+        ));
+
+        let changes = self.log_diff(&todos, &backup);
+
+        if changes.is_empty() {
+            self.log_text("The list is the same as the backup");
+            return self.log_changes("backup-restore", vec![]);
+        }
+
+        if Confirm::configured()?.asks(changes.len())
+            && !self.confirm(&format!(
+                "Restore the backup, changing {} ToDo item{}?",
+                changes.len(),
+                if changes.len() == 1 { "" } else { "s" }
+            ))
+        {
+            self.log_text("Nothing was restored");
+            return self.log_changes("backup-restore", vec![]);
+        }
+
+        database.store_existing_items(backup.clone())?;
+        self.record_change(todos, &backup)?;
+
+        self.log_changes("backup-restore", changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn should_keep_the_latest_backups_and_one_per_day() {
+        // Three backups a day over four days
+        let timestamps: Vec<u64> = (0..4)
+            .flat_map(|day| (0..3).map(move |hour| 1_700_006_400 + day * DAY + hour * 3600))
+            .collect();
+
+        let mut expired = expired_backups(&timestamps, 2, 3);
+        expired.sort_unstable();
+
+        // The two latest are kept, with the last of the three latest days
+        let mut expected: Vec<u64> = timestamps.clone();
+        expected.retain(|timestamp| {
+            ![timestamps[11], timestamps[10], timestamps[8], timestamps[5]].contains(timestamp)
+        });
+        assert_eq!(expired, expected);
+
+        assert_eq!(expired_backups(&timestamps, 20, 0), vec![]);
+        assert_eq!(expired_backups(&timestamps, 0, 0).len(), 12);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{date::Date, todo::ToDo};
+
+use super::import::{Imported, Unmapped};
+
+/// The columns written for each item, which are also those read back.
+const COLUMNS: [&str; 9] = [
+    "title",
+    "description",
+    "done",
+    "priority",
+    "due",
+    "tags",
+    "project",
+    "created",
+    "updated",
+];
+
+/// What values spreadsheets run as formulas start with.
+const FORMULA_STARTS: [char; 4] = ['=', '+', '-', '@'];
+
+/// Whether a value starts like a formula, after the `'` written before one.
+fn is_escaped_formula(value: &str) -> bool {
+    value
+        .strip_prefix('\'')
+        .is_some_and(|formula| formula.starts_with(FORMULA_STARTS) || is_escaped_formula(formula))
+}
+
+/// Puts a `'` before values that spreadsheets would run as formulas, and
+/// before those already escaped that way, so reading them back with
+/// `unescape_formula` gives the value as it was.
+fn escape_formula(value: &str) -> String {
+    match value.starts_with(FORMULA_STARTS) || is_escaped_formula(value) {
+        true => format!("'{value}"),
+        false => value.to_string(),
+    }
+}
+
+fn unescape_formula(value: &str) -> &str {
+    match is_escaped_formula(value) {
+        true => &value[1..],
+        false => value,
+    }
+}
+
+/// Quotes a value when it holds a separator, a quote or a line break.
+fn quote(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// Writes items as CSV with a header row, tags separated by spaces and the
+/// created and updated times as unix timestamps. Values that start like a
+/// formula get a `'` before them.
+pub fn serialise(todos: &[ToDo]) -> String {
+    let mut csv = format!("{}\n", COLUMNS.join(","));
+
+    for todo in todos {
+        let values: [String; 9] = [
+            todo.title.clone(),
+            todo.description.clone(),
+            todo.is_done().to_string(),
+            todo.priority.map(String::from).unwrap_or_default(),
+            todo.due.map(|due| due.to_string()).unwrap_or_default(),
+            todo.tags.join(" "),
+            todo.project.clone().unwrap_or_default(),
+            todo.created
+                .map(|created| created.to_string())
+                .unwrap_or_default(),
+            todo.updated
+                .map(|updated| updated.to_string())
+                .unwrap_or_default(),
+        ];
+
+        let row: Vec<String> = values
+            .iter()
+            .map(|value| quote(&escape_formula(value)))
+            .collect();
+        csv += &format!("{}\n", row.join(","));
+    }
+
+    csv
+}
+
+/// Splits CSV into records, each with the line it starts on. Quoted values
+/// can hold separators, doubled quotes and line breaks.
+fn records(csv: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records: Vec<(usize, Vec<String>)> = vec![];
+    let mut record: Vec<String> = vec![];
+    let mut value = String::new();
+    let (mut line, mut start) = (1, 1);
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if value.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut value)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut value));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            ('\n', true) => {
+                value.push(c);
+                line += 1;
+            }
+            _ => value.push(c),
+        }
+    }
+
+    if !value.is_empty() || !record.is_empty() {
+        record.push(value);
+        records.push((start, record));
+    }
+
+    records
+        .into_iter()
+        .filter(|(_, record)| record.iter().any(|value| !value.is_empty()))
+        .collect()
+}
+
+/// Reads CSV with a header row naming the columns written by `serialise`, in
+/// any order. Values that cannot be read are left out of the item, and the
+/// `'` written before formulas is taken off.
+pub fn parse(csv: &str) -> Imported {
+    let mut records = records(csv).into_iter();
+    let mut todos: Vec<(usize, ToDo)> = vec![];
+    let mut unmapped: Vec<Unmapped> = vec![];
+
+    let header: Vec<String> = match records.next() {
+        Some((_, header)) => header
+            .iter()
+            .map(|column| column.trim().to_lowercase())
+            .collect(),
+        None => return (todos, unmapped),
+    };
+
+    for column in header.iter() {
+        if !COLUMNS.contains(&column.as_str()) {
+            unmapped.push(Unmapped {
+                line: 1,
+                text: column.clone(),
+                reason: "there is no such field",
+            });
+        }
+    }
+
+    for (line, record) in records {
+        let mut todo = ToDo::new(String::new(), String::new());
+
+        for (column, value) in header.iter().zip(record.iter()) {
+            let value = unescape_formula(value.trim());
+            if value.is_empty() {
+                continue;
+            }
+
+            let valid = match column.as_str() {
+                "title" => {
+                    todo.title = value.into();
+                    true
+                }
+                "description" => {
+                    todo.description = value.into();
+                    true
+                }
+                "done" => match value.to_lowercase().as_str() {
+                    "true" | "yes" | "x" | "1" => {
+                        todo.done = "✅".into();
+                        true
+                    }
+                    "false" | "no" | "0" => true,
+                    _ => false,
+                },
+                "priority" => todo.set_field(&format!("priority:{value}")).is_ok(),
+                "due" => Date::parse(value).map(|due| todo.due = Some(due)).is_ok(),
+                "tags" => {
+                    todo.tags = value.split_whitespace().map(String::from).collect();
+                    true
+                }
+                "project" => {
+                    todo.project = Some(value.into());
+                    true
+                }
+                "created" => value
+                    .parse()
+                    .map(|created| todo.created = Some(created))
+                    .is_ok(),
+                "updated" => value
+                    .parse()
+                    .map(|updated| todo.updated = Some(updated))
+                    .is_ok(),
+                _ => true,
+            };
+
+            if !valid {
+                unmapped.push(Unmapped {
+                    line,
+                    text: format!("{column}:{value}"),
+                    reason: "the value is not valid",
+                });
+            }
+        }
+
+        match todo.title.is_empty() {
+            true => unmapped.push(Unmapped {
+                line,
+                text: record.join(","),
+                reason: "there is no text for the title",
+            }),
+            false => todos.push((line, todo)),
+        }
+    }
+
+    (todos, unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_back_what_it_writes() {
+        let mut first = ToDo::new("Fix login, again".into(), "He said \"soon\"".into());
+        first.priority = Some('A');
+        first.due = Some(Date::new(2026, 10, 1).unwrap());
+        first.tags = vec!["bug".into(), "ui".into()];
+        first.project = Some("web".into());
+        first.created = Some(1_788_220_800);
+        first.updated = Some(1_788_224_400);
+
+        let mut second = ToDo::new("Pay rent".into(), String::new());
+        second.done = "✅".into();
+
+        let csv = serialise(&[first.clone(), second.clone()]);
+        assert_eq!(
+            csv,
+            "title,description,done,priority,due,tags,project,created,updated\n\
+             \"Fix login, again\",\"He said \"\"soon\"\"\",false,A,2026-10-01,bug ui,web,1788220800,1788224400\n\
+             Pay rent,,true,,,,,,\n"
+        );
+
+        let (todos, unmapped) = parse(&csv);
+        assert_eq!(todos, vec![(2, first), (3, second)]);
+        assert_eq!(unmapped, vec![]);
+    }
+
+    #[test]
+    fn should_report_what_it_cannot_read() {
+        let (todos, unmapped) = parse("Title,Owner,Due\n\"Multi\nline\",Sam,soon\n,Alex,\n");
+
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].1.title, "Multi\nline");
+
+        let reasons: Vec<(usize, &str, &str)> = unmapped
+            .iter()
+            .map(|unmapped| (unmapped.line, unmapped.text.as_str(), unmapped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, "owner", "there is no such field"),
+                (2, "due:soon", "the value is not valid"),
+                (4, ",Alex,", "there is no text for the title")
+            ]
+        );
+    }
+
+    #[test]
+    fn should_not_write_formulas() {
+        let first = ToDo::new(
+            "=HYPERLINK(\"http://example.com\")".into(),
+            "-5 degrees".into(),
+        );
+        let mut second = ToDo::new("'@home".into(), "+1 from Sam".into());
+        second.project = Some("@work".into());
+        let third = ToDo::new("''=SUM(A1)".into(), "it's fine".into());
+
+        let csv = serialise(&[first.clone(), second.clone(), third.clone()]);
+        assert_eq!(
+            csv,
+            "title,description,done,priority,due,tags,project,created,updated\n\
+             \"'=HYPERLINK(\"\"http://example.com\"\")\",'-5 degrees,false,,,,,,\n\
+             ''@home,'+1 from Sam,false,,,,'@work,,\n\
+             '''=SUM(A1),it's fine,false,,,,,,\n"
+        );
+
+        let (todos, unmapped) = parse(&csv);
+        assert_eq!(todos, vec![(2, first), (3, second), (4, third)]);
+        assert_eq!(unmapped, vec![]);
+    }
+}
@@ -1,124 +1,1776 @@
 use std::{
     any::Any,
+    cell::RefCell,
     env,
-    fs::{DirBuilder, File},
-    io::{Read, Write},
+    fs::{self, DirBuilder, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
     vec,
 };
 
-use crate::todo::ToDo;
+use crate::{
+    config::{self, Source},
+    date, paths,
+    todo::ToDo,
+};
+
+use super::{
+    backup, encryption,
+    events::{self, Record},
+    journal::Journal,
+    trash::Trashed,
+};
 
+/// Storage of the items, where `read_items`, `store_existing_items` and
+/// `store_item` work on the list in use. Databases that keep more than one
+/// list also implement the methods working on lists by name.
 pub trait Database: Any {
     fn read_items(&self) -> Result<Vec<ToDo>, &'static str>;
     fn store_existing_items(&self, todos: Vec<ToDo>) -> Result<(), &'static str>;
     fn store_item(&self, todo: ToDo) -> Result<(), &'static str>;
+
+    /// Describes which database is in use, for databases that can be found
+    /// in more than one place.
+    fn location(&self) -> Option<String> {
+        None
+    }
+
+    fn list_names(&self) -> Result<Vec<String>, &'static str> {
+        Err("Database does not support named lists")
+    }
+    fn create_list(&self, _name: &str) -> Result<(), &'static str> {
+        Err("Database does not support named lists")
+    }
+    fn rename_list(&self, _name: &str, _new_name: &str) -> Result<(), &'static str> {
+        Err("Database does not support named lists")
+    }
+    fn delete_list(&self, _name: &str) -> Result<(), &'static str> {
+        Err("Database does not support named lists")
+    }
+    fn read_list(&self, _name: &str) -> Result<Vec<ToDo>, &'static str> {
+        Err("Database does not support named lists")
+    }
+    fn store_list(&self, _name: &str, _todos: Vec<ToDo>) -> Result<(), &'static str> {
+        Err("Database does not support named lists")
+    }
+
+    /// The completed items taken out of the list in use with `archive`.
+    fn read_archive(&self) -> Result<Vec<ToDo>, &'static str> {
+        Err("Database does not support an archive")
+    }
+    fn store_archive(&self, _todos: Vec<ToDo>) -> Result<(), &'static str> {
+        Err("Database does not support an archive")
+    }
+
+    /// The items deleted from the list in use, until the trash is emptied.
+    fn read_trash(&self) -> Result<Vec<Trashed>, &'static str> {
+        Err("Database does not support a trash")
+    }
+    fn store_trash(&self, _trash: Vec<Trashed>) -> Result<(), &'static str> {
+        Err("Database does not support a trash")
+    }
+
+    /// The timestamps of the copies kept of the list in use, oldest first.
+    fn backups(&self) -> Result<Vec<u64>, &'static str> {
+        Err("Database does not keep backups")
+    }
+    fn read_backup(&self, _timestamp: u64) -> Result<Vec<ToDo>, &'static str> {
+        Err("Database does not keep backups")
+    }
+
+    /// Every change recorded to the items of the list in use, oldest first.
+    fn change_records(&self) -> Result<Vec<Record>, &'static str> {
+        Err("Database does not keep the history of items")
+    }
+
+    /// The lines of the list in use as they are stored, read or not.
+    fn read_list_lines(&self) -> Result<Vec<String>, &'static str> {
+        Err("Database cannot be checked")
+    }
+    /// Replaces the list in use with `todos`, setting the `quarantined`
+    /// lines aside in a file next to it.
+    fn repair_list(
+        &self,
+        _todos: Vec<ToDo>,
+        _quarantined: Vec<String>,
+    ) -> Result<(), &'static str> {
+        Err("Database cannot be repaired")
+    }
+
+    /// How the list in use compares to the way it was last written.
+    fn integrity(&self) -> Result<Integrity, &'static str> {
+        Ok(Integrity::Verified)
+    }
+
+    /// The warnings about the files of the database gathered since they were
+    /// last taken, for the action to log.
+    fn take_warnings(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn is_encrypted(&self) -> Result<bool, &'static str> {
+        Ok(false)
+    }
+    /// Encrypts every file kept for the list in use with `passphrase`,
+    /// giving how many there were. Files already encrypted are decrypted
+    /// with the passphrase in use first.
+    fn encrypt_list(&self, _passphrase: &str) -> Result<usize, &'static str> {
+        Err("Database cannot be encrypted")
+    }
+
+    /// The changes made to the list in use that can be undone, none for
+    /// databases that do not keep them.
+    fn read_journal(&self) -> Result<Journal, &'static str> {
+        Ok(Journal::default())
+    }
+    fn store_journal(&self, _journal: &Journal) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    /// The items of the list in use as they were written to `file` when it
+    /// was last synced with it, if it ever was.
+    fn read_sync_base(&self, _file: &Path) -> Result<Option<Vec<ToDo>>, &'static str> {
+        Ok(None)
+    }
+    fn store_sync_base(&self, _file: &Path, _todos: &[ToDo]) -> Result<(), &'static str> {
+        Ok(())
+    }
 }
 
 const DATABASE_NAME: &str = "todo-list.txt";
+const LIST_EXTENSION: &str = "txt";
+const ARCHIVE_EXTENSION: &str = "archive";
+const TRASH_EXTENSION: &str = "trash";
+const EVENTS_EXTENSION: &str = "events";
+const HISTORY_EXTENSION: &str = "history";
+const QUARANTINE_EXTENSION: &str = "quarantine";
+const CHECKSUM_EXTENSION: &str = "checksum";
+
+/// Files kept next to a list, which follow it when it is renamed or deleted.
+const LIST_SIDECARS: [&str; 6] = [
+    ARCHIVE_EXTENSION,
+    TRASH_EXTENSION,
+    EVENTS_EXTENSION,
+    HISTORY_EXTENSION,
+    QUARANTINE_EXTENSION,
+    CHECKSUM_EXTENSION,
+];
+
+/// Name of the file or directory holding the database of a project.
+pub const PROJECT_DATABASE_NAME: &str = ".terminal-todo";
+
+/// The list used when no other one was picked, kept in `todo-list.txt` like
+/// the single list of older versions.
+pub const DEFAULT_LIST: &str = "default";
 
 pub struct DatabaseAgent {}
 
-fn get_database() -> Result<String, &'static str> {
-    let database_location = env::var("TODO_DB").unwrap_or_else(|_| {
-        let db_base_path = if cfg!(target_os = "windows") {
-            let mut windows_app_data_path = env::var("AppData").unwrap_or_default();
+thread_local! {
+    /// Warnings about the files read and written, gathered by the functions
+    /// working on them until `take_warnings` hands them to the action.
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+fn warn(warning: String) {
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(warning));
+}
+
+thread_local! {
+    /// Each event log with the list as it was last read from it or written
+    /// to it, with the ids of its items. What an action changed is worked out
+    /// against it, so that what other processes appended since is kept.
+    static KNOWN_LOGS: RefCell<Vec<(PathBuf, events::Replayed)>> = const { RefCell::new(vec![]) };
+}
+
+fn known_log(events: &Path) -> Option<events::Replayed> {
+    KNOWN_LOGS.with(|logs| {
+        logs.borrow()
+            .iter()
+            .find(|(path, _)| path == events)
+            .map(|(_, replayed)| replayed.clone())
+    })
+}
+
+fn remember_log(events: &Path, replayed: events::Replayed) {
+    KNOWN_LOGS.with(|logs| {
+        let mut logs = logs.borrow_mut();
+        logs.retain(|(path, _)| path != events);
+        logs.push((events.to_path_buf(), replayed));
+    });
+}
+
+/// How a list file compares to the checksum recorded when it was last
+/// written: the same, changed since, or cut short like by a sync that did not
+/// finish.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Integrity {
+    Verified,
+    Modified,
+    Truncated,
+}
+
+/// How lists are written, from the `database.backend` setting: rewritten as
+/// a whole on every change, or as changes appended to an event log next to
+/// the list.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Backend {
+    Text,
+    Events,
+}
+
+impl Backend {
+    pub fn new(backend_name: &str) -> Result<Self, &'static str> {
+        match backend_name {
+            "text" => Ok(Backend::Text),
+            "events" => Ok(Backend::Events),
+            _ => Err("Database backend can only be text or events"),
+        }
+    }
+
+    pub fn configured() -> Result<Self, &'static str> {
+        Backend::new(config::current().get("database.backend").unwrap_or("text"))
+    }
+}
+
+/// After how many events since the last snapshot a new one is written, from
+/// the `database.compact_after` setting.
+fn configured_compact_after() -> Result<usize, &'static str> {
+    config::current()
+        .get("database.compact_after")
+        .unwrap_or("100")
+        .parse::<usize>()
+        .map_err(|_| "database.compact_after should be a number")
+}
+
+/// The name of the list in use, from `--list` or the `database.list` setting.
+pub fn current_list() -> &'static str {
+    config::current()
+        .get("database.list")
+        .unwrap_or(DEFAULT_LIST)
+}
+
+/// List names end up in file names, so they are kept to letters, numbers,
+/// `-` and `_`.
+pub fn validate_list_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("List names can only have letters, numbers, - and _");
+    }
+
+    if name == DATABASE_NAME.trim_end_matches(".txt") {
+        return Err("List name is reserved");
+    }
+
+    Ok(())
+}
+
+/// Where the lists are kept: a directory with a file per list, or a single
+/// `.terminal-todo` file holding the only list of a project.
+struct Location {
+    path: PathBuf,
+    project: bool,
+}
+
+/// The nearest `.terminal-todo` file or directory in the current directory or
+/// one of its ancestors, leaving out the one older versions kept in the home
+/// directory.
+pub fn find_project_database() -> Option<PathBuf> {
+    nearest_project_database(&env::current_dir().ok()?, paths::legacy_dir().as_deref())
+}
+
+fn nearest_project_database(dir: &Path, legacy_dir: Option<&Path>) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_DATABASE_NAME))
+        .filter(|path| Some(path.as_path()) != legacy_dir)
+        .find(|path| path.exists())
+}
+
+/// Creates an empty project database in `dir`.
+pub fn init_project_database(dir: &Path) -> Result<PathBuf, &'static str> {
+    let path = dir.join(PROJECT_DATABASE_NAME);
+
+    if path.exists() {
+        return Err("This directory already has a .terminal-todo database");
+    }
+
+    if DirBuilder::new().create(&path).is_err() || File::create(path.join(DATABASE_NAME)).is_err() {
+        return Err("Could not create the .terminal-todo database");
+    }
+
+    Ok(path)
+}
+
+/// A database path given on the command line or in the environment is used
+/// as is. Otherwise a project database wins over the configured or default
+/// global one, unless `--global` turned discovery off.
+fn get_location() -> Result<Location, &'static str> {
+    let config = config::current();
+    Backend::configured()?;
+
+    let database_path = config.lookup("database.path");
 
-            windows_app_data_path = windows_app_data_path + "/.terminal-todo";
+    if let Some((path, Source::CommandLine | Source::Environment(_))) = database_path {
+        return Ok(Location {
+            path: PathBuf::from(path),
+            project: false,
+        });
+    }
+
+    if config.get("database.discover") != Some("false") {
+        if let Some(path) = find_project_database() {
+            return Ok(Location {
+                path,
+                project: true,
+            });
+        }
+    }
+
+    if let Some((path, _)) = database_path {
+        return Ok(Location {
+            path: PathBuf::from(path),
+            project: false,
+        });
+    }
+
+    let data_dir = paths::data_dir()?;
+
+    if DirBuilder::new().recursive(true).create(&data_dir).is_err() {
+        return Err("Could not create DB directory path");
+    }
+
+    migrate_legacy_database(&data_dir.join(DATABASE_NAME))?;
+
+    Ok(Location {
+        path: data_dir,
+        project: false,
+    })
+}
+
+fn get_list_path(name: &str) -> Result<PathBuf, &'static str> {
+    let file_name = match name {
+        DEFAULT_LIST => DATABASE_NAME.to_string(),
+        _ => {
+            validate_list_name(name)?;
+            format!("{name}.{LIST_EXTENSION}")
+        }
+    };
+
+    let location = get_location()?;
+
+    if location.path.is_file() {
+        return match name {
+            DEFAULT_LIST => Ok(location.path),
+            _ => Err("A .terminal-todo file only holds the default list, make it a directory to have more"),
+        };
+    }
+
+    Ok(location.path.join(file_name))
+}
+
+fn get_database() -> Result<PathBuf, &'static str> {
+    let list = current_list();
+    let database = get_list_path(list)?;
+
+    if list != DEFAULT_LIST && !database.is_file() {
+        return Err("List does not exist, create it with list-create");
+    }
+
+    Ok(database)
+}
+
+/// The archive of the list in use, kept next to it with the `.archive`
+/// extension so that it is not taken for a list.
+fn get_archive_path() -> Result<PathBuf, &'static str> {
+    Ok(get_database()?.with_extension(ARCHIVE_EXTENSION))
+}
 
-            let dir_build_res = DirBuilder::new()
-                .recursive(true)
-                .create(&windows_app_data_path);
+/// Names what is kept about the list in use in the state directory, like its
+/// undo journal and backups. They go there rather than next to the list so
+/// that synced or shared databases do not carry them along, named after a
+/// hash of the list path.
+fn get_state_key() -> Result<String, &'static str> {
+    let database = get_database()?;
+    let database = fs::canonicalize(&database).unwrap_or(database);
 
-            if let Err(_) = dir_build_res {
-                return "Could not create DB directory path".into();
+    Ok(format!(
+        "{:016x}",
+        fnv1a(database.to_string_lossy().as_bytes())
+    ))
+}
+
+/// FNV-1a, which is stable across builds unlike the std hasher.
+pub(super) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The checksum of list contents as recorded next to the list: the hash
+/// followed by the length.
+fn checksum(contents: &[u8]) -> String {
+    format!("{:016x} {}\n", fnv1a(contents), contents.len())
+}
+
+/// Compares list contents with a recorded checksum. Contents that got
+/// shorter and are empty or stop in the middle of a line were cut short,
+/// anything else that does not match was changed.
+fn compare_checksum(contents: &[u8], recorded: &str) -> Integrity {
+    if checksum(contents).trim_end() == recorded.trim_end() {
+        return Integrity::Verified;
+    }
+
+    let recorded_length = recorded
+        .split(' ')
+        .nth(1)
+        .and_then(|length| length.trim_end().parse::<usize>().ok());
+
+    match recorded_length {
+        Some(length)
+            if contents.len() < length && (contents.is_empty() || !contents.ends_with(b"\n")) =>
+        {
+            Integrity::Truncated
+        }
+        _ => Integrity::Modified,
+    }
+}
+
+/// How a list file compares to its recorded checksum. Lists without one,
+/// like those written by older versions, are taken as they are.
+fn check_integrity(list: &Path) -> Integrity {
+    match fs::read_to_string(list.with_extension(CHECKSUM_EXTENSION)) {
+        Ok(recorded) => compare_checksum(&fs::read(list).unwrap_or_default(), &recorded),
+        Err(_) => Integrity::Verified,
+    }
+}
+
+/// Records the checksum of a list file just written.
+fn store_checksum(list: &Path) -> Result<(), &'static str> {
+    let contents = fs::read(list).unwrap_or_default();
+
+    match fs::write(list.with_extension(CHECKSUM_EXTENSION), checksum(&contents)) {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Could not store the checksum of the list"),
+    }
+}
+
+/// Warns when a list file is not the way it was last written.
+fn warn_about_integrity(list: &Path) {
+    match check_integrity(list) {
+        Integrity::Verified => {}
+        Integrity::Modified => warn(format!(
+            "{} was changed outside of terminal-todo since it was last written",
+            list.display()
+        )),
+        Integrity::Truncated => warn(format!(
+            "{} is shorter than when it was last written and may have been cut off, see backup list",
+            list.display()
+        )),
+    }
+}
+
+fn get_journal_path() -> Result<PathBuf, &'static str> {
+    Ok(paths::state_dir()?
+        .join("journal")
+        .join(format!("{}.{LIST_EXTENSION}", get_state_key()?)))
+}
+
+/// Where the items last synced with `file` are kept, for each list and file.
+fn get_sync_base_path(file: &Path) -> Result<PathBuf, &'static str> {
+    let file = fs::canonicalize(file)
+        .or_else(|_| std::path::absolute(file))
+        .unwrap_or(file.to_path_buf());
+
+    Ok(paths::state_dir()?.join("sync").join(format!(
+        "{}-{:016x}.{LIST_EXTENSION}",
+        get_state_key()?,
+        fnv1a(file.to_string_lossy().as_bytes())
+    )))
+}
+
+fn get_backup_dir() -> Result<PathBuf, &'static str> {
+    Ok(paths::state_dir()?.join("backups").join(get_state_key()?))
+}
+
+/// Copies the list in use before it is overwritten, or its event log with
+/// the events backend, then lets go of the backups the retention policy no
+/// longer keeps.
+fn backup_database(database: &Path) -> Result<(), &'static str> {
+    let (keep, daily) = backup::configured_retention()?;
+
+    back_up(database, &get_backup_dir()?, keep, daily)
+}
+
+fn back_up(
+    database: &Path,
+    backup_dir: &Path,
+    keep: usize,
+    daily: usize,
+) -> Result<(), &'static str> {
+    let events = database.with_extension(EVENTS_EXTENSION);
+    let (source, extension) = match Backend::configured()? {
+        Backend::Events if events.is_file() => (events, EVENTS_EXTENSION),
+        _ => (database.to_path_buf(), LIST_EXTENSION),
+    };
+
+    if (keep == 0 && daily == 0) || !source.is_file() {
+        return Ok(());
+    }
+
+    if DirBuilder::new()
+        .recursive(true)
+        .create(backup_dir)
+        .is_err()
+    {
+        return Err("Could not create the backup directory");
+    }
+
+    // Several writes in the same second keep the copy from before the first
+    let timestamp = date::now();
+    if get_backup_path(backup_dir, timestamp).is_none()
+        && fs::copy(source, backup_dir.join(format!("{timestamp}.{extension}"))).is_err()
+    {
+        return Err("Could not back up the database");
+    }
+
+    for timestamp in backup::expired_backups(&list_backups(backup_dir), keep, daily) {
+        if let Some(backup) = get_backup_path(backup_dir, timestamp) {
+            let _ = fs::remove_file(backup);
+        }
+    }
+
+    Ok(())
+}
+
+/// The backup taken at `timestamp`, a copy of a list or of an event log.
+fn get_backup_path(backup_dir: &Path, timestamp: u64) -> Option<PathBuf> {
+    [LIST_EXTENSION, EVENTS_EXTENSION]
+        .iter()
+        .map(|extension| backup_dir.join(format!("{timestamp}.{extension}")))
+        .find(|backup| backup.is_file())
+}
+
+fn list_backups(backup_dir: &Path) -> Vec<u64> {
+    let entries = match fs::read_dir(backup_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut timestamps: Vec<u64> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == LIST_EXTENSION || ext == EVENTS_EXTENSION)
+        })
+        .filter_map(|path| path.file_stem()?.to_str()?.parse::<u64>().ok())
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    timestamps
+}
+
+/// Whether the files of `list` are encrypted, which is told by the list
+/// file or its event log.
+fn is_encrypted_list(list: &Path) -> bool {
+    [list.to_path_buf(), list.with_extension(EVENTS_EXTENSION)]
+        .iter()
+        .any(|file| fs::read(file).is_ok_and(|contents| encryption::is_encrypted(&contents)))
+}
+
+/// Reads a file of the database, decrypting it when it is encrypted. Gives
+/// nothing for a file that does not exist.
+fn read_file(path: &Path) -> Result<Option<String>, &'static str> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(None),
+    };
+
+    match encryption::is_encrypted(&contents) {
+        true => encryption::decrypt(&contents).map(Some),
+        false => match String::from_utf8(contents) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(_) => Err("Database is corrupted, could not read data"),
+        },
+    }
+}
+
+/// Writes a file of the database, encrypted when `encrypted` is set.
+fn write_file(path: &Path, contents: &str, encrypted: bool) -> Result<(), &'static str> {
+    let written = match encrypted {
+        true => fs::write(path, encryption::encrypt(contents)?),
+        false => fs::write(path, contents),
+    };
+
+    written.map_err(|_| "Could not write to the database")
+}
+
+/// Appends to a file of the database. An encrypted file cannot be appended
+/// to, so it is decrypted and written again as a whole.
+fn append_file(path: &Path, contents: &str, encrypted: bool) -> Result<(), &'static str> {
+    if encrypted {
+        let existing = read_file(path)?.unwrap_or_default();
+        return write_file(path, &(existing + contents), true);
+    }
+
+    File::options()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|_| "Could not write to the database")
+}
+
+/// Whether lines of a list that cannot be read are skipped with a warning
+/// instead of failing, from the `database.lenient` setting.
+fn configured_lenient() -> bool {
+    config::current().get("database.lenient") == Some("true")
+}
+
+/// Reads a list file, skipping the lines that cannot be read in lenient
+/// mode.
+fn read_list_todos(list: &Path) -> Result<Vec<ToDo>, &'static str> {
+    if !configured_lenient() {
+        return read_todos(list).map_err(|err| match err {
+            "Database is corrupted, could not read data" => {
+                "Database is corrupted, run doctor to find what cannot be read"
             }
+            err => err,
+        });
+    }
 
-            windows_app_data_path
-        } else {
-            let mut unix_home_path = env::var("HOME").unwrap_or_default();
+    let list_str = read_file(list)?.unwrap_or_default();
+    let mut todos: Vec<ToDo> = vec![];
 
-            unix_home_path = unix_home_path + "/.terminal-todo";
+    for (number, line) in list_str.lines().enumerate() {
+        match ToDo::salvage(line) {
+            (Some(todo), problems) if problems.is_empty() => todos.push(todo),
+            (_, problems) => warn(format!(
+                "Skipping line {} of {}, which {}",
+                number + 1,
+                list.display(),
+                problems.join(" and ")
+            )),
+        }
+    }
 
-            let dir_build_res = DirBuilder::new().recursive(true).create(&unix_home_path);
+    Ok(todos)
+}
+
+/// Sets the lines of a list file that cannot be read aside in its
+/// quarantine file, so that rewriting a list read leniently does not lose
+/// them.
+fn quarantine_unreadable_lines(list: &Path) -> Result<(), &'static str> {
+    let list_str = read_file(list)?.unwrap_or_default();
+    let unreadable: Vec<String> = list_str
+        .lines()
+        .filter(|line| ToDo::deserialise(line).is_err() && !line.trim().is_empty())
+        .map(String::from)
+        .collect();
+
+    quarantine(list, &unreadable)
+}
 
-            if let Err(_) = dir_build_res {
-                return "Could not create DB directory path".into();
+/// Appends `lines` to the quarantine file of `list`.
+fn quarantine(list: &Path, lines: &[String]) -> Result<(), &'static str> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let quarantined: String = lines.iter().map(|line| format!("{line}\n")).collect();
+
+    append_file(
+        &list.with_extension(QUARANTINE_EXTENSION),
+        &quarantined,
+        is_encrypted_list(list),
+    )
+    .map_err(|_| "Could not set the unreadable lines aside")
+}
+
+/// Reads a list the way the configured backend keeps it.
+fn read_list_file(list: &Path) -> Result<Vec<ToDo>, &'static str> {
+    match Backend::configured()? {
+        Backend::Events => {
+            adopt_history(list)?;
+
+            let events = list.with_extension(EVENTS_EXTENSION);
+            match events.is_file() {
+                true => {
+                    let replayed = events::replay(&read_event_log(&events, true)?);
+                    let todos = replayed.todos();
+                    remember_log(&events, replayed);
+                    Ok(todos)
+                }
+                false => read_list_todos(list),
             }
+        }
+        Backend::Text => {
+            warn_about_integrity(list);
+            fold_event_log(list)
+        }
+    }
+}
 
-            unix_home_path
-        };
+/// Stores a list the way the configured backend keeps it. The text backend
+/// rewrites the list and records the changes in its history, the events
+/// backend only appends them to its event log.
+fn write_list_file(list: &Path, todos: &[ToDo]) -> Result<(), &'static str> {
+    match Backend::configured()? {
+        Backend::Text => {
+            fold_event_log(list)?;
+            append_events(&list.with_extension(HISTORY_EXTENSION), list, todos, false)?;
+            if configured_lenient() {
+                quarantine_unreadable_lines(list)?;
+            }
+            write_todos(list, todos, is_encrypted_list(list))?;
+            store_checksum(list)
+        }
+        Backend::Events => {
+            adopt_history(list)?;
+            append_events(&list.with_extension(EVENTS_EXTENSION), list, todos, true)
+        }
+    }
+}
+
+/// Writes the list left by the event log of `list`, if there is one, into
+/// the list file once back to the text backend. The log then goes on as the
+/// history of the list.
+fn fold_event_log(list: &Path) -> Result<Vec<ToDo>, &'static str> {
+    let events = list.with_extension(EVENTS_EXTENSION);
+
+    if !events.is_file() {
+        return read_list_todos(list);
+    }
+
+    let todos = events::replay(&read_event_log(&events, true)?).todos();
+    write_todos(list, &todos, is_encrypted_list(list))?;
+    store_checksum(list)?;
+
+    if fs::rename(&events, list.with_extension(HISTORY_EXTENSION)).is_err() {
+        return Err("Could not turn the event log into the history of the list");
+    }
+
+    warn(format!(
+        "Moved the changes in {} into the list, which is back to the text backend",
+        events.display()
+    ));
+
+    Ok(todos)
+}
+
+/// Starts the event log of `list` from its history once moved to the events
+/// backend, catching up with any change made to the list file since.
+fn adopt_history(list: &Path) -> Result<(), &'static str> {
+    let (events, history) = (
+        list.with_extension(EVENTS_EXTENSION),
+        list.with_extension(HISTORY_EXTENSION),
+    );
+
+    if events.is_file() || !history.is_file() {
+        return Ok(());
+    }
+
+    if fs::rename(&history, &events).is_err() {
+        return Err("Could not turn the history of the list into its event log");
+    }
+
+    append_events(&events, list, &read_list_todos(list)?, false)
+}
+
+/// Reads the records of an event log, only those from the last snapshot on
+/// when `latest` is set.
+fn read_event_log(events: &Path, latest: bool) -> Result<Vec<Record>, &'static str> {
+    let log = match read_file(events)? {
+        Some(log) => log,
+        None => return Err("Could not read the event log"),
+    };
+
+    match latest {
+        true => events::parse_log(events::since_last_snapshot(&log)),
+        false => events::parse_log(&log),
+    }
+}
+
+/// Appends to the log at `events_path` the events turning the list as it
+/// was last read into `todos`, starting the log with a snapshot of the list
+/// file the first time. The log is locked meanwhile, and the events are
+/// applied on top of what other processes appended since it was read. Every
+/// `database.compact_after` events a snapshot is added so that reading does
+/// not have to replay the whole log, and the list file is refreshed with it
+/// when `refresh_list` is set.
+fn append_events(
+    events_path: &Path,
+    list: &Path,
+    todos: &[ToDo],
+    refresh_list: bool,
+) -> Result<(), &'static str> {
+    let timestamp = date::now();
+    let started = fs::metadata(events_path).is_ok_and(|metadata| metadata.len() > 0);
+
+    // Held until the events are appended, so that processes writing at the
+    // same time take turns
+    let lock = File::options()
+        .append(true)
+        .create(true)
+        .open(events_path)
+        .map_err(|_| "Could not append to the event log")?;
+    lock.lock().map_err(|_| "Could not lock the event log")?;
+
+    let (mut records, mut new_records) = match started {
+        true => (read_event_log(events_path, true)?, vec![]),
+        false => {
+            let initial = events::snapshot(&events::Replayed::default(), &read_list_todos(list)?);
+            (
+                vec![],
+                vec![Record {
+                    timestamp,
+                    event: initial,
+                }],
+            )
+        }
+    };
+    records.extend(new_records.iter().cloned());
+
+    let mut replayed = events::replay(&records);
+    let known = known_log(events_path).unwrap_or_else(|| replayed.clone());
+    let (changes, own) = events::rebase(events::events_between(&known, todos), &known, &replayed);
+
+    for event in changes {
+        replayed.apply(&event);
+        records.push(Record {
+            timestamp,
+            event: event.clone(),
+        });
+        new_records.push(Record { timestamp, event });
+    }
+
+    if events::records_since_snapshot(&records) > configured_compact_after()? {
+        new_records.push(Record {
+            timestamp,
+            event: events::Event::Snapshot {
+                items: replayed.items.clone(),
+                next_id: replayed.next_id,
+            },
+        });
+
+        if refresh_list {
+            write_todos(list, &replayed.todos(), is_encrypted_list(list))?;
+            store_checksum(list)?;
+        }
+    }
+
+    let log: String = new_records.iter().map(Record::serialise).collect();
+
+    // A single append, so that readers do not see half of it
+    append_file(events_path, &log, is_encrypted_list(list))
+        .map_err(|_| "Could not append to the event log")?;
+    remember_log(events_path, own);
+
+    Ok(())
+}
+
+/// Encrypts the list at `database`, the files kept next to it but its
+/// checksum, and `state_files`, returning how many files were encrypted.
+fn encrypt_list_files(
+    database: &Path,
+    state_files: Vec<PathBuf>,
+    passphrase: &str,
+) -> Result<usize, &'static str> {
+    let mut files: Vec<PathBuf> = vec![database.to_path_buf()];
+    files.extend(
+        LIST_SIDECARS
+            .iter()
+            .filter(|extension| **extension != CHECKSUM_EXTENSION)
+            .map(|extension| database.with_extension(extension)),
+    );
+    files.extend(state_files);
+    files.retain(|file| file == database || file.is_file());
+
+    // Everything is read before anything is written, so that a wrong
+    // passphrase leaves the files as they were
+    let mut contents: Vec<(PathBuf, String)> = vec![];
+    for file in files {
+        let file_contents = read_file(&file)?.unwrap_or_default();
+        contents.push((file, file_contents));
+    }
+
+    encryption::set_passphrase(passphrase);
+
+    // Every file is written next to itself and synced before any of them
+    // replaces the one it was made from, so that a failure part of the way
+    // leaves none of them changed
+    let mut written: Vec<(PathBuf, &PathBuf)> = vec![];
+    for (file, file_contents) in contents.iter() {
+        let temp = encrypting_path(file);
+        let result = encryption::encrypt(file_contents).and_then(|encrypted| {
+            write_synced(&temp, &encrypted).map_err(|_| "Could not write to the database")
+        });
+        written.push((temp, file));
+
+        if let Err(err) = result {
+            for (temp, _) in written {
+                let _ = fs::remove_file(temp);
+            }
+            return Err(err);
+        }
+    }
+
+    for (temp, file) in written {
+        if fs::rename(&temp, file).is_err() {
+            return Err("Could not replace a file of the list with its encrypted copy");
+        }
+    }
+    store_checksum(database)?;
 
-        db_base_path
+    Ok(contents.len())
+}
+
+/// Where the encrypted copy of a file is written before it replaces it.
+fn encrypting_path(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".encrypting");
+    file.with_file_name(name)
+}
+
+/// Writes a file and waits for it to be on disk.
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Moves the database from `~/.terminal-todo`, where it was kept before
+/// following the XDG directories, unless there already is one at `database`.
+fn migrate_legacy_database(database: &Path) -> Result<(), &'static str> {
+    match paths::legacy_dir() {
+        Some(legacy_dir) => move_legacy_database(&legacy_dir, database),
+        None => Ok(()),
+    }
+}
+
+fn move_legacy_database(legacy_dir: &Path, database: &Path) -> Result<(), &'static str> {
+    let legacy_database = legacy_dir.join(DATABASE_NAME);
+
+    if !legacy_database.is_file() || database.exists() {
+        return Ok(());
+    }
+
+    let moved = fs::rename(&legacy_database, database).or_else(|_| {
+        fs::copy(&legacy_database, database).and_then(|_| fs::remove_file(&legacy_database))
     });
 
-    Ok(format!("{}/{}", database_location, DATABASE_NAME))
+    if moved.is_err() {
+        return Err("Could not move the database out of ~/.terminal-todo");
+    }
+
+    // Only goes away when nothing else was put in there
+    let _ = fs::remove_dir(legacy_dir);
+
+    warn(format!(
+        "Moved the database from {} to {}",
+        legacy_database.display(),
+        database.display()
+    ));
+
+    Ok(())
 }
 
 impl Database for DatabaseAgent {
-    fn store_item(&self, todo: ToDo) -> Result<(), &'static str> {
-        let db = get_database()?;
+    fn location(&self) -> Option<String> {
+        let location = get_location().ok()?;
+        let list = current_list();
+
+        Some(format!(
+            "Using the {}{} database at {}",
+            if list == DEFAULT_LIST {
+                String::new()
+            } else {
+                format!("{list} list of the ")
+            },
+            if location.project {
+                "project"
+            } else {
+                "global"
+            },
+            location.path.display()
+        ))
+    }
+
+    fn list_names(&self) -> Result<Vec<String>, &'static str> {
+        let location = get_location()?;
+
+        if location.path.is_file() {
+            return Ok(vec![DEFAULT_LIST.into()]);
+        }
+
+        let entries = match fs::read_dir(location.path) {
+            Ok(entries) => entries,
+            Err(_) => return Err("Could not read the database directory"),
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file() && path.extension().is_some_and(|ext| ext == LIST_EXTENSION)
+            })
+            .filter_map(|path| path.file_stem()?.to_str().map(String::from))
+            .filter(|name| validate_list_name(name).is_ok())
+            .collect();
+
+        names.push(DEFAULT_LIST.into());
+        names.sort();
+        names.dedup();
+
+        Ok(names)
+    }
+
+    fn create_list(&self, name: &str) -> Result<(), &'static str> {
+        let path = get_list_path(name)?;
+
+        if name == DEFAULT_LIST || path.exists() {
+            return Err("List already exists");
+        }
+
+        match File::create(path) {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Could not create the list"),
+        }
+    }
+
+    fn rename_list(&self, name: &str, new_name: &str) -> Result<(), &'static str> {
+        if name == DEFAULT_LIST || new_name == DEFAULT_LIST {
+            return Err("The default list cannot be renamed");
+        }
+
+        let (path, new_path) = (get_list_path(name)?, get_list_path(new_name)?);
 
-        let mut database: File = File::options()
-            .append(true)
-            .create(true)
-            .open(db)
-            .expect("Error finding the database");
+        if !path.is_file() {
+            return Err("List does not exist");
+        }
+        if new_path.exists() {
+            return Err("List already exists");
+        }
 
-        database
-            .write(todo.serialise().as_bytes())
-            .expect("Error storing ToDo item");
+        fs::rename(&path, &new_path).map_err(|_| "Could not rename the list")?;
 
-        println!("Item added to the database");
+        for extension in LIST_SIDECARS {
+            let sidecar = path.with_extension(extension);
+            if sidecar.is_file() {
+                fs::rename(sidecar, new_path.with_extension(extension))
+                    .map_err(|_| "Could not rename the archive or trash of the list")?;
+            }
+        }
 
         Ok(())
     }
 
-    fn store_existing_items(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+    fn delete_list(&self, name: &str) -> Result<(), &'static str> {
+        if name == DEFAULT_LIST {
+            return Err("The default list cannot be deleted");
+        }
+
+        let path = get_list_path(name)?;
+
+        if !path.is_file() {
+            return Err("List does not exist");
+        }
+
+        fs::remove_file(&path).map_err(|_| "Could not delete the list")?;
+
+        for extension in LIST_SIDECARS {
+            let sidecar = path.with_extension(extension);
+            if sidecar.is_file() {
+                fs::remove_file(sidecar)
+                    .map_err(|_| "Could not delete the archive or trash of the list")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_list(&self, name: &str) -> Result<Vec<ToDo>, &'static str> {
+        let path = get_list_path(name)?;
+
+        if name != DEFAULT_LIST && !path.is_file() {
+            return Err("List does not exist");
+        }
+
+        read_list_file(&path)
+    }
+
+    fn store_list(&self, name: &str, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        let path = get_list_path(name)?;
+
+        if name != DEFAULT_LIST && !path.is_file() {
+            return Err("List does not exist");
+        }
+
+        write_list_file(&path, &todos)
+    }
+
+    fn store_item(&self, todo: ToDo) -> Result<(), &'static str> {
         let db = get_database()?;
 
-        let mut database: File = File::options()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(db)
-            .expect("Error openning the database");
+        // Both backends record the creation, the text one then just appends the
+        // item to the list instead of rewriting it
+        let mut todos = read_list_file(&db)?;
+        todos.push(todo.clone());
 
-        let mut serialised_todos = Vec::new();
+        if Backend::configured()? == Backend::Events {
+            return write_list_file(&db, &todos);
+        }
+        append_events(&db.with_extension(HISTORY_EXTENSION), &db, &todos, false)?;
 
-        todos.iter().for_each(|todo| {
-            serialised_todos.push(todo.serialise());
-        });
+        // Adding to a list changed outside of terminal-todo does not make it
+        // trusted again
+        let verified = check_integrity(&db) == Integrity::Verified;
 
-        let serialised_todos = serialised_todos.join("");
+        append_file(&db, &todo.serialise(), is_encrypted_list(&db))?;
+
+        match verified {
+            true => store_checksum(&db),
+            false => Ok(()),
+        }
+    }
 
-        database
-            .write(&serialised_todos.as_bytes())
-            .expect("Error storing ToDo item");
+    fn store_existing_items(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        let database = get_database()?;
 
-        Ok(())
+        backup_database(&database)?;
+        write_list_file(&database, &todos)
     }
 
     fn read_items(&self) -> Result<Vec<ToDo>, &'static str> {
-        let db = get_database()?;
+        read_list_file(&get_database()?)
+    }
 
-        let mut database: File = File::options()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(db)
-            .expect("Error openning the database");
+    fn read_archive(&self) -> Result<Vec<ToDo>, &'static str> {
+        let archive = get_archive_path()?;
 
-        let mut todo_items: Vec<ToDo> = vec![];
-        let mut db_content: String = String::new();
+        match archive.is_file() {
+            true => read_todos(&archive),
+            false => Ok(vec![]),
+        }
+    }
 
-        database
-            .read_to_string(&mut db_content)
-            .expect("Error reading the database content");
+    fn store_archive(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        write_todos(
+            &get_archive_path()?,
+            &todos,
+            is_encrypted_list(&get_database()?),
+        )
+    }
 
-        for todo_serialised in db_content.lines() {
-            todo_items.push(ToDo::deserialise(todo_serialised)?);
+    fn read_trash(&self) -> Result<Vec<Trashed>, &'static str> {
+        match read_file(&get_database()?.with_extension(TRASH_EXTENSION))? {
+            Some(trash_str) => trash_str.lines().map(Trashed::deserialise).collect(),
+            None => Ok(vec![]),
         }
+    }
+
+    fn store_trash(&self, trash: Vec<Trashed>) -> Result<(), &'static str> {
+        let trash_str: String = trash.iter().map(Trashed::serialise).collect();
+
+        let database = get_database()?;
+
+        write_file(
+            &database.with_extension(TRASH_EXTENSION),
+            &trash_str,
+            is_encrypted_list(&database),
+        )
+        .map_err(|_| "Could not store the trash")
+    }
+
+    fn backups(&self) -> Result<Vec<u64>, &'static str> {
+        Ok(list_backups(&get_backup_dir()?))
+    }
+
+    fn read_backup(&self, timestamp: u64) -> Result<Vec<ToDo>, &'static str> {
+        match get_backup_path(&get_backup_dir()?, timestamp) {
+            Some(backup)
+                if backup
+                    .extension()
+                    .is_some_and(|ext| ext == EVENTS_EXTENSION) =>
+            {
+                read_event_log(&backup, true).map(|records| events::replay(&records).todos())
+            }
+            Some(backup) => read_todos(&backup),
+            None => Err("There is no backup with that timestamp, see backup list"),
+        }
+    }
+
+    fn change_records(&self) -> Result<Vec<Record>, &'static str> {
+        let database = get_database()?;
+        let events = database.with_extension(EVENTS_EXTENSION);
+
+        let log = match Backend::configured()? {
+            Backend::Events if events.is_file() => events,
+            _ => database.with_extension(HISTORY_EXTENSION),
+        };
+
+        match log.is_file() {
+            true => read_event_log(&log, false),
+            false => Ok(vec![]),
+        }
+    }
+
+    fn read_list_lines(&self) -> Result<Vec<String>, &'static str> {
+        if Backend::configured()? == Backend::Events {
+            return Err("Only lists kept by the text backend can be checked");
+        }
+
+        let list_str = read_file(&get_database()?)?.unwrap_or_default();
+        Ok(list_str.lines().map(String::from).collect())
+    }
+
+    fn repair_list(&self, todos: Vec<ToDo>, quarantined: Vec<String>) -> Result<(), &'static str> {
+        let database = get_database()?;
+
+        // The list cannot be read as it is, so it is written as is without
+        // going through the history, which catches up on the next change
+        backup_database(&database)?;
+        quarantine(&database, &quarantined)?;
+        write_todos(&database, &todos, is_encrypted_list(&database))?;
+        store_checksum(&database)
+    }
+
+    fn is_encrypted(&self) -> Result<bool, &'static str> {
+        Ok(is_encrypted_list(&get_database()?))
+    }
+
+    fn encrypt_list(&self, passphrase: &str) -> Result<usize, &'static str> {
+        let database = get_database()?;
+
+        let mut state_files: Vec<PathBuf> = vec![get_journal_path()?];
+        if let Ok(backups) = fs::read_dir(get_backup_dir()?) {
+            state_files.extend(backups.flatten().map(|backup| backup.path()));
+        }
+        let state_key = get_state_key()?;
+        if let Ok(sync_bases) = fs::read_dir(paths::state_dir()?.join("sync")) {
+            state_files.extend(
+                sync_bases
+                    .flatten()
+                    .filter(|base| base.file_name().to_string_lossy().starts_with(&state_key))
+                    .map(|base| base.path()),
+            );
+        }
+
+        encrypt_list_files(&database, state_files, passphrase)
+    }
+
+    fn integrity(&self) -> Result<Integrity, &'static str> {
+        match Backend::configured()? {
+            Backend::Text => Ok(check_integrity(&get_database()?)),
+            Backend::Events => Ok(Integrity::Verified),
+        }
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        WARNINGS.with(|warnings| warnings.take())
+    }
+
+    fn read_journal(&self) -> Result<Journal, &'static str> {
+        match read_file(&get_journal_path()?)? {
+            Some(journal_str) => Journal::deserialise(&journal_str),
+            None => Ok(Journal::default()),
+        }
+    }
+
+    fn store_journal(&self, journal: &Journal) -> Result<(), &'static str> {
+        let path = get_journal_path()?;
+
+        if let Some(dir) = path.parent() {
+            if DirBuilder::new().recursive(true).create(dir).is_err() {
+                return Err("Could not create the journal directory");
+            }
+        }
+
+        write_file(
+            &path,
+            &journal.serialise(),
+            is_encrypted_list(&get_database()?),
+        )
+        .map_err(|_| "Could not store the journal")
+    }
+
+    fn read_sync_base(&self, file: &Path) -> Result<Option<Vec<ToDo>>, &'static str> {
+        match read_file(&get_sync_base_path(file)?)? {
+            Some(base_str) => base_str
+                .lines()
+                .map(ToDo::deserialise)
+                .collect::<Result<Vec<ToDo>, &'static str>>()
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn store_sync_base(&self, file: &Path, todos: &[ToDo]) -> Result<(), &'static str> {
+        let path = get_sync_base_path(file)?;
+
+        if let Some(dir) = path.parent() {
+            if DirBuilder::new().recursive(true).create(dir).is_err() {
+                return Err("Could not create the sync directory");
+            }
+        }
+
+        let base_str: String = todos.iter().map(ToDo::serialise).collect();
+        write_file(&path, &base_str, is_encrypted_list(&get_database()?))
+            .map_err(|_| "Could not store what was synced")
+    }
+}
+
+/// Writes items to a file of the database, encrypted when `encrypted` is set
+/// like the list they belong to.
+fn write_todos(db: &Path, todos: &[ToDo], encrypted: bool) -> Result<(), &'static str> {
+    if encrypted {
+        let serialised_todos: String = todos.iter().map(ToDo::serialise).collect();
+        return write_file(db, &serialised_todos, true);
+    }
+
+    let mut database: File = File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(db)
+        .expect("Error openning the database");
+
+    let mut serialised_todos = Vec::new();
+
+    todos.iter().for_each(|todo| {
+        serialised_todos.push(todo.serialise());
+    });
+
+    let serialised_todos = serialised_todos.join("");
+
+    database
+        .write_all(serialised_todos.as_bytes())
+        .expect("Error storing ToDo item");
+
+    Ok(())
+}
+
+fn read_todos(db: &Path) -> Result<Vec<ToDo>, &'static str> {
+    let mut database: File = File::options()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .open(db)
+        .expect("Error openning the database");
+
+    let mut todo_items: Vec<ToDo> = vec![];
+    let mut db_content: Vec<u8> = vec![];
+
+    database
+        .read_to_end(&mut db_content)
+        .expect("Error reading the database content");
+
+    let db_content: String = match encryption::is_encrypted(&db_content) {
+        true => encryption::decrypt(&db_content)?,
+        false => String::from_utf8(db_content)
+            .map_err(|_| "Database is corrupted, could not read data")?,
+    };
+
+    for todo_serialised in db_content.lines() {
+        todo_items.push(ToDo::deserialise(todo_serialised)?);
+    }
+
+    Ok(todo_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::test_database::todos;
+
+    /// The passphrase every test encrypting files uses, as it is kept for the
+    /// whole run.
+    const PASSPHRASE: &str = "correct horse";
+
+    /// An empty directory of its own for a test to write files in.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("terminal-todo-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        DirBuilder::new().recursive(true).create(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn should_move_the_legacy_database_once() {
+        let dir = test_dir("legacy");
+        let legacy_dir = dir.join(".terminal-todo");
+        let data_dir = dir.join("data");
+        DirBuilder::new().create(&legacy_dir).unwrap();
+        DirBuilder::new().create(&data_dir).unwrap();
+        fs::write(legacy_dir.join(DATABASE_NAME), "a,description,❌\n").unwrap();
+
+        let database = data_dir.join(DATABASE_NAME);
+        move_legacy_database(&legacy_dir, &database).unwrap();
+
+        assert_eq!(read_todos(&database), Ok(todos(&["a"])));
+        assert!(!legacy_dir.exists());
+        assert_eq!(
+            WARNINGS.with(|warnings| warnings.take()),
+            vec![format!(
+                "Moved the database from {} to {}",
+                legacy_dir.join(DATABASE_NAME).display(),
+                database.display()
+            )]
+        );
+
+        // A database already in the new place is left alone
+        DirBuilder::new().create(&legacy_dir).unwrap();
+        fs::write(legacy_dir.join(DATABASE_NAME), "b,description,❌\n").unwrap();
+        move_legacy_database(&legacy_dir, &database).unwrap();
+
+        assert_eq!(read_todos(&database), Ok(todos(&["a"])));
+        assert!(legacy_dir.join(DATABASE_NAME).is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_find_the_nearest_project_database() {
+        let dir = test_dir("project");
+        let nested = dir.join("web").join("src");
+        DirBuilder::new().recursive(true).create(&nested).unwrap();
+
+        assert_eq!(nearest_project_database(&nested, None), None);
+
+        let project = init_project_database(&dir).unwrap();
+        assert_eq!(project, dir.join(PROJECT_DATABASE_NAME));
+        assert!(project.join(DATABASE_NAME).is_file());
+        assert_eq!(
+            init_project_database(&dir),
+            Err("This directory already has a .terminal-todo database")
+        );
+        assert_eq!(
+            nearest_project_database(&nested, None),
+            Some(project.clone())
+        );
+
+        // The nearest one wins, and it can be a single file
+        let web_project = dir.join("web").join(PROJECT_DATABASE_NAME);
+        File::create(&web_project).unwrap();
+        assert_eq!(
+            nearest_project_database(&nested, None),
+            Some(web_project.clone())
+        );
+
+        // The directory older versions kept in the home directory is not one
+        fs::remove_file(&web_project).unwrap();
+        assert_eq!(nearest_project_database(&nested, Some(&project)), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_let_go_of_the_backups_no_longer_kept() {
+        let dir = test_dir("backups");
+        let (list, backup_dir) = (dir.join(DATABASE_NAME), dir.join("backups"));
+        fs::write(&list, "a,description,❌\n").unwrap();
+
+        // Nothing is kept when neither the latest nor daily backups are
+        back_up(&list, &backup_dir, 0, 0).unwrap();
+        assert!(!backup_dir.exists());
+
+        let now = date::now();
+        let (day, hour) = (24 * 60 * 60, 60 * 60);
+        let older = [
+            now - 3 * day,
+            now - 3 * day + hour,
+            now - 2 * day,
+            now - day,
+        ];
+        DirBuilder::new().create(&backup_dir).unwrap();
+        for timestamp in older {
+            fs::write(backup_dir.join(format!("{timestamp}.{LIST_EXTENSION}")), "").unwrap();
+        }
+
+        // The latest one, taken now, and the last one of yesterday
+        back_up(&list, &backup_dir, 1, 2).unwrap();
+
+        let backups = list_backups(&backup_dir);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0], now - day);
+        assert!(backups[1] >= now);
+        assert_eq!(
+            read_todos(&get_backup_path(&backup_dir, backups[1]).unwrap()),
+            Ok(todos(&["a"]))
+        );
+
+        // With only daily backups the older days go first
+        back_up(&list, &backup_dir, 0, 1).unwrap();
+        let kept = list_backups(&backup_dir);
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0] >= backups[1]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_adopt_the_history_and_fold_the_event_log() {
+        let dir = test_dir("events");
+        let list = dir.join(DATABASE_NAME);
+        let (events, history) = (
+            list.with_extension(EVENTS_EXTENSION),
+            list.with_extension(HISTORY_EXTENSION),
+        );
+        fs::write(&list, "a,description,❌\n").unwrap();
+
+        write_list_file(&list, &todos(&["a", "b"])).unwrap();
+        assert!(history.is_file());
+
+        // A change made to the list file since is caught up with
+        fs::write(
+            &list,
+            todos(&["a", "b", "c"])
+                .iter()
+                .map(ToDo::serialise)
+                .collect::<String>(),
+        )
+        .unwrap();
+        adopt_history(&list).unwrap();
+
+        assert!(!history.exists());
+        let replayed = |latest| events::replay(&read_event_log(&events, latest).unwrap()).todos();
+        assert_eq!(replayed(true), todos(&["a", "b", "c"]));
+
+        append_events(&events, &list, &todos(&["a", "c"]), true).unwrap();
+        assert_eq!(replayed(true), todos(&["a", "c"]));
+        assert_eq!(replayed(false), todos(&["a", "c"]));
+        assert_eq!(read_todos(&list), Ok(todos(&["a", "b", "c"])));
+
+        // Back to the text backend the log goes on as the history
+        assert_eq!(fold_event_log(&list), Ok(todos(&["a", "c"])));
+        assert_eq!(read_todos(&list), Ok(todos(&["a", "c"])));
+        assert_eq!(check_integrity(&list), Integrity::Verified);
+        assert!(!events.exists());
+        assert!(history.is_file());
+        assert_eq!(
+            WARNINGS.with(|warnings| warnings.take()),
+            vec![format!(
+                "Moved the changes in {} into the list, which is back to the text backend",
+                events.display()
+            )]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_encrypt_the_files_kept_next_to_the_list() {
+        let dir = test_dir("encrypt");
+        let list = dir.join(DATABASE_NAME);
+        let journal = dir.join("journal.txt");
+        fs::write(&list, "a,description,❌\n").unwrap();
+        write_list_file(&list, &todos(&["a", "b"])).unwrap();
+        fs::write(list.with_extension(ARCHIVE_EXTENSION), "c,description,❌\n").unwrap();
+        fs::write(
+            list.with_extension(TRASH_EXTENSION),
+            "1760000000,d,description,❌\n",
+        )
+        .unwrap();
+        fs::write(&journal, "journal\n").unwrap();
+        let history = fs::read_to_string(list.with_extension(HISTORY_EXTENSION)).unwrap();
+
+        // The list, its history, archive and trash, and the journal
+        assert_eq!(
+            encrypt_list_files(&list, vec![journal.clone()], PASSPHRASE),
+            Ok(5)
+        );
+
+        for file in [
+            list.clone(),
+            list.with_extension(HISTORY_EXTENSION),
+            list.with_extension(ARCHIVE_EXTENSION),
+            list.with_extension(TRASH_EXTENSION),
+            journal.clone(),
+        ] {
+            assert!(encryption::is_encrypted(&fs::read(&file).unwrap()));
+        }
+        assert_eq!(check_integrity(&list), Integrity::Verified);
+        assert_eq!(read_list_file(&list), Ok(todos(&["a", "b"])));
+        assert_eq!(
+            read_todos(&list.with_extension(ARCHIVE_EXTENSION)),
+            Ok(todos(&["c"]))
+        );
+        assert_eq!(
+            read_file(&list.with_extension(HISTORY_EXTENSION)),
+            Ok(Some(history))
+        );
+        assert_eq!(read_file(&journal), Ok(Some("journal\n".into())));
+
+        assert!(!encrypting_path(&list).exists());
+
+        // Files added next to the list later on are encrypted too
+        quarantine(&list, &["unreadable".into()]).unwrap();
+        assert!(encryption::is_encrypted(
+            &fs::read(list.with_extension(QUARANTINE_EXTENSION)).unwrap()
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_leave_the_files_as_they_were_when_one_cannot_be_encrypted() {
+        let dir = test_dir("encrypt-failure");
+        let list = dir.join(DATABASE_NAME);
+        let journal = dir.join("journal.txt");
+        fs::write(&list, "a,description,❌\n").unwrap();
+        fs::write(&journal, "journal\n").unwrap();
+        // Its encrypted copy cannot be written where a directory is
+        fs::create_dir(encrypting_path(&journal)).unwrap();
+
+        assert_eq!(
+            encrypt_list_files(&list, vec![journal.clone()], PASSPHRASE),
+            Err("Could not write to the database")
+        );
+
+        assert_eq!(fs::read_to_string(&list).unwrap(), "a,description,❌\n");
+        assert_eq!(fs::read_to_string(&journal).unwrap(), "journal\n");
+        assert!(!encrypting_path(&list).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_keep_a_rewritten_list_encrypted() {
+        encryption::set_passphrase(PASSPHRASE);
+        let dir = test_dir("encrypted-rewrite");
+        // A project database kept in a single file, which has no extension
+        let list = dir.join(PROJECT_DATABASE_NAME);
+        write_file(&list, &todos(&["a"])[0].serialise(), true).unwrap();
+
+        write_list_file(&list, &todos(&["a", "b"])).unwrap();
+
+        assert!(encryption::is_encrypted(&fs::read(&list).unwrap()));
+        assert!(encryption::is_encrypted(
+            &fs::read(list.with_extension(HISTORY_EXTENSION)).unwrap()
+        ));
+        assert_eq!(read_list_file(&list), Ok(todos(&["a", "b"])));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_keep_what_other_processes_appended_to_the_event_log() {
+        let dir = test_dir("concurrent-events");
+        let list = dir.join(DATABASE_NAME);
+        let events = list.with_extension(EVENTS_EXTENSION);
+        fs::write(&list, "a,description,❌\nb,description,❌\n").unwrap();
+        append_events(&events, &list, &todos(&["a", "b"]), false).unwrap();
+        let replayed = || events::replay(&read_event_log(&events, true).unwrap()).todos();
+
+        // Read by this process, then another one creates an item
+        remember_log(
+            &events,
+            events::replay(&read_event_log(&events, true).unwrap()),
+        );
+        append_file(&events, "1700000000 created 2 c,description,❌\n", false).unwrap();
+
+        append_events(&events, &list, &todos(&["a"]), false).unwrap();
+        assert_eq!(replayed(), todos(&["a", "c"]));
+
+        let mut done = todos(&["a"]);
+        done[0].done = "✅".into();
+        append_events(&events, &list, &done, false).unwrap();
+        done.push(todos(&["d"])[0].clone());
+        append_events(&events, &list, &done, false).unwrap();
+
+        let records = read_event_log(&events, false).unwrap();
+        assert_eq!(
+            events::replay(&records)
+                .items
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<u64>>(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(replayed()[1].title, "c");
+        assert!(replayed()[0].is_done());
+        // Only the changes made, with the ids of the items they were made to
+        let changes: Vec<&events::Event> =
+            records[1..].iter().map(|record| &record.event).collect();
+        assert!(matches!(
+            changes.as_slice(),
+            [
+                events::Event::Created { id: 2, .. },
+                events::Event::Deleted { id: 1 },
+                events::Event::StatusChanged { id: 0, .. },
+                events::Event::Created { id: 3, .. }
+            ]
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_check_the_list_against_its_stored_checksum() {
+        let dir = test_dir("checksum");
+        let list = dir.join(DATABASE_NAME);
+
+        // Lists written before checksums were stored are taken as they are
+        fs::write(&list, "a,description,❌\n").unwrap();
+        assert_eq!(check_integrity(&list), Integrity::Verified);
+
+        write_list_file(&list, &todos(&["a", "b"])).unwrap();
+        assert_eq!(
+            fs::read_to_string(list.with_extension(CHECKSUM_EXTENSION)).unwrap(),
+            checksum(&fs::read(&list).unwrap())
+        );
+        assert_eq!(check_integrity(&list), Integrity::Verified);
+
+        fs::write(&list, "a,description,✅\nb,description,❌\n").unwrap();
+        assert_eq!(check_integrity(&list), Integrity::Modified);
+        warn_about_integrity(&list);
+
+        fs::write(&list, "a,descr").unwrap();
+        store_checksum(&list).unwrap();
+        fs::write(&list, "a,de").unwrap();
+        assert_eq!(check_integrity(&list), Integrity::Truncated);
+        warn_about_integrity(&list);
+
+        assert_eq!(
+            WARNINGS.with(|warnings| warnings.take()),
+            vec![
+                format!(
+                    "{} was changed outside of terminal-todo since it was last written",
+                    list.display()
+                ),
+                format!(
+                    "{} is shorter than when it was last written and may have been cut off, see backup list",
+                    list.display()
+                )
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_tell_changed_lists_from_truncated_ones() {
+        let contents = "a,description,❌\nb,description,❌\n";
+        let recorded = checksum(contents.as_bytes());
 
-        Ok(todo_items)
+        assert_eq!(
+            compare_checksum(contents.as_bytes(), &recorded),
+            Integrity::Verified
+        );
+        assert_eq!(
+            compare_checksum(
+                b"a,description,\xe2\x9d\x8c\nc,description,\xe2\x9d\x8c\n",
+                &recorded
+            ),
+            Integrity::Modified
+        );
+        // A whole line taken out is an edit, half of one a cut
+        assert_eq!(
+            compare_checksum(
+                &contents.as_bytes()[..contents.find('b').unwrap()],
+                &recorded
+            ),
+            Integrity::Modified
+        );
+        assert_eq!(
+            compare_checksum(&contents.as_bytes()[..20], &recorded),
+            Integrity::Truncated
+        );
+        assert_eq!(compare_checksum(b"", &recorded), Integrity::Truncated);
     }
 }
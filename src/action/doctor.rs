@@ -0,0 +1,173 @@
+use crate::{output::Json, todo::ToDo};
+
+use super::{Action, Confirm};
+
+/// A line of the list that cannot be read, with why and what repairing it
+/// does.
+#[derive(PartialEq, Debug)]
+pub struct Diagnosis {
+    pub line: usize,
+    pub problems: Vec<&'static str>,
+    pub salvaged: Option<ToDo>,
+}
+
+impl Diagnosis {
+    fn repair(&self) -> &'static str {
+        match (&self.salvaged, self.problems.as_slice()) {
+            (Some(_), _) => "salvaged",
+            (None, ["is empty"]) => "removed",
+            (None, _) => "quarantined",
+        }
+    }
+}
+
+/// Checks every line of a list, giving the items that can be kept as they
+/// are or salvaged and the lines that cannot be read.
+pub fn diagnose(lines: &[String]) -> (Vec<ToDo>, Vec<Diagnosis>) {
+    let mut todos: Vec<ToDo> = vec![];
+    let mut diagnoses: Vec<Diagnosis> = vec![];
+
+    for (index, line) in lines.iter().enumerate() {
+        let (todo, problems) = ToDo::salvage(line);
+
+        if let Some(todo) = todo.clone() {
+            todos.push(todo);
+        }
+        if !problems.is_empty() {
+            diagnoses.push(Diagnosis {
+                line: index + 1,
+                problems,
+                salvaged: todo,
+            });
+        }
+    }
+
+    (todos, diagnoses)
+}
+
+impl Action<'_> {
+    /// Reports the lines of the list that cannot be read and, with
+    /// `--repair`, keeps what can be read of them and sets the rest aside.
+    pub(super) fn doctor(&mut self) -> Result<(), &'static str> {
+        let repair = match self.arguments.as_slice() {
+            [] => false,
+            [flag] if flag == "--repair" => true,
+            _ => return Err("Doctor should be used as doctor or doctor --repair"),
+        };
+
+        let database = self.database.expect("Database could not be found");
+        let lines: Vec<String> = database.read_list_lines()?;
+        let (todos, diagnoses) = diagnose(&lines);
+
+        if diagnoses.is_empty() {
+            self.log_text(&format!(
+                "No problems found, all {} ToDo item{} can be read",
+                todos.len(),
+                if todos.len() == 1 { "" } else { "s" }
+            ));
+            return self.log_json(vec![], Json::Array);
+        }
+
+        for diagnosis in diagnoses.iter() {
+            self.log_text(&format!(
+                "Line {} {}, it can be {}",
+                diagnosis.line,
+                diagnosis.problems.join(" and "),
+                diagnosis.repair()
+            ));
+        }
+
+        let problems: Vec<Json> = diagnoses
+            .iter()
+            .map(|diagnosis| {
+                Json::object(vec![
+                    ("line", Json::Number(diagnosis.line as i64)),
+                    (
+                        "problems",
+                        Json::Array(diagnosis.problems.iter().map(|p| Json::string(p)).collect()),
+                    ),
+                    ("repair", Json::string(diagnosis.repair())),
+                ])
+            })
+            .collect();
+
+        let line_count = diagnoses.len();
+        let lines_text = format!(
+            "{line_count} line{}",
+            if line_count == 1 { "" } else { "s" }
+        );
+
+        if !repair {
+            self.log_text(&format!("Run doctor --repair to fix {lines_text}"));
+            return self.log_json(problems, Json::Array);
+        }
+
+        if Confirm::configured()?.asks(line_count)
+            && !self.confirm(&format!("Repair {lines_text}?"))
+        {
+            self.log_text("Nothing was repaired");
+            return self.log_json(problems, Json::Array);
+        }
+
+        let quarantined: Vec<String> = diagnoses
+            .iter()
+            .filter(|diagnosis| diagnosis.repair() == "quarantined")
+            .map(|diagnosis| lines[diagnosis.line - 1].clone())
+            .collect();
+        let quarantined_count = quarantined.len();
+
+        database.repair_list(todos, quarantined)?;
+
+        self.log_text(&format!(
+            "Repaired {lines_text}, {quarantined_count} set aside in the quarantine file"
+        ));
+
+        self.log_json(problems, Json::Array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::{database::Database, test_database::TestDatabase},
+        log_wrapper::LogWrapper,
+    };
+
+    #[test]
+    fn should_report_and_repair_unreadable_lines() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a"]);
+        *database.unreadable_lines.borrow_mut() = vec![
+            "b,description,✅,due:soon".into(),
+            "".into(),
+            "garbage".into(),
+        ];
+
+        let mut doctor_action = Action::new("doctor", &mut logger, &database).unwrap();
+        assert_eq!(doctor_action.execute_action(), Ok(()));
+        assert_eq!(database.read_list_lines().unwrap().len(), 4);
+
+        let mut doctor_action = Action::new("doctor", &mut logger, &database).unwrap();
+        doctor_action.arguments = vec!["--repair".into()];
+        assert_eq!(doctor_action.execute_action(), Ok(()));
+        assert_eq!(
+            database.read_list_lines(),
+            Ok(vec![
+                "a,description,❌".to_string(),
+                "b,description,✅".to_string()
+            ])
+        );
+        assert_eq!(*database.quarantined.borrow(), vec!["garbage"]);
+
+        let output = String::from_utf8(logger.std_writer).unwrap();
+        assert!(output.starts_with(
+            "Line 2 has a due date that is not YYYY-MM-DD, it can be salvaged
+Line 3 is empty, it can be removed
+Line 4 is missing the title, description or status, it can be quarantined
+Run doctor --repair to fix 3 lines
+"
+        ));
+        assert!(output.ends_with("Repaired 3 lines, 1 set aside in the quarantine file\n"));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    env, fs,
+    sync::{Mutex, OnceLock},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+
+use crate::config;
+
+use super::Action;
+
+/// What encrypted files start with, followed by the salt the key was derived
+/// with, the nonce and the encrypted contents.
+const MAGIC: &[u8] = b"terminal-todo encrypted 1\n";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+
+const WRONG_PASSPHRASE: &str = "Wrong passphrase, the encrypted list could not be read";
+
+/// The passphrase in use once it was asked for, so that it is only asked
+/// once per run.
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+/// Keys already derived from the passphrase in use, by salt, as deriving one
+/// is slow on purpose.
+static KEYS: Mutex<Vec<([u8; SALT_LENGTH], [u8; 32])>> = Mutex::new(Vec::new());
+/// The salt of the files written in this run, so that one key does for all.
+static WRITE_SALT: OnceLock<[u8; SALT_LENGTH]> = OnceLock::new();
+
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// The passphrase given by the `TODO_PASSPHRASE` environment variable or
+/// the file at the `encryption.key_file` setting, if any.
+fn configured_passphrase() -> Option<Result<String, &'static str>> {
+    if let Ok(passphrase) = env::var("TODO_PASSPHRASE") {
+        return Some(Ok(passphrase));
+    }
+
+    config::current()
+        .get("encryption.key_file")
+        .map(|key_file| {
+            fs::read_to_string(key_file)
+                .map(|passphrase| passphrase.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|_| "Could not read the key file given by encryption.key_file")
+        })
+}
+
+/// The passphrase of the encrypted list, asked for when it is not
+/// configured.
+fn passphrase() -> Result<String, &'static str> {
+    let mut current = PASSPHRASE.lock().unwrap();
+
+    if current.is_none() {
+        let passphrase = match configured_passphrase() {
+            Some(passphrase) => passphrase?,
+            None => rpassword::prompt_password("Passphrase of the list: ").map_err(|_| {
+                "A passphrase is needed to read the encrypted list, give it with TODO_PASSPHRASE or encryption.key_file"
+            })?,
+        };
+        *current = Some(passphrase);
+    }
+
+    Ok(current.clone().unwrap())
+}
+
+/// Uses `passphrase` for the files read and written from now on.
+pub fn set_passphrase(passphrase: &str) {
+    *PASSPHRASE.lock().unwrap() = Some(passphrase.into());
+    KEYS.lock().unwrap().clear();
+}
+
+/// Asks for a new passphrase twice, unless it is given by `variable`.
+fn new_passphrase(variable: &str) -> Result<String, &'static str> {
+    let passphrase = match env::var(variable) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = rpassword::prompt_password("New passphrase: ")
+                .map_err(|_| "Could not read the new passphrase")?;
+            let repeated = rpassword::prompt_password("New passphrase again: ")
+                .map_err(|_| "Could not read the new passphrase")?;
+
+            if passphrase != repeated {
+                return Err("The passphrases are not the same");
+            }
+            passphrase
+        }
+    };
+
+    match passphrase.is_empty() {
+        true => Err("The passphrase cannot be empty"),
+        false => Ok(passphrase),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; SALT_LENGTH]) -> Result<[u8; 32], &'static str> {
+    let mut key = [0u8; 32];
+
+    match Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key) {
+        Ok(_) => Ok(key),
+        Err(_) => Err("Could not derive a key from the passphrase"),
+    }
+}
+
+fn key_for(salt: &[u8; SALT_LENGTH]) -> Result<[u8; 32], &'static str> {
+    if let Some((_, key)) = KEYS.lock().unwrap().iter().find(|(other, _)| other == salt) {
+        return Ok(*key);
+    }
+
+    let key = derive_key(&passphrase()?, salt)?;
+    KEYS.lock().unwrap().push((*salt, key));
+    Ok(key)
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], &'static str> {
+    let mut bytes = [0u8; N];
+
+    match getrandom::getrandom(&mut bytes) {
+        Ok(_) => Ok(bytes),
+        Err(_) => Err("Could not get random bytes to encrypt with"),
+    }
+}
+
+/// Encrypts `contents` with a key derived from `key` and `salt`.
+fn seal(key: &[u8; 32], salt: &[u8; SALT_LENGTH], contents: &str) -> Result<Vec<u8>, &'static str> {
+    let nonce: [u8; NONCE_LENGTH] = random_bytes()?;
+
+    let encrypted = XChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(XNonce::from_slice(&nonce), contents.as_bytes())
+        .map_err(|_| "Could not encrypt the list")?;
+
+    Ok([MAGIC, salt, &nonce, &encrypted].concat())
+}
+
+/// Decrypts what `seal` wrote, with the key derived by `key_for` from the
+/// salt it was written with.
+fn open<F>(encrypted: &[u8], key_for: F) -> Result<String, &'static str>
+where
+    F: FnOnce(&[u8; SALT_LENGTH]) -> Result<[u8; 32], &'static str>,
+{
+    const CORRUPTED: &str = "Encrypted file is corrupted, could not read data";
+
+    let rest = &encrypted[MAGIC.len().min(encrypted.len())..];
+    if rest.len() < SALT_LENGTH + NONCE_LENGTH {
+        return Err(CORRUPTED);
+    }
+
+    let (salt, rest) = rest.split_at(SALT_LENGTH);
+    let (nonce, contents) = rest.split_at(NONCE_LENGTH);
+    let key = key_for(salt.try_into().unwrap())?;
+
+    let decrypted = XChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(XNonce::from_slice(nonce), contents)
+        .map_err(|_| WRONG_PASSPHRASE)?;
+
+    String::from_utf8(decrypted).map_err(|_| CORRUPTED)
+}
+
+/// Encrypts `contents` with the passphrase in use.
+pub fn encrypt(contents: &str) -> Result<Vec<u8>, &'static str> {
+    let salt = match WRITE_SALT.get() {
+        Some(salt) => *salt,
+        None => {
+            let salt = random_bytes()?;
+            *WRITE_SALT.get_or_init(|| salt)
+        }
+    };
+
+    seal(&key_for(&salt)?, &salt, contents)
+}
+
+/// Decrypts a file written by `encrypt`, with the passphrase in use.
+pub fn decrypt(encrypted: &[u8]) -> Result<String, &'static str> {
+    open(encrypted, key_for)
+}
+
+impl Action<'_> {
+    /// Encrypts every file kept for the list in use, with a passphrase from
+    /// `TODO_PASSPHRASE`, `encryption.key_file` or asked for.
+    pub(super) fn encrypt(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+
+        if database.is_encrypted()? {
+            return Err("The list is already encrypted, see change-passphrase");
+        }
+
+        let passphrase = match configured_passphrase() {
+            Some(passphrase) => passphrase?,
+            None => new_passphrase("TODO_PASSPHRASE")?,
+        };
+        if passphrase.is_empty() {
+            return Err("The passphrase cannot be empty");
+        }
+
+        let file_count = database.encrypt_list(&passphrase)?;
+
+        self.log_text(&format!(
+            "Encrypted {file_count} file{} of the list",
+            if file_count == 1 { "" } else { "s" }
+        ));
+
+        Ok(())
+    }
+
+    /// Encrypts the list again with a new passphrase, from
+    /// `TODO_NEW_PASSPHRASE` or asked for.
+    pub(super) fn change_passphrase(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+
+        if !database.is_encrypted()? {
+            return Err("The list is not encrypted, see encrypt");
+        }
+
+        // Makes sure the current passphrase is right before asking for another
+        database.read_items()?;
+        let passphrase = new_passphrase("TODO_NEW_PASSPHRASE")?;
+
+        let file_count = database.encrypt_list(&passphrase)?;
+
+        self.log_text(&format!(
+            "Changed the passphrase of {file_count} file{} of the list",
+            if file_count == 1 { "" } else { "s" }
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_decrypt_with_the_right_passphrase() {
+        let salt = [7u8; SALT_LENGTH];
+        let key = derive_key("correct horse", &salt).unwrap();
+
+        let encrypted = seal(&key, &salt, "title,description,❌\n").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted
+            .windows("title".len())
+            .any(|window| window == b"title"));
+
+        assert_eq!(
+            open(&encrypted, |salt| derive_key("correct horse", salt)),
+            Ok("title,description,❌\n".to_string())
+        );
+        assert_eq!(
+            open(&encrypted, |salt| derive_key("battery staple", salt)),
+            Err(WRONG_PASSPHRASE)
+        );
+        assert_eq!(
+            open(&encrypted[..MAGIC.len() + 3], |salt| derive_key(
+                "correct horse",
+                salt
+            )),
+            Err("Encrypted file is corrupted, could not read data")
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::todo::ToDo;
+
+/// A change to a list as kept by the `events` backend. Items are told apart
+/// by ids that are never reused, so that the log can tell the history of each
+/// of them.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Event {
+    Created {
+        id: u64,
+        todo: ToDo,
+    },
+    Edited {
+        id: u64,
+        todo: ToDo,
+    },
+    StatusChanged {
+        id: u64,
+        todo: ToDo,
+    },
+    Deleted {
+        id: u64,
+    },
+    /// The whole list, which replaces everything before it when replaying,
+    /// with the id the next created item gets.
+    Snapshot {
+        items: Vec<(u64, ToDo)>,
+        next_id: u64,
+    },
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Record {
+    pub timestamp: u64,
+    pub event: Event,
+}
+
+/// The list made by replaying a log, with the ids of its items and the id
+/// the next created item gets.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Replayed {
+    pub items: Vec<(u64, ToDo)>,
+    pub next_id: u64,
+}
+
+impl Record {
+    /// Writes the record as lines of `<timestamp> <event> <id> <item>`, a
+    /// snapshot being a `<timestamp> snapshot <count> <next id>` line followed
+    /// by one `<id> <item>` line per item.
+    pub fn serialise(&self) -> String {
+        let timestamp = self.timestamp;
+
+        match &self.event {
+            Event::Created { id, todo } => format!("{timestamp} created {id} {}", todo.serialise()),
+            Event::Edited { id, todo } => format!("{timestamp} edited {id} {}", todo.serialise()),
+            Event::StatusChanged { id, todo } => {
+                format!("{timestamp} status {id} {}", todo.serialise())
+            }
+            Event::Deleted { id } => format!("{timestamp} deleted {id}\n"),
+            Event::Snapshot { items, next_id } => {
+                let mut snapshot = format!("{timestamp} snapshot {} {next_id}\n", items.len());
+                for (id, todo) in items {
+                    snapshot += &format!("{id} {}", todo.serialise());
+                }
+                snapshot
+            }
+        }
+    }
+}
+
+/// Reads a log written by `Record::serialise`.
+pub fn parse_log(log: &str) -> Result<Vec<Record>, &'static str> {
+    const CORRUPTED: &str = "Event log is corrupted, could not read data";
+
+    let mut records: Vec<Record> = vec![];
+    let mut lines = log.lines();
+
+    while let Some(line) = lines.next() {
+        let mut parts = line.splitn(4, ' ');
+        let (timestamp, kind, id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(timestamp), Some(kind), Some(id)) => (timestamp, kind, id),
+            _ => return Err(CORRUPTED),
+        };
+        let timestamp = timestamp.parse::<u64>().map_err(|_| CORRUPTED)?;
+        let number = id.parse::<u64>().map_err(|_| CORRUPTED)?;
+        let rest = parts.next();
+
+        let event = match (kind, rest) {
+            ("created", Some(todo)) => Event::Created {
+                id: number,
+                todo: ToDo::deserialise(todo)?,
+            },
+            ("edited", Some(todo)) => Event::Edited {
+                id: number,
+                todo: ToDo::deserialise(todo)?,
+            },
+            ("status", Some(todo)) => Event::StatusChanged {
+                id: number,
+                todo: ToDo::deserialise(todo)?,
+            },
+            ("deleted", None) => Event::Deleted { id: number },
+            ("snapshot", Some(next_id)) => {
+                let mut items: Vec<(u64, ToDo)> = vec![];
+
+                for _ in 0..number {
+                    let (id, todo_str) = match lines.next().and_then(|line| line.split_once(' ')) {
+                        Some(item) => item,
+                        None => return Err(CORRUPTED),
+                    };
+                    items.push((
+                        id.parse::<u64>().map_err(|_| CORRUPTED)?,
+                        ToDo::deserialise(todo_str)?,
+                    ));
+                }
+
+                Event::Snapshot {
+                    items,
+                    next_id: next_id.parse::<u64>().map_err(|_| CORRUPTED)?,
+                }
+            }
+            _ => return Err(CORRUPTED),
+        };
+
+        records.push(Record { timestamp, event });
+    }
+
+    Ok(records)
+}
+
+/// Applies every record in order to get the current list.
+pub fn replay(records: &[Record]) -> Replayed {
+    let mut replayed = Replayed::default();
+
+    for record in records {
+        replayed.apply(&record.event);
+    }
+
+    replayed
+}
+
+impl Replayed {
+    pub fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Created { id, todo } => {
+                self.items.push((*id, todo.clone()));
+                self.next_id = self.next_id.max(id + 1);
+            }
+            Event::Edited { id, todo } | Event::StatusChanged { id, todo } => {
+                if let Some((_, item)) = self.items.iter_mut().find(|(item_id, _)| item_id == id) {
+                    *item = todo.clone();
+                }
+            }
+            Event::Deleted { id } => self.items.retain(|(item_id, _)| item_id != id),
+            Event::Snapshot { items, next_id } => {
+                self.items = items.clone();
+                self.next_id = self.next_id.max(*next_id);
+            }
+        }
+    }
+
+    pub fn todos(&self) -> Vec<ToDo> {
+        self.items.iter().map(|(_, todo)| todo.clone()).collect()
+    }
+}
+
+/// How many records were written since the last snapshot, which is how many
+/// have to be replayed on top of it.
+pub fn records_since_snapshot(records: &[Record]) -> usize {
+    records
+        .iter()
+        .rev()
+        .take_while(|record| !matches!(record.event, Event::Snapshot { .. }))
+        .count()
+}
+
+/// The events that turn the replayed list into `todos`. Items are matched by
+/// position when some of them changed but not their number, else by their
+/// content. Changes that are neither edits, creations at the end nor
+/// deletions, like reordering, are recorded as a snapshot.
+pub fn events_between(replayed: &Replayed, todos: &[ToDo]) -> Vec<Event> {
+    let items = assign_ids(replayed, todos);
+    let reordered = items.iter().all(|(id, _)| *id < replayed.next_id);
+
+    let events: Vec<Event> = match replayed.items.len() == todos.len() && !reordered {
+        true => replayed
+            .items
+            .iter()
+            .zip(todos)
+            .filter(|((_, before), after)| before.serialise() != after.serialise())
+            .map(|((id, before), after)| {
+                let status_only = ToDo {
+                    done: after.done.clone(),
+                    updated: after.updated,
+                    ..before.clone()
+                }
+                .serialise()
+                    == after.serialise();
+
+                let (id, todo) = (*id, after.clone());
+                match status_only {
+                    true => Event::StatusChanged { id, todo },
+                    false => Event::Edited { id, todo },
+                }
+            })
+            .collect(),
+        false => replayed
+            .items
+            .iter()
+            .filter(|(id, _)| !items.iter().any(|(item_id, _)| item_id == id))
+            .map(|(id, _)| Event::Deleted { id: *id })
+            .chain(
+                items
+                    .iter()
+                    .filter(|(id, _)| *id >= replayed.next_id)
+                    .map(|(id, todo)| Event::Created {
+                        id: *id,
+                        todo: todo.clone(),
+                    }),
+            )
+            .collect(),
+    };
+
+    let mut result = replayed.clone();
+    for event in events.iter() {
+        result.apply(event);
+    }
+
+    let same = result.items.len() == todos.len()
+        && result
+            .items
+            .iter()
+            .zip(todos)
+            .all(|((_, todo), other)| todo.serialise() == other.serialise());
+
+    match same {
+        true => events,
+        false => vec![snapshot(replayed, todos)],
+    }
+}
+
+/// Moves `events`, worked out against `known`, the list as it was read, onto
+/// `latest`, the list as the log has it now, which other processes may have
+/// appended to since. Created items get ids that are not taken yet, changes
+/// to items deleted since are dropped and a snapshot keeps the items created
+/// since. Also gives the list as the writer sees it with the events applied.
+pub fn rebase(events: Vec<Event>, known: &Replayed, latest: &Replayed) -> (Vec<Event>, Replayed) {
+    let mut next_id = latest.next_id.max(known.next_id);
+    let mut own = known.clone();
+    let mut rebased: Vec<Event> = vec![];
+
+    for event in events {
+        match event {
+            Event::Created { todo, .. } => {
+                let event = Event::Created { id: next_id, todo };
+                next_id += 1;
+                own.apply(&event);
+                rebased.push(event);
+            }
+            Event::Edited { id, .. } | Event::StatusChanged { id, .. } | Event::Deleted { id } => {
+                own.apply(&event);
+                if latest.items.iter().any(|(item_id, _)| *item_id == id) {
+                    rebased.push(event);
+                }
+            }
+            Event::Snapshot { items, .. } => {
+                let items: Vec<(u64, ToDo)> = items
+                    .into_iter()
+                    .map(|(id, todo)| match id >= known.next_id {
+                        true => {
+                            next_id += 1;
+                            (next_id - 1, todo)
+                        }
+                        false => (id, todo),
+                    })
+                    .collect();
+                let created_since = latest
+                    .items
+                    .iter()
+                    .filter(|(id, _)| *id >= known.next_id)
+                    .cloned();
+
+                own.apply(&Event::Snapshot {
+                    items: items.clone(),
+                    next_id,
+                });
+                rebased.push(Event::Snapshot {
+                    items: items.into_iter().chain(created_since).collect(),
+                    next_id,
+                });
+            }
+        }
+    }
+
+    own.next_id = own.next_id.max(next_id);
+    (rebased, own)
+}
+
+/// A snapshot of `todos`, keeping the ids of the replayed items they match.
+pub fn snapshot(replayed: &Replayed, todos: &[ToDo]) -> Event {
+    let items = assign_ids(replayed, todos);
+    let next_id = items
+        .iter()
+        .map(|(id, _)| id + 1)
+        .fold(replayed.next_id, u64::max);
+
+    Event::Snapshot { items, next_id }
+}
+
+/// The part of a log starting at its last snapshot, which is all that is
+/// needed to get the current list. The item lines of a snapshot are skipped
+/// over, so that an item whose title starts with "snapshot" is not taken for
+/// one.
+pub fn since_last_snapshot(log: &str) -> &str {
+    let mut start = 0;
+    let mut offset = 0;
+    let mut lines = log.split_inclusive('\n');
+
+    while let Some(line) = lines.next() {
+        let parts: Vec<&str> = line.trim_end().split(' ').collect();
+
+        if let [timestamp, "snapshot", count, next_id] = parts.as_slice() {
+            if let (Ok(_), Ok(count), Ok(_)) = (
+                timestamp.parse::<u64>(),
+                count.parse::<usize>(),
+                next_id.parse::<u64>(),
+            ) {
+                start = offset;
+                offset += line.len();
+                for item in lines.by_ref().take(count) {
+                    offset += item.len();
+                }
+                continue;
+            }
+        }
+
+        offset += line.len();
+    }
+
+    &log[start..]
+}
+
+/// Gives each of `todos` the id of an unused replayed item with the same
+/// content, or a new id.
+fn assign_ids(replayed: &Replayed, todos: &[ToDo]) -> Vec<(u64, ToDo)> {
+    let mut unused: Vec<(u64, String)> = replayed
+        .items
+        .iter()
+        .map(|(id, todo)| (*id, todo.serialise()))
+        .collect();
+    let mut next_id = replayed.next_id;
+
+    todos
+        .iter()
+        .map(|todo| {
+            let serialised = todo.serialise();
+
+            let id = match unused.iter().position(|(_, other)| *other == serialised) {
+                Some(position) => unused.remove(position).0,
+                None => {
+                    next_id += 1;
+                    next_id - 1
+                }
+            };
+
+            (id, todo.clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::test_database::todos;
+
+    fn record(events: Vec<Event>) -> Vec<Record> {
+        events
+            .into_iter()
+            .map(|event| Record {
+                timestamp: 1_700_000_000,
+                event,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_derive_events_that_replay_to_the_list() {
+        let mut replayed = Replayed::default();
+
+        let mut changes: Vec<Vec<ToDo>> = vec![
+            todos(&["a", "b"]),
+            todos(&["a", "b", "c"]),
+            todos(&["a", "c"]),
+            todos(&["c", "a"]),
+        ];
+        let mut done = todos(&["c", "a"]);
+        done[1].done = "✅".into();
+        changes.push(done);
+
+        let mut log = String::new();
+
+        for todos in changes.iter() {
+            for record in record(events_between(&replayed, todos)) {
+                log += &record.serialise();
+                replayed.apply(&record.event);
+            }
+        }
+
+        let records = parse_log(&log).unwrap();
+        assert_eq!(replay(&records), replayed);
+        assert_eq!(replayed.todos(), changes[4]);
+
+        let kinds: Vec<&str> = records
+            .iter()
+            .map(|record| match record.event {
+                Event::Created { .. } => "created",
+                Event::Edited { .. } => "edited",
+                Event::StatusChanged { .. } => "status",
+                Event::Deleted { .. } => "deleted",
+                Event::Snapshot { .. } => "snapshot",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec!["created", "created", "created", "deleted", "snapshot", "status"]
+        );
+        assert_eq!(records_since_snapshot(&records), 1);
+        // Ids follow the items through the snapshot
+        assert_eq!(replayed.items[1].0, 0);
+    }
+
+    #[test]
+    fn should_replay_from_the_last_snapshot() {
+        let log = "1 created 0 a,b,❌\n2 snapshot 1 2\n1 c,d,❌\n3 created 2 e,f,❌\n";
+        let since = since_last_snapshot(log);
+        assert!(since.starts_with("2 snapshot"));
+
+        let replayed = replay(&parse_log(since).unwrap());
+        assert_eq!(replayed, replay(&parse_log(log).unwrap()));
+        assert_eq!(replayed.next_id, 3);
+        assert_eq!(replayed.items.len(), 2);
+    }
+
+    #[test]
+    fn should_not_take_an_item_named_snapshot_for_one() {
+        let records = [
+            Record {
+                timestamp: 1,
+                event: Event::Snapshot {
+                    items: vec![(7, todos(&["snapshot 1 2"])[0].clone())],
+                    next_id: 8,
+                },
+            },
+            Record {
+                timestamp: 2,
+                event: Event::Created {
+                    id: 8,
+                    todo: todos(&["b"])[0].clone(),
+                },
+            },
+        ];
+        let log: String = records.iter().map(Record::serialise).collect();
+
+        assert_eq!(since_last_snapshot(&log), log);
+        assert_eq!(
+            replay(&parse_log(since_last_snapshot(&log)).unwrap()).todos(),
+            todos(&["snapshot 1 2", "b"])
+        );
+    }
+
+    #[test]
+    fn should_keep_items_created_since_when_rebasing() {
+        let known = replay(&[Record {
+            timestamp: 1,
+            event: snapshot(&Replayed::default(), &todos(&["a", "b"])),
+        }]);
+        let mut latest = known.clone();
+        latest.apply(&Event::Created {
+            id: 2,
+            todo: todos(&["c"])[0].clone(),
+        });
+
+        // Reordering is only told by a snapshot
+        let reordered = todos(&["b", "a"]);
+        let (events, own) = rebase(events_between(&known, &reordered), &known, &latest);
+        latest.apply(&events[0]);
+
+        assert_eq!(latest.todos(), todos(&["b", "a", "c"]));
+        assert_eq!(own.todos(), reordered);
+
+        let (events, _) = rebase(
+            events_between(&own, &todos(&["b", "a", "d"])),
+            &own,
+            &latest,
+        );
+        assert_eq!(
+            events,
+            vec![Event::Created {
+                id: 3,
+                todo: todos(&["d"])[0].clone()
+            }]
+        );
+    }
+
+    #[test]
+    fn should_report_corrupted_logs() {
+        assert_eq!(
+            parse_log("1700000000 snapshot 2 2\n0 a,b,❌\n"),
+            Err("Event log is corrupted, could not read data")
+        );
+        assert_eq!(
+            parse_log("1700000000 renamed 1 a,b,❌\n"),
+            Err("Event log is corrupted, could not read data")
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::fs;
+
+use crate::{date, filter::Filter, output::Json, todo::ToDo};
+
+use super::{
+    csv,
+    database::{self, Database},
+    events, ical, taskwarrior, todotxt, Action,
+};
+
+/// Writes items as a Markdown task list.
+fn markdown(todos: &[&ToDo]) -> String {
+    todos
+        .iter()
+        .map(|todo| {
+            format!(
+                "- [{}] {}\n",
+                if todo.is_done() { "x" } else { " " },
+                todo.title.replace('\n', " ")
+            )
+        })
+        .collect()
+}
+
+/// The UIDs other apps know the items by. Items imported from Taskwarrior
+/// keep their uuid, and others the id recorded in their history, so that they
+/// keep their UID when edited. Those without one, like items of a list
+/// changed outside of terminal-todo, get a UID made from when they were
+/// created and their title.
+fn uids(database: &dyn Database, todos: &[ToDo]) -> Vec<String> {
+    let list = database::current_list();
+    let replayed = events::replay(&database.change_records().unwrap_or_default());
+
+    todos
+        .iter()
+        .enumerate()
+        .map(
+            |(index, todo)| match (&todo.uuid, replayed.items.get(index)) {
+                (Some(uuid), _) => uuid.clone(),
+                (None, Some((id, recorded))) if recorded.serialise() == todo.serialise() => {
+                    format!("{id}-{list}@terminal-todo")
+                }
+                _ => format!(
+                    "{}-{:016x}-{list}@terminal-todo",
+                    todo.created.unwrap_or_default(),
+                    database::fnv1a(todo.title.as_bytes())
+                ),
+            },
+        )
+        .collect()
+}
+
+impl Action<'_> {
+    /// `export --format <format> [--output <file>] [filter]` writes the items
+    /// matching the filter in another format, to the file or to stdout. With
+    /// JSON output, what goes to stdout is the export in a JSON document.
+    pub(super) fn export(&mut self) -> Result<(), &'static str> {
+        let mut format: Option<String> = None;
+        let mut path: Option<String> = None;
+        let mut filter_arguments: Vec<String> = vec![];
+        let mut arguments = self.arguments.clone().into_iter();
+
+        while let Some(argument) = arguments.next() {
+            let (option, inline_value) = match argument.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value.into())),
+                _ => (argument.as_str(), None),
+            };
+
+            let value = match option {
+                "--format" | "--output" | "-o" => match inline_value.or_else(|| arguments.next()) {
+                    Some(value) => Some(value),
+                    None => return Err("Option requires a value"),
+                },
+                _ => None,
+            };
+
+            match option {
+                "--format" => format = value,
+                "--output" | "-o" => path = value,
+                _ => filter_arguments.push(argument),
+            }
+        }
+
+        let format = match format {
+            Some(format) => format,
+            None => return Err(
+                "Export should be used as export --format todotxt|markdown|csv|ical|taskwarrior",
+            ),
+        };
+
+        let filter: Filter = match Filter::parse(&filter_arguments.join(" ")) {
+            Ok(filter) => filter,
+            Err(err) => {
+                self.log_error_detail(&format!("Invalid filter: {err}"));
+                return Err("Could not parse the list filter");
+            }
+        };
+
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let matching_todos: Vec<&ToDo> = todos.iter().filter(|todo| filter.matches(todo)).collect();
+        let count = matching_todos.len();
+        let with_uids = || -> Vec<(String, &ToDo)> {
+            uids(database, &todos)
+                .into_iter()
+                .zip(todos.iter())
+                .filter(|(_, todo)| filter.matches(todo))
+                .collect()
+        };
+
+        let contents = match format.as_str() {
+            "todotxt" => matching_todos
+                .iter()
+                .map(|todo| todotxt::serialise(todo))
+                .collect(),
+            "markdown" => markdown(&matching_todos),
+            "csv" => {
+                let matching_todos: Vec<ToDo> = matching_todos.into_iter().cloned().collect();
+                csv::serialise(&matching_todos)
+            }
+            "ical" => ical::serialise(&with_uids(), date::now()),
+            "taskwarrior" => {
+                let tasks: Vec<(String, &ToDo)> = with_uids()
+                    .into_iter()
+                    .map(|(uid, todo)| match &todo.uuid {
+                        Some(uuid) => (uuid.clone(), todo),
+                        None => (taskwarrior::uuid(&uid), todo),
+                    })
+                    .collect();
+                taskwarrior::serialise(&tasks)
+            }
+            _ => return Err("Export can only be to todotxt, markdown, csv, ical or taskwarrior"),
+        };
+
+        let path = match path {
+            Some(path) => path,
+            None if self.output.is_json() => {
+                return self.log_json_value(Json::object(vec![
+                    ("format", Json::string(&format)),
+                    ("count", Json::Number(count as i64)),
+                    ("contents", Json::string(&contents)),
+                ]));
+            }
+            None => {
+                self.logger.as_mut().unwrap().log_std(&contents).unwrap();
+                return Ok(());
+            }
+        };
+
+        if fs::write(&path, contents).is_err() {
+            return Err("Could not write the exported file");
+        }
+
+        self.log_text(&format!(
+            "Exported {count} ToDo item{} to {path}",
+            if count == 1 { "" } else { "s" }
+        ));
+
+        self.log_json_value(Json::object(vec![
+            ("format", Json::string(&format)),
+            ("file", Json::string(&path)),
+            ("count", Json::Number(count as i64)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{action::test_database::TestDatabase, log_wrapper::LogWrapper, output::OutputMode};
+
+    #[test]
+    fn should_export_matching_items_as_markdown() {
+        let mut done = ToDo::new("Pay rent".into(), String::new());
+        done.done = "✅".into();
+        let mut tagged = ToDo::new("Fix login".into(), String::new());
+        tagged.tags = vec!["bug".into()];
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::with_items(vec![
+            done,
+            tagged,
+            ToDo::new("Call Mom".into(), String::new()),
+        ]);
+
+        let mut export_action = Action::new("export", &mut logger, &database).unwrap();
+        export_action.arguments = vec!["--format=markdown".into(), "not".into(), "tag:bug".into()];
+        assert_eq!(export_action.execute_action(), Ok(()));
+
+        assert_eq!(
+            String::from_utf8(logger.std_writer).unwrap(),
+            "- [x] Pay rent\n- [ ] Call Mom\n"
+        );
+    }
+
+    #[test]
+    fn should_export_to_the_given_file() {
+        let dir = env::temp_dir().join(format!("terminal-todo-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("export.csv");
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::with_items(vec![ToDo::new("Call Mom".into(), String::new())]);
+
+        let mut export_action = Action::new("export", &mut logger, &database).unwrap();
+        export_action.arguments = vec![
+            "--format".into(),
+            "csv".into(),
+            "--output".into(),
+            path.to_string_lossy().into(),
+        ];
+        assert_eq!(export_action.execute_action(), Ok(()));
+
+        let exported = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            exported,
+            "title,description,done,priority,due,tags,project,created,updated\nCall Mom,,false,,,,,,\n"
+        );
+        assert_eq!(
+            String::from_utf8(logger.std_writer).unwrap(),
+            format!("Exported 1 ToDo item to {}\n", path.display())
+        );
+    }
+
+    #[test]
+    fn should_export_to_stdout_in_a_json_document() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["Call Mom"]);
+
+        let mut export_action = Action::new("export", &mut logger, &database).unwrap();
+        export_action.output = OutputMode::Json;
+        export_action.arguments = vec!["--format=markdown".into()];
+        assert_eq!(export_action.execute_action(), Ok(()));
+
+        assert_eq!(
+            String::from_utf8(logger.std_writer).unwrap(),
+            "{\"format\":\"markdown\",\"count\":1,\"contents\":\"- [ ] Call Mom\\n\"}\n"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{
+    date::{self, Date},
+    output::Json,
+    todo::ToDo,
+};
+
+use super::{
+    events::{self, Event, Record, Replayed},
+    Action,
+};
+
+/// A change to one item: when it happened, what kind of change it was and
+/// the fields it changed as `(field, before, after)`.
+#[derive(PartialEq, Debug)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub kind: &'static str,
+    pub fields: Vec<(&'static str, String, String)>,
+}
+
+/// The fields that differ between two versions of an item, as text.
+fn changed_fields(before: &ToDo, after: &ToDo) -> Vec<(&'static str, String, String)> {
+    let fields = |todo: &ToDo| {
+        [
+            ("title", todo.title.replace('\n', "")),
+            ("description", todo.description.replace('\n', "")),
+            (
+                "status",
+                if todo.is_done() { "done" } else { "open" }.to_string(),
+            ),
+            (
+                "priority",
+                todo.priority.map(String::from).unwrap_or_default(),
+            ),
+            (
+                "due",
+                todo.due.map(|due| due.to_string()).unwrap_or_default(),
+            ),
+            ("tags", todo.tags.join(" ")),
+            ("project", todo.project.clone().unwrap_or_default()),
+        ]
+    };
+
+    fields(before)
+        .into_iter()
+        .zip(fields(after))
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| (field, before, after))
+        .collect()
+}
+
+/// Goes through the records to find every change to the item with `id`.
+/// Items first seen in a snapshot, like those already in the list when
+/// changes started being recorded, are `recorded` there.
+pub fn item_history(records: &[Record], id: u64) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = vec![];
+    let mut current: Option<ToDo> = None;
+
+    for record in records {
+        let (kind, todo) = match &record.event {
+            Event::Created { id: item_id, todo } if *item_id == id => ("created", Some(todo)),
+            Event::Edited { id: item_id, todo } if *item_id == id => ("edited", Some(todo)),
+            Event::StatusChanged { id: item_id, todo } if *item_id == id => ("status", Some(todo)),
+            Event::Deleted { id: item_id } if *item_id == id => ("deleted", None),
+            Event::Snapshot { items, .. } => {
+                match items.iter().find(|(item_id, _)| *item_id == id) {
+                    Some((_, todo)) if current.is_none() => ("recorded", Some(todo)),
+                    Some((_, todo)) => ("edited", Some(todo)),
+                    None if current.is_some() => ("deleted", None),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        let fields = match (&current, todo) {
+            (Some(before), Some(after)) => changed_fields(before, after),
+            _ => vec![],
+        };
+
+        // Snapshots hold every item, most of them unchanged
+        if kind == "edited" && fields.is_empty() && current.as_ref() == todo {
+            continue;
+        }
+
+        entries.push(HistoryEntry {
+            timestamp: record.timestamp,
+            kind,
+            fields,
+        });
+        current = todo.cloned();
+    }
+
+    entries
+}
+
+/// The timestamp as a date and time of day, in local time.
+fn format_timestamp(timestamp: u64) -> String {
+    let timestamp = date::local(timestamp);
+    format!(
+        "{} {:02}:{:02}",
+        Date::from_timestamp(timestamp),
+        timestamp % 86400 / 3600,
+        timestamp % 3600 / 60
+    )
+}
+
+impl Action<'_> {
+    /// Shows every recorded change to an item, oldest first.
+    pub(super) fn history(&mut self) -> Result<(), &'static str> {
+        let item_index: usize = self.get_item_index_arg()?;
+
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let records: Vec<Record> = database.change_records()?;
+
+        let todo = match todos.get(item_index) {
+            Some(todo) => todo,
+            None => return Err("Given ToDo Item index is wrong"),
+        };
+
+        let replayed: Replayed = events::replay(&records);
+
+        // The recorded list is the same as the stored one, unless it was
+        // changed without going through this tool
+        let id = match replayed.items.get(item_index) {
+            Some((id, recorded)) if recorded.serialise() == todo.serialise() => *id,
+            _ => return Err("The history of this item is not known, the list was changed outside of terminal-todo"),
+        };
+
+        self.log_text(&format!(
+            "History of #{} {}",
+            item_index + 1,
+            todo.title.replace('\n', "")
+        ));
+
+        let mut entries: Vec<Json> = vec![];
+
+        for entry in item_history(&records, id) {
+            self.log_text(&format!(
+                "{} {}",
+                format_timestamp(entry.timestamp),
+                entry.kind
+            ));
+            for (field, before, after) in entry.fields.iter() {
+                self.log_text(&format!(
+                    "    {field}: {} -> {}",
+                    if before.is_empty() { "(none)" } else { before },
+                    if after.is_empty() { "(none)" } else { after }
+                ));
+            }
+
+            entries.push(Json::object(vec![
+                ("timestamp", Json::Number(entry.timestamp as i64)),
+                ("event", Json::string(entry.kind)),
+                (
+                    "changes",
+                    Json::Array(
+                        entry
+                            .fields
+                            .iter()
+                            .map(|(field, before, after)| {
+                                Json::object(vec![
+                                    ("field", Json::string(field)),
+                                    ("before", Json::string(before)),
+                                    ("after", Json::string(after)),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ]));
+        }
+
+        self.log_json(entries, Json::Array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::test_database::{todo, TestDatabase},
+        log_wrapper::LogWrapper,
+    };
+
+    fn records() -> Vec<Record> {
+        let mut edited = todo("b", true);
+        edited.priority = Some('A');
+
+        vec![
+            Record {
+                timestamp: 1_700_000_000,
+                event: Event::Snapshot {
+                    items: vec![(0, todo("a", false))],
+                    next_id: 1,
+                },
+            },
+            Record {
+                timestamp: 1_700_003_600,
+                event: Event::Created {
+                    id: 1,
+                    todo: todo("b", false),
+                },
+            },
+            Record {
+                timestamp: 1_700_007_200,
+                event: Event::StatusChanged {
+                    id: 1,
+                    todo: todo("b", true),
+                },
+            },
+            Record {
+                timestamp: 1_700_010_800,
+                event: Event::Snapshot {
+                    items: vec![(1, edited), (0, todo("a", false))],
+                    next_id: 2,
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn should_follow_an_item_through_the_records() {
+        let history = item_history(&records(), 1);
+
+        let kinds: Vec<&str> = history.iter().map(|entry| entry.kind).collect();
+        assert_eq!(kinds, vec!["created", "status", "edited"]);
+        assert_eq!(
+            history[1].fields,
+            vec![("status", "open".to_string(), "done".to_string())]
+        );
+        assert_eq!(
+            history[2].fields,
+            vec![("priority", String::new(), "A".to_string())]
+        );
+
+        // Unchanged in the last snapshot
+        assert_eq!(item_history(&records(), 0).len(), 1);
+    }
+
+    #[test]
+    fn should_print_the_history_of_an_item() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut edited = todo("b", true);
+        edited.priority = Some('A');
+        let database = TestDatabase {
+            records: records(),
+            ..TestDatabase::with_items(vec![edited, todo("a", false)])
+        };
+        let mut history_action = Action::new("history", &mut logger, &database).unwrap();
+        history_action.arguments = vec!["1".into()];
+
+        assert_eq!(history_action.execute_action(), Ok(()));
+        assert_eq!(
+            String::from_utf8(logger.std_writer).unwrap(),
+            "History of #1 b
+2023-11-14 23:13 created
+2023-11-15 00:13 status
+    status: open -> done
+2023-11-15 01:13 edited
+    priority: (none) -> A
+"
+        );
+    }
+}
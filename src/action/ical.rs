@@ -0,0 +1,431 @@
+use crate::{date::Date, todo::ToDo};
+
+use super::import::{Imported, Unmapped};
+
+/// Content lines are folded to this many bytes, not counting the line break.
+const LINE_LENGTH: usize = 75;
+
+/// Escapes a text value, in which backslashes, semicolons, commas and line
+/// breaks have a meaning.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => unescaped.push('\\'),
+            },
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+/// Splits a list of text values on the commas that are not escaped.
+fn split_values(value: &str) -> Vec<String> {
+    let mut values: Vec<String> = vec![];
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            ',' => values.push(unescape(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    values.push(unescape(&current));
+
+    values
+}
+
+/// Ends a content line with CRLF, folding it into lines of at most
+/// `LINE_LENGTH` bytes that go on with a space, without splitting characters.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded + "\r\n"
+}
+
+/// Joins folded lines back, each with the number of the line it starts on.
+fn unfold(ics: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = vec![];
+
+    for (index, line) in ics.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+
+    lines
+}
+
+/// Splits a content line into its upper-cased name and its value, leaving
+/// out the parameters. Colons in quoted parameter values do not end the name.
+fn split_content_line(line: &str) -> Option<(String, &str)> {
+    let mut quoted = false;
+
+    let colon = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(index),
+        _ => None,
+    })?;
+
+    let name = line[..colon].split(';').next().unwrap_or_default();
+
+    Some((name.to_uppercase(), &line[colon + 1..]))
+}
+
+/// A UTC date-time as written by iCalendar, `YYYYMMDDTHHMMSSZ`.
+pub(super) fn format_date_time(timestamp: u64) -> String {
+    let date = Date::from_timestamp(timestamp);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        date.year,
+        date.month,
+        date.day,
+        timestamp % 86_400 / 3_600,
+        timestamp % 3_600 / 60,
+        timestamp % 60
+    )
+}
+
+fn parse_date(value: &str) -> Option<Date> {
+    let number = |range: std::ops::Range<usize>| value.get(range)?.parse::<u32>().ok();
+
+    Date::new(number(0..4)? as i32, number(4..6)?, number(6..8)?).ok()
+}
+
+/// Reads a date or a date-time. Date-times in a time zone are taken as UTC,
+/// which is close enough for the dates items keep.
+pub(super) fn parse_timestamp(value: &str) -> Option<u64> {
+    let date = parse_date(value)?;
+    let seconds = match value.get(8..) {
+        Some("") => 0,
+        Some(time) => {
+            let time = time.strip_prefix('T')?.trim_end_matches('Z');
+            let number = |range: std::ops::Range<usize>| time.get(range)?.parse::<u64>().ok();
+            if time.len() != 6 {
+                return None;
+            }
+            number(0..2)? * 3_600 + number(2..4)? * 60 + number(4..6)?
+        }
+        None => return None,
+    };
+
+    u64::try_from(date.days())
+        .ok()
+        .map(|days| days * 86_400 + seconds)
+}
+
+/// iCalendar priorities go from 1, the highest, to 9, the lowest, which
+/// letters A to I stand for. Lower letters are kept as 9.
+fn priority_number(priority: char) -> u32 {
+    ((priority as u32).saturating_sub('A' as u32) + 1).min(9)
+}
+
+/// Writes items as a calendar of VTODO components, each with its UID.
+/// `timestamp` is when the calendar was written, as iCalendar asks for.
+pub fn serialise(todos: &[(String, &ToDo)], timestamp: u64) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".into(),
+        "VERSION:2.0".into(),
+        "PRODID:-//terminal-todo//terminal-todo//EN".into(),
+    ];
+
+    for (uid, todo) in todos {
+        lines.push("BEGIN:VTODO".into());
+        lines.push(format!("UID:{}", escape(uid)));
+        lines.push(format!("DTSTAMP:{}", format_date_time(timestamp)));
+        lines.push(format!("SUMMARY:{}", escape(&todo.title)));
+        if !todo.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&todo.description)));
+        }
+
+        match todo.is_done() {
+            true => {
+                lines.push("STATUS:COMPLETED".into());
+                if let Some(updated) = todo.updated {
+                    lines.push(format!("COMPLETED:{}", format_date_time(updated)));
+                }
+            }
+            false => lines.push("STATUS:NEEDS-ACTION".into()),
+        }
+
+        if let Some(due) = todo.due {
+            lines.push(format!(
+                "DUE;VALUE=DATE:{:04}{:02}{:02}",
+                due.year, due.month, due.day
+            ));
+        }
+        if let Some(priority) = todo.priority {
+            lines.push(format!("PRIORITY:{}", priority_number(priority)));
+        }
+        if !todo.tags.is_empty() {
+            let tags: Vec<String> = todo.tags.iter().map(|tag| escape(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(created) = todo.created {
+            lines.push(format!("CREATED:{}", format_date_time(created)));
+        }
+        if let Some(updated) = todo.updated {
+            lines.push(format!("LAST-MODIFIED:{}", format_date_time(updated)));
+        }
+
+        lines.push("END:VTODO".into());
+    }
+
+    lines.push("END:VCALENDAR".into());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Reads the VTODO components of a calendar. Other components, and those
+/// nested in a VTODO like alarms, are left out.
+pub fn parse(ics: &str) -> Imported {
+    let mut todos: Vec<(usize, ToDo)> = vec![];
+    let mut unmapped: Vec<Unmapped> = vec![];
+    // The item being read with the line it starts on, and how deep in
+    // components nested in it the lines are
+    let mut current: Option<(usize, ToDo)> = None;
+    let mut depth = 0;
+
+    for (line, content_line) in unfold(ics) {
+        let (name, value) = match split_content_line(&content_line) {
+            Some(parts) => parts,
+            None => {
+                if current.is_some() && depth == 0 {
+                    unmapped.push(Unmapped {
+                        line,
+                        text: content_line.clone(),
+                        reason: "it is not a property",
+                    });
+                }
+                continue;
+            }
+        };
+
+        let todo = match (&mut current, name.as_str()) {
+            (None, "BEGIN") if value.eq_ignore_ascii_case("VTODO") => {
+                current = Some((line, ToDo::new(String::new(), String::new())));
+                continue;
+            }
+            (None, _) => continue,
+            (Some(_), "BEGIN") => {
+                depth += 1;
+                continue;
+            }
+            (Some(_), "END") if depth > 0 => {
+                depth -= 1;
+                continue;
+            }
+            (Some(_), "END") => {
+                let (start, todo) = current.take().unwrap();
+                match todo.title.is_empty() {
+                    true => unmapped.push(Unmapped {
+                        line: start,
+                        text: "BEGIN:VTODO".into(),
+                        reason: "there is no text for the title",
+                    }),
+                    false => todos.push((start, todo)),
+                }
+                continue;
+            }
+            (Some(_), _) if depth > 0 => continue,
+            (Some((_, todo)), _) => todo,
+        };
+
+        let valid = match name.as_str() {
+            "SUMMARY" => {
+                todo.title = unescape(value);
+                true
+            }
+            "DESCRIPTION" => {
+                todo.description = unescape(value);
+                true
+            }
+            "STATUS" => match value.to_uppercase().as_str() {
+                "COMPLETED" => {
+                    todo.done = "✅".into();
+                    true
+                }
+                "NEEDS-ACTION" | "IN-PROCESS" | "CANCELLED" => true,
+                _ => false,
+            },
+            "COMPLETED" => parse_timestamp(value)
+                .map(|completed| {
+                    todo.done = "✅".into();
+                    todo.updated = Some(completed);
+                })
+                .is_some(),
+            "DUE" => parse_date(value).map(|due| todo.due = Some(due)).is_some(),
+            "PRIORITY" => match value.trim().parse::<u32>() {
+                Ok(0) => true,
+                Ok(priority @ 1..=9) => {
+                    todo.priority = char::from_u32('A' as u32 + priority - 1);
+                    true
+                }
+                _ => false,
+            },
+            "CATEGORIES" => {
+                todo.tags.extend(
+                    split_values(value)
+                        .iter()
+                        .map(|tag| tag.split_whitespace().collect::<Vec<&str>>().join("-"))
+                        .filter(|tag| !tag.is_empty()),
+                );
+                true
+            }
+            "CREATED" => parse_timestamp(value)
+                .map(|created| todo.created = Some(created))
+                .is_some(),
+            "LAST-MODIFIED" => parse_timestamp(value)
+                .map(|updated| todo.updated = todo.updated.max(Some(updated)))
+                .is_some(),
+            "UID" | "DTSTAMP" => true,
+            _ => {
+                unmapped.push(Unmapped {
+                    line,
+                    text: content_line.clone(),
+                    reason: "there is no such field",
+                });
+                continue;
+            }
+        };
+
+        if !valid {
+            unmapped.push(Unmapped {
+                line,
+                text: content_line.clone(),
+                reason: "the value is not valid",
+            });
+        }
+    }
+
+    (todos, unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_fold_long_lines_without_splitting_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|line| line.len() <= LINE_LENGTH));
+        assert!(folded.contains("\r\n é"));
+        assert_eq!(unfold(&folded), vec![(1, line)]);
+    }
+
+    #[test]
+    fn should_read_back_what_it_writes() {
+        let mut open = ToDo::new(
+            "Call Mom; then Dad, maybe".into(),
+            "Ask about\nthe weekend \\ trip".into(),
+        );
+        open.priority = Some('B');
+        open.due = Some(Date::new(2026, 10, 1).unwrap());
+        open.tags = vec!["phone".into(), "evening".into()];
+        open.created = Some(1_788_220_800);
+        open.updated = Some(1_788_224_400);
+
+        let mut done = ToDo::new("Pay rent".into(), String::new());
+        done.done = "✅".into();
+        done.updated = Some(1_789_862_400);
+
+        let ics = serialise(
+            &[
+                ("1-default@terminal-todo".into(), &open),
+                ("2-default@terminal-todo".into(), &done),
+            ],
+            1_792_368_000,
+        );
+        assert!(ics.contains(
+            "SUMMARY:Call Mom\\; then Dad\\, maybe\r\nDESCRIPTION:Ask about\\nthe weekend \\\\ trip\r\n"
+        ));
+        assert!(ics.contains("STATUS:COMPLETED\r\nCOMPLETED:20260920T000000Z\r\n"));
+        assert!(
+            ics.contains("DUE;VALUE=DATE:20261001\r\nPRIORITY:2\r\nCATEGORIES:phone,evening\r\n")
+        );
+
+        let (todos, unmapped) = parse(&ics);
+        assert_eq!(todos, vec![(4, open), (16, done)]);
+        assert_eq!(unmapped, vec![]);
+    }
+
+    #[test]
+    fn should_only_read_todos() {
+        let (todos, unmapped) = parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:Meeting\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VTODO\r\n\
+             SUMMARY:Renew\r\n  passport\r\n\
+             DUE;TZID=\"Europe/Paris:Central\":20261101T090000\r\n\
+             PRIORITY:high\r\n\
+             X-APPLE-SORT-ORDER:3\r\n\
+             BEGIN:VALARM\r\n\
+             DESCRIPTION:Reminder\r\n\
+             END:VALARM\r\n\
+             END:VTODO\r\n\
+             BEGIN:VTODO\r\n\
+             END:VTODO\r\n\
+             END:VCALENDAR\r\n",
+        );
+
+        assert_eq!(todos.len(), 1);
+        let (line, renew) = &todos[0];
+        assert_eq!(*line, 5);
+        assert_eq!(renew.title, "Renew passport");
+        assert_eq!(renew.description, "");
+        assert_eq!(renew.due, Some(Date::new(2026, 11, 1).unwrap()));
+
+        let reasons: Vec<(usize, &str)> = unmapped
+            .iter()
+            .map(|unmapped| (unmapped.line, unmapped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (9, "the value is not valid"),
+                (10, "there is no such field"),
+                (15, "there is no text for the title")
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs;
+
+use crate::{
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::{csv, ical, taskwarrior, todotxt, Action};
+
+/// A part of what was imported that could not be mapped onto the fields of
+/// an item, or a whole line that was left out, with why.
+#[derive(PartialEq, Debug)]
+pub struct Unmapped {
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+/// The items read from a file to import, with the line each starts on, and
+/// what could not be mapped onto them.
+pub type Imported = (Vec<(usize, ToDo)>, Vec<Unmapped>);
+
+/// Whether two items are the same as far as importing goes: the same
+/// Taskwarrior uuid, or the same title, whatever the case, in the same
+/// project.
+fn is_duplicate(todo: &ToDo, other: &ToDo) -> bool {
+    (todo.uuid.is_some() && todo.uuid == other.uuid)
+        || (todo.title.trim().to_lowercase() == other.title.trim().to_lowercase()
+            && todo.project == other.project)
+}
+
+impl Action<'_> {
+    /// `import --from <format> <file>` adds the items of a file written in
+    /// another format to the list, leaving out those already in it.
+    pub(super) fn import(&mut self) -> Result<(), &'static str> {
+        let arguments = self.arguments.clone();
+        let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+        let (format, path) =
+            match arguments.as_slice() {
+                ["--from", format, path] | [path, "--from", format] => (*format, *path),
+                [flag, path] | [path, flag] if flag.starts_with("--from=") => {
+                    (&flag["--from=".len()..], *path)
+                }
+                _ => return Err(
+                    "Import should be used as import --from todotxt|csv|ical|taskwarrior <file>",
+                ),
+            };
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Err("Could not read the file to import"),
+        };
+
+        let (imported, unmapped) = match format {
+            "todotxt" => todotxt::parse(&contents),
+            "csv" => csv::parse(&contents),
+            "ical" => ical::parse(&contents),
+            "taskwarrior" => taskwarrior::parse(&contents)?,
+            _ => return Err("Import can only be from todotxt, csv, ical or taskwarrior"),
+        };
+
+        for unmapped in unmapped.iter() {
+            self.log_text(&format!(
+                "Line {}: `{}` was not imported as a field, {}",
+                unmapped.line, unmapped.text, unmapped.reason
+            ));
+        }
+
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let mut after: Vec<ToDo> = todos.clone();
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+        let mut duplicate_count = 0;
+
+        for (line, todo) in imported {
+            if after.iter().any(|other| is_duplicate(&todo, other)) {
+                self.log_text(&format!(
+                    "Line {line}: `{}` is already in the list",
+                    todo.title
+                ));
+                duplicate_count += 1;
+                continue;
+            }
+
+            after.push(todo);
+            changes.push((
+                None,
+                Some(output::todo_to_json(after.len(), after.last().unwrap())),
+            ));
+        }
+
+        if !changes.is_empty() {
+            database.store_existing_items(after.clone())?;
+            self.record_change(todos, &after)?;
+        }
+
+        self.log_text(&format!(
+            "Imported {} ToDo item{}, {duplicate_count} already in the list",
+            changes.len(),
+            if changes.len() == 1 { "" } else { "s" }
+        ));
+
+        self.log_changes("import", changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{action::test_database::TestDatabase, log_wrapper::LogWrapper};
+    use std::env;
+
+    #[test]
+    fn should_import_todotxt_without_duplicates() {
+        let dir = env::temp_dir().join(format!("terminal-todo-import-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.txt");
+        fs::write(
+            &path,
+            "Call Mom +family\ncall mom +family\n(B) Pay rent\nx done:yes\n",
+        )
+        .unwrap();
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["Pay rent"]);
+
+        let mut import_action = Action::new("import", &mut logger, &database).unwrap();
+        import_action.arguments = vec![
+            "--from".into(),
+            "todotxt".into(),
+            path.to_string_lossy().into(),
+        ];
+        assert_eq!(import_action.execute_action(), Ok(()));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(database.titles(), vec!["Pay rent", "Call Mom", "done:yes"]);
+        assert_eq!(
+            String::from_utf8(logger.std_writer).unwrap(),
+            "Line 4: `done:yes` was not imported as a field, there is no such field
+Line 2: `call mom` is already in the list
+Line 3: `Pay rent` is already in the list
+Imported 2 ToDo items, 2 already in the list
+"
+        );
+    }
+}
@@ -0,0 +1,578 @@
+use crate::{
+    config,
+    date::{self, Date},
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::{database::fnv1a, Action, Confirm};
+
+const CHANGED_SINCE: &str = "The list was changed since, so it cannot be done safely";
+
+/// A change made by a mutating action, kept as the items it took out and put
+/// in so that it can be reverted and reapplied.
+#[derive(PartialEq, Debug, Clone)]
+pub struct JournalEntry {
+    /// The command line of the action, like `delete 3`.
+    pub action: String,
+    pub timestamp: u64,
+    /// Checksums of the whole list before and after the change, which tell
+    /// whether it is still the way the change found or left it.
+    pub checksums: (u64, u64),
+    /// The items taken out, with their positions in the list before.
+    pub removed: Vec<(usize, ToDo)>,
+    /// The items put in, with their positions in the list after.
+    pub added: Vec<(usize, ToDo)>,
+}
+
+impl JournalEntry {
+    /// Keeps the items that differ between `before` and `after`. The few
+    /// changes these cannot tell, like items moved around while others were
+    /// removed, keep the whole lists instead.
+    pub fn new(action: String, timestamp: u64, before: &[ToDo], after: &[ToDo]) -> Self {
+        let owned = |item: Option<(usize, &ToDo)>| item.map(|(index, todo)| (index, todo.clone()));
+        let (mut removed, mut added) = (vec![], vec![]);
+
+        for (from, to) in diff_items(before, after) {
+            removed.extend(owned(from));
+            added.extend(owned(to));
+        }
+
+        if apply(before, &removed, &added).is_none_or(|applied| !same_items(&applied, after)) {
+            removed = before.iter().cloned().enumerate().collect();
+            added = after.iter().cloned().enumerate().collect();
+        }
+
+        JournalEntry {
+            action,
+            timestamp,
+            checksums: (checksum(before), checksum(after)),
+            removed,
+            added,
+        }
+    }
+
+    /// The list as it was before the change, from the list as the change
+    /// left it when undoing, or the other way around when redoing.
+    pub fn replay(&self, todos: &[ToDo], undo: bool) -> Result<Vec<ToDo>, &'static str> {
+        let (expected, taken_out, put_in) = match undo {
+            true => (self.checksums.1, &self.added, &self.removed),
+            false => (self.checksums.0, &self.removed, &self.added),
+        };
+
+        match checksum(todos) == expected {
+            true => apply(todos, taken_out, put_in).ok_or(CHANGED_SINCE),
+            false => Err(CHANGED_SINCE),
+        }
+    }
+}
+
+/// Takes the `taken_out` items out of `todos`, when they are where they should
+/// be, then puts the `put_in` ones in at their positions. Both are in the
+/// order of their positions.
+fn apply(
+    todos: &[ToDo],
+    taken_out: &[(usize, ToDo)],
+    put_in: &[(usize, ToDo)],
+) -> Option<Vec<ToDo>> {
+    let mut todos = todos.to_vec();
+
+    for (index, todo) in taken_out.iter().rev() {
+        if todos.get(*index)?.serialise() != todo.serialise() {
+            return None;
+        }
+        todos.remove(*index);
+    }
+
+    for (index, todo) in put_in {
+        if *index > todos.len() {
+            return None;
+        }
+        todos.insert(*index, todo.clone());
+    }
+
+    Some(todos)
+}
+
+fn checksum(todos: &[ToDo]) -> u64 {
+    let serialised: String = todos.iter().map(|todo| todo.serialise()).collect();
+    fnv1a(serialised.as_bytes())
+}
+
+/// The changes that can be undone, most recent last, and the undone ones that
+/// can be redone.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Journal {
+    pub undo: Vec<JournalEntry>,
+    pub redo: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Adds a new change, forgetting the oldest ones past `depth` and
+    /// everything that could be redone.
+    pub fn record(&mut self, entry: JournalEntry, depth: usize) {
+        self.undo.push(entry);
+        self.redo.clear();
+
+        if self.undo.len() > depth {
+            self.undo.drain(..self.undo.len() - depth);
+        }
+    }
+
+    /// Reads a journal written by `serialise`, where each entry starts with
+    /// an `undo` or `redo` line, with the checksums of the list before and
+    /// after the change, followed by the items it took out (`-`) and put in
+    /// (`+`) with their positions.
+    pub fn deserialise(journal_str: &str) -> Result<Self, &'static str> {
+        let mut journal = Journal::default();
+        let mut current: Option<(bool, JournalEntry)> = None;
+
+        for line in journal_str.lines() {
+            match line.split_once(' ') {
+                Some((kind @ ("undo" | "redo"), header)) => {
+                    journal.push(current.take());
+
+                    let mut fields = header.splitn(4, ' ');
+                    let (timestamp, before, after) = match (
+                        fields.next().map(str::parse::<u64>),
+                        fields.next().map(|before| u64::from_str_radix(before, 16)),
+                        fields.next().map(|after| u64::from_str_radix(after, 16)),
+                    ) {
+                        (Some(Ok(timestamp)), Some(Ok(before)), Some(Ok(after))) => {
+                            (timestamp, before, after)
+                        }
+                        _ => return Err("Journal is corrupted, could not read data"),
+                    };
+
+                    current = Some((
+                        kind == "undo",
+                        JournalEntry {
+                            action: fields.next().unwrap_or_default().into(),
+                            timestamp,
+                            checksums: (before, after),
+                            removed: vec![],
+                            added: vec![],
+                        },
+                    ));
+                }
+                Some((side @ ("-" | "+"), item)) => {
+                    let entry = match current.as_mut() {
+                        Some((_, entry)) => entry,
+                        None => return Err("Journal is corrupted, could not read data"),
+                    };
+                    let (index, todo) = match item.split_once(' ') {
+                        Some((index, todo_str)) => {
+                            (index.parse::<usize>(), ToDo::deserialise(todo_str))
+                        }
+                        None => return Err("Journal is corrupted, could not read data"),
+                    };
+                    let item = match (index, todo) {
+                        (Ok(index), Ok(todo)) => (index, todo),
+                        _ => return Err("Journal is corrupted, could not read data"),
+                    };
+
+                    match side {
+                        "-" => entry.removed.push(item),
+                        _ => entry.added.push(item),
+                    }
+                }
+                _ => return Err("Journal is corrupted, could not read data"),
+            }
+        }
+
+        journal.push(current);
+
+        Ok(journal)
+    }
+
+    fn push(&mut self, entry: Option<(bool, JournalEntry)>) {
+        match entry {
+            Some((true, entry)) => self.undo.push(entry),
+            Some((false, entry)) => self.redo.push(entry),
+            None => {}
+        }
+    }
+
+    pub fn serialise(&self) -> String {
+        let mut journal_str = String::new();
+
+        for (kind, entries) in [("undo", &self.undo), ("redo", &self.redo)] {
+            for entry in entries {
+                let (before, after) = entry.checksums;
+                journal_str += &format!(
+                    "{kind} {} {before:016x} {after:016x} {}\n",
+                    entry.timestamp, entry.action
+                );
+                for (index, todo) in entry.removed.iter() {
+                    journal_str += &format!("- {index} {}", todo.serialise());
+                }
+                for (index, todo) in entry.added.iter() {
+                    journal_str += &format!("+ {index} {}", todo.serialise());
+                }
+            }
+        }
+
+        journal_str
+    }
+}
+
+/// How many changes are kept to be undone, from the `undo.depth` setting.
+pub fn configured_depth() -> Result<usize, &'static str> {
+    config::current()
+        .get("undo.depth")
+        .unwrap_or("20")
+        .parse::<usize>()
+        .map_err(|_| "undo.depth should be a number")
+}
+
+/// Whether the journalled action moved items between the list and the trash,
+/// which undoing and redoing it has to do too.
+fn moves_to_trash(action: &str) -> bool {
+    let mut words = action.split(' ');
+    match words.next() {
+        Some("delete") => words.all(|word| word != "--purge"),
+        Some("restore") => true,
+        _ => false,
+    }
+}
+
+/// Whether the journalled action moved items between the list and the
+/// archive.
+fn moves_to_archive(action: &str) -> bool {
+    matches!(action.split(' ').next(), Some("archive" | "unarchive"))
+}
+
+fn same_items(todos: &[ToDo], other_todos: &[ToDo]) -> bool {
+    todos.len() == other_todos.len()
+        && todos
+            .iter()
+            .zip(other_todos)
+            .all(|(todo, other_todo)| todo.serialise() == other_todo.serialise())
+}
+
+/// An item as it was and as it becomes, with its position in each list.
+pub type ItemChange<'a> = (Option<(usize, &'a ToDo)>, Option<(usize, &'a ToDo)>);
+
+/// The items that differ between two versions of a list, `None` standing for
+/// an item that is only in one of them. Lists of the same length are compared
+/// position by position.
+pub fn diff_items<'a>(from: &'a [ToDo], to: &'a [ToDo]) -> Vec<ItemChange<'a>> {
+    if from.len() == to.len() {
+        return from
+            .iter()
+            .zip(to)
+            .enumerate()
+            .filter(|(_, (from_todo, to_todo))| from_todo.serialise() != to_todo.serialise())
+            .map(|(index, (from_todo, to_todo))| (Some((index, from_todo)), Some((index, to_todo))))
+            .collect();
+    }
+
+    let only_in = |todos: &'a [ToDo], others: &'a [ToDo]| {
+        let mut others: Vec<String> = others.iter().map(|todo| todo.serialise()).collect();
+
+        todos
+            .iter()
+            .enumerate()
+            .filter(move |(_, todo)| {
+                match others.iter().position(|other| *other == todo.serialise()) {
+                    Some(position) => {
+                        others.remove(position);
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect::<Vec<(usize, &ToDo)>>()
+    };
+
+    only_in(from, to)
+        .into_iter()
+        .map(|removed| (Some(removed), None))
+        .chain(
+            only_in(to, from)
+                .into_iter()
+                .map(|added| (None, Some(added))),
+        )
+        .collect()
+}
+
+impl Action<'_> {
+    /// Keeps the change a mutating action made to the list so it can be
+    /// undone.
+    pub(super) fn record_change(
+        &mut self,
+        before: Vec<ToDo>,
+        after: &[ToDo],
+    ) -> Result<(), &'static str> {
+        let mut action = self.action_type.name().to_string();
+        for argument in self.arguments.iter() {
+            action.push(' ');
+            action += argument;
+        }
+
+        self.record_change_as(action, before, after)
+    }
+
+    /// Journals a change made on the way by another action, under the
+    /// command line that would have made it.
+    pub(super) fn record_change_as(
+        &mut self,
+        action: String,
+        before: Vec<ToDo>,
+        after: &[ToDo],
+    ) -> Result<(), &'static str> {
+        let depth = configured_depth()?;
+
+        if depth == 0 {
+            return Ok(());
+        }
+
+        let database = self.database.expect("Database could not be found");
+        let mut journal = database.read_journal()?;
+
+        journal.record(
+            JournalEntry::new(action, date::now(), &before, after),
+            depth,
+        );
+
+        database.store_journal(&journal)
+    }
+
+    /// Prints a line for every item that differs between `from` and `to`,
+    /// returning the differences as changes to report.
+    pub(super) fn log_diff(
+        &mut self,
+        from: &[ToDo],
+        to: &[ToDo],
+    ) -> Vec<(Option<Json>, Option<Json>)> {
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for (from, to) in diff_items(from, to) {
+            let line = match (from, to) {
+                (Some((index, _)), Some(_)) => format!("Changes #{}", index + 1),
+                (Some((index, _)), None) => format!("Removes #{}", index + 1),
+                (None, Some((index, _))) => format!("Brings back #{}", index + 1),
+                (None, None) => continue,
+            };
+            let title = to.or(from).map(|(_, todo)| todo.title.replace('\n', ""));
+            self.log_text(&format!("{line} {}", title.unwrap_or_default()));
+
+            changes.push((
+                from.map(|(index, todo)| output::todo_to_json(index + 1, todo)),
+                to.map(|(index, todo)| output::todo_to_json(index + 1, todo)),
+            ));
+        }
+
+        changes
+    }
+
+    pub(super) fn undo(&mut self) -> Result<(), &'static str> {
+        self.replay_journal(true)
+    }
+
+    pub(super) fn redo(&mut self) -> Result<(), &'static str> {
+        self.replay_journal(false)
+    }
+
+    /// Puts the list back the way it was before the last change, or after the
+    /// last undone one, once it has shown what is going to change.
+    fn replay_journal(&mut self, undo: bool) -> Result<(), &'static str> {
+        let action_name = if undo { "undo" } else { "redo" };
+        let database = self.database.expect("Database could not be found");
+        let mut journal = database.read_journal()?;
+
+        let entry = match undo {
+            true => journal.undo.pop(),
+            false => journal.redo.pop(),
+        };
+        let entry = match entry {
+            Some(entry) => entry,
+            None if undo => return Err("Nothing to undo"),
+            None => return Err("Nothing to redo"),
+        };
+
+        let current = database.read_items()?;
+        let replacement = entry.replay(&current, undo)?;
+
+        self.log_text(&format!(
+            "{} `{}` from {}",
+            if undo { "Undoing" } else { "Redoing" },
+            entry.action,
+            Date::from_timestamp(date::local(entry.timestamp))
+        ));
+
+        let changes = self.log_diff(&current, &replacement);
+
+        if Confirm::configured()?.asks(changes.len())
+            && !self.confirm(&format!("Go on with {action_name}?"))
+        {
+            self.log_text(if undo {
+                "Nothing was undone"
+            } else {
+                "Nothing was redone"
+            });
+            return self.log_changes(action_name, vec![]);
+        }
+
+        database.store_existing_items(replacement.clone())?;
+        if moves_to_trash(&entry.action) {
+            self.replay_trash(&diff_items(&current, &replacement))?;
+        } else if moves_to_archive(&entry.action) {
+            self.replay_archive(&diff_items(&current, &replacement))?;
+        }
+
+        match undo {
+            true => journal.redo.push(entry),
+            false => journal.undo.push(entry),
+        }
+        database.store_journal(&journal)?;
+
+        self.log_changes(action_name, changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        action::test_database::{todos, TestDatabase},
+        log_wrapper::LogWrapper,
+    };
+
+    #[test]
+    fn should_round_trip_and_trim_the_journal() {
+        let mut journal = Journal::default();
+
+        for (position, action) in ["create", "done 1", "delete 1"].iter().enumerate() {
+            journal.record(
+                JournalEntry::new(
+                    action.to_string(),
+                    1_700_000_000 + position as u64,
+                    &todos(&["a, b"]),
+                    &todos(&["c"]),
+                ),
+                2,
+            );
+        }
+        journal.redo.push(journal.undo.pop().unwrap());
+
+        assert_eq!(journal.undo.len(), 1);
+        assert_eq!(journal.undo[0].action, "done 1");
+        assert_eq!(Journal::deserialise(&journal.serialise()), Ok(journal));
+        assert_eq!(
+            Journal::deserialise("- a,b,❌\n"),
+            Err("Journal is corrupted, could not read data")
+        );
+    }
+
+    #[test]
+    fn should_only_keep_the_changed_items() {
+        let before = todos(&["a", "b", "c"]);
+        let mut after = before.clone();
+        after[1].done = "✅".into();
+
+        let entry = JournalEntry::new("done 2".into(), 1_700_000_000, &before, &after);
+        assert_eq!(entry.removed, vec![(1, before[1].clone())]);
+        assert_eq!(entry.added, vec![(1, after[1].clone())]);
+        assert_eq!(entry.replay(&after, true), Ok(before.clone()));
+        assert_eq!(entry.replay(&before, false), Ok(after.clone()));
+        assert_eq!(entry.replay(&before, true), Err(CHANGED_SINCE));
+
+        // Items moved around while one was removed keep the whole lists
+        let after = todos(&["c", "a"]);
+        let entry = JournalEntry::new("sort".into(), 1_700_000_000, &before, &after);
+        assert_eq!(entry.removed.len(), 3);
+        assert_eq!(entry.replay(&after, true), Ok(before.clone()));
+        assert_eq!(entry.replay(&before, false), Ok(after));
+    }
+
+    #[test]
+    fn should_undo_and_redo_a_delete() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b", "c"]);
+
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["2".into()];
+        assert_eq!(delete_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["a", "c"]);
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["a", "b", "c"]);
+
+        let output = String::from_utf8(logger.std_writer).unwrap();
+        assert!(output.starts_with("Undoing `delete 2` from "));
+        assert!(output.ends_with("\nBrings back #2 b\n"));
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Err("Nothing to undo"));
+
+        let mut redo_action = Action::new("redo", &mut logger, &database).unwrap();
+        assert_eq!(redo_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn should_take_an_undone_delete_out_of_the_trash() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b", "c"]);
+
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["2".into()];
+        assert_eq!(delete_action.execute_action(), Ok(()));
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let mut trash_action = Action::new("trash", &mut logger, &database).unwrap();
+        trash_action.arguments = vec!["list".into()];
+        assert_eq!(trash_action.execute_action(), Ok(()));
+        assert_eq!(logger.std_writer, b"The trash is empty\n");
+
+        let mut redo_action = Action::new("redo", &mut logger, &database).unwrap();
+        assert_eq!(redo_action.execute_action(), Ok(()));
+        assert_eq!(database.trashed_titles(), vec!["b"]);
+
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["1".into(), "--purge".into()];
+        assert_eq!(delete_action.execute_action(), Ok(()));
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(undo_action.execute_action(), Ok(()));
+        let mut redo_action = Action::new("redo", &mut logger, &database).unwrap();
+        assert_eq!(redo_action.execute_action(), Ok(()));
+        assert_eq!(database.trash.borrow().len(), 1);
+    }
+
+    #[test]
+    fn should_not_undo_when_the_list_changed_since() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b"]);
+
+        let mut done_action = Action::new("done", &mut logger, &database).unwrap();
+        done_action.arguments = vec!["1".into()];
+        assert_eq!(done_action.execute_action(), Ok(()));
+
+        database.todos.borrow_mut().pop();
+
+        let mut undo_action = Action::new("undo", &mut logger, &database).unwrap();
+        assert_eq!(
+            undo_action.execute_action(),
+            Err("The list was changed since, so it cannot be done safely")
+        );
+        assert_eq!(database.titles(), vec!["a"]);
+    }
+
+    #[test]
+    fn should_diff_lists() {
+        let (before, after) = (todos(&["a", "b", "c"]), todos(&["a", "c"]));
+        let diff = diff_items(&before, &after);
+        assert_eq!(diff, vec![(Some((1, &before[1])), None)]);
+
+        let (before, mut after) = (todos(&["a", "b"]), todos(&["a", "b"]));
+        after[1].done = "✅".into();
+        let diff = diff_items(&before, &after);
+        assert_eq!(diff, vec![(Some((1, &before[1])), Some((1, &after[1])))]);
+    }
+}
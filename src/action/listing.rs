@@ -0,0 +1,437 @@
+use std::cmp::Ordering;
+
+use crate::{config, date::Date, template::LONG_TEMPLATE, theme::Theme, todo::ToDo};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SortKey {
+    Position,
+    Title,
+    Status,
+    Priority,
+    Due,
+    Created,
+    Updated,
+}
+
+impl SortKey {
+    pub fn new(key_name: &str) -> Result<Self, &'static str> {
+        match key_name {
+            "position" => Ok(SortKey::Position),
+            "title" => Ok(SortKey::Title),
+            "status" => Ok(SortKey::Status),
+            "priority" => Ok(SortKey::Priority),
+            "due" => Ok(SortKey::Due),
+            "created" => Ok(SortKey::Created),
+            "updated" => Ok(SortKey::Updated),
+            _ => Err(
+                "Sort key can only be position, title, status, priority, due, created or updated",
+            ),
+        }
+    }
+
+    fn compare(&self, (a_index, a): &(usize, &ToDo), (b_index, b): &(usize, &ToDo)) -> Ordering {
+        match self {
+            SortKey::Position => a_index.cmp(b_index),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Status => a.is_done().cmp(&b.is_done()),
+            SortKey::Priority => compare_missing_last(&a.priority, &b.priority),
+            SortKey::Due => compare_missing_last(&a.due, &b.due),
+            SortKey::Created => compare_missing_last(&a.created, &b.created),
+            SortKey::Updated => compare_missing_last(&a.updated, &b.updated),
+        }
+    }
+}
+
+fn compare_missing_last<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GroupBy {
+    Status,
+    Project,
+    Tag,
+    Due,
+}
+
+impl GroupBy {
+    pub fn new(group_name: &str) -> Result<Self, &'static str> {
+        match group_name {
+            "status" => Ok(GroupBy::Status),
+            "project" => Ok(GroupBy::Project),
+            "tag" => Ok(GroupBy::Tag),
+            "due" => Ok(GroupBy::Due),
+            _ => Err("Group can only be status, project, tag or due"),
+        }
+    }
+
+    /// The keys of the groups an item belongs to, `None` for items without a
+    /// project or tags. Items with several tags show up under each of them.
+    fn group_keys(&self, todo: &ToDo, today: Date) -> Vec<Option<String>> {
+        match self {
+            GroupBy::Status if todo.is_done() => vec![Some("Done".into())],
+            GroupBy::Status => vec![Some("Open".into())],
+            GroupBy::Project => vec![todo.project.clone()],
+            GroupBy::Tag if todo.tags.is_empty() => vec![None],
+            GroupBy::Tag => todo.tags.iter().cloned().map(Some).collect(),
+            GroupBy::Due => vec![Some(due_bucket(todo.due, today).into())],
+        }
+    }
+
+    fn compare_groups(&self, a: Option<&str>, b: Option<&str>) -> Ordering {
+        match (self, a, b) {
+            (_, None, None) => Ordering::Equal,
+            (_, None, Some(_)) => Ordering::Greater,
+            (_, Some(_), None) => Ordering::Less,
+            (GroupBy::Status, Some(a), Some(b)) => a.cmp(b).reverse(),
+            (GroupBy::Project | GroupBy::Tag, Some(a), Some(b)) => {
+                a.to_lowercase().cmp(&b.to_lowercase())
+            }
+            (GroupBy::Due, Some(a), Some(b)) => {
+                let position = |name: &str| DUE_BUCKETS.iter().position(|bucket| *bucket == name);
+                position(a).cmp(&position(b))
+            }
+        }
+    }
+}
+
+/// A group of listed items, by the project, tag, status or due date they
+/// share.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Group {
+    pub by: GroupBy,
+    pub key: Option<String>,
+}
+
+impl Group {
+    /// The name the group is printed with.
+    pub fn label(&self) -> &str {
+        match (&self.key, self.by) {
+            (Some(key), _) => key,
+            (None, GroupBy::Project) => "No project",
+            (None, _) => "No tags",
+        }
+    }
+}
+
+const DUE_BUCKETS: [&str; 5] = ["Overdue", "Today", "This week", "Later", "No due date"];
+
+fn due_bucket(due: Option<Date>, today: Date) -> &'static str {
+    let due = match due {
+        Some(due) => due,
+        None => return DUE_BUCKETS[4],
+    };
+    let end_of_week = today.add_days(i64::from(7 - today.weekday()));
+
+    match due {
+        _ if due < today => DUE_BUCKETS[0],
+        _ if due == today => DUE_BUCKETS[1],
+        _ if due <= end_of_week => DUE_BUCKETS[2],
+        _ => DUE_BUCKETS[3],
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ListFormat {
+    Long,
+    Table,
+}
+
+impl ListFormat {
+    pub fn new(format_name: &str) -> Result<Self, &'static str> {
+        match format_name {
+            "long" => Ok(ListFormat::Long),
+            "table" => Ok(ListFormat::Table),
+            _ => Err("Format can only be table or long"),
+        }
+    }
+
+    /// The format used when `--format` is not given, from the `list.format`
+    /// setting.
+    pub fn configured() -> Result<Self, &'static str> {
+        ListFormat::new(config::current().get("list.format").unwrap_or("long"))
+    }
+}
+
+/// Items to print paired with their index in the list.
+pub type Listed<'a> = Vec<(usize, &'a ToDo)>;
+
+/// How `list` orders, groups and lays out the items it prints, taken from the
+/// `--sort key[,key...]`, `--reverse`, `--group-by group`, `--format table|long`,
+/// `--template template`, `--theme theme` and `--description` arguments.
+#[derive(PartialEq, Debug)]
+pub struct ListOptions {
+    pub sort: Vec<SortKey>,
+    pub reverse: bool,
+    pub group_by: Option<GroupBy>,
+    pub format: ListFormat,
+    pub template: Option<String>,
+    pub theme: Option<Theme>,
+    pub description: bool,
+}
+
+impl ListOptions {
+    /// Takes the listing options out of `arguments`, returning them along
+    /// with the arguments left for the filter expression.
+    pub fn parse(
+        arguments: &[String],
+        default_format: ListFormat,
+    ) -> Result<(Self, Vec<String>), &'static str> {
+        let mut options = ListOptions {
+            sort: vec![],
+            reverse: false,
+            group_by: None,
+            format: default_format,
+            template: None,
+            theme: None,
+            description: false,
+        };
+        let mut rest: Vec<String> = vec![];
+        let mut arguments = arguments.iter();
+
+        while let Some(arg) = arguments.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            match flag {
+                "--sort" | "--group-by" | "--format" | "--template" | "--theme" => {
+                    let value = match inline_value.or_else(|| arguments.next().cloned()) {
+                        Some(value) => value,
+                        None => return Err("Option requires a value"),
+                    };
+
+                    match flag {
+                        "--sort" => {
+                            for key_name in value.split(',') {
+                                options.sort.push(SortKey::new(key_name)?);
+                            }
+                        }
+                        "--group-by" => options.group_by = Some(GroupBy::new(&value)?),
+                        "--template" => {
+                            options.format = ListFormat::Long;
+                            options.template = Some(value);
+                        }
+                        "--theme" => options.theme = Some(Theme::named(&value)?),
+                        _ => options.format = ListFormat::new(&value)?,
+                    }
+                }
+                "--reverse" => options.reverse = true,
+                "--description" => options.description = true,
+                _ => rest.push(arg.clone()),
+            }
+        }
+
+        Ok((options, rest))
+    }
+
+    /// The template items are printed with in the long format: the one given
+    /// with `--template`, else the `list.template` setting, else the built-in
+    /// long layout.
+    pub fn template(&self) -> String {
+        match &self.template {
+            Some(template) => template.clone(),
+            None => config::current()
+                .get("list.template")
+                .unwrap_or(LONG_TEMPLATE)
+                .into(),
+        }
+    }
+
+    /// The theme given with `--theme`, else the configured one.
+    pub fn theme(&self) -> Result<Theme, &'static str> {
+        match &self.theme {
+            Some(theme) => Ok(theme.clone()),
+            None => Theme::configured(),
+        }
+    }
+
+    /// Sorts the `(index, item)` pairs; ties keep their position in the list.
+    pub fn sort(&self, todos: &mut [(usize, &ToDo)]) {
+        todos.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .fold(Ordering::Equal, |ordering, key| {
+                    ordering.then_with(|| key.compare(a, b))
+                })
+                .then_with(|| a.0.cmp(&b.0))
+        });
+
+        if self.reverse {
+            todos.reverse();
+        }
+    }
+
+    /// Splits the already sorted items into groups. Without a `--group-by`
+    /// everything ends up in one ungrouped list.
+    pub fn group<'a>(&self, todos: Listed<'a>, today: Date) -> Vec<(Option<Group>, Listed<'a>)> {
+        let group_by = match self.group_by {
+            Some(group_by) => group_by,
+            None => return vec![(None, todos)],
+        };
+
+        let mut groups: Vec<(Option<String>, Listed<'a>)> = vec![];
+
+        for (index, todo) in todos {
+            for key in group_by.group_keys(todo, today) {
+                match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+                    Some((_, group)) => group.push((index, todo)),
+                    None => groups.push((key, vec![(index, todo)])),
+                }
+            }
+        }
+
+        groups.sort_by(|(a, _), (b, _)| group_by.compare_groups(a.as_deref(), b.as_deref()));
+
+        groups
+            .into_iter()
+            .map(|(key, group)| (Some(Group { by: group_by, key }), group))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, fields: &[&str]) -> ToDo {
+        let mut todo = ToDo::new(title.into(), "description".into());
+        for field in fields {
+            todo.set_field(field).unwrap();
+        }
+        todo
+    }
+
+    fn args(arguments: &[&str]) -> Vec<String> {
+        arguments.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn should_take_list_options_out_of_the_arguments() {
+        let (options, rest) = ListOptions::parse(
+            &args(&["--sort", "due,title", "tag:bug", "--group-by=project"]),
+            ListFormat::Long,
+        )
+        .unwrap();
+        assert_eq!(options.sort, vec![SortKey::Due, SortKey::Title]);
+        assert_eq!(options.group_by, Some(GroupBy::Project));
+        assert!(!options.reverse);
+        assert_eq!(options.format, ListFormat::Long);
+        assert_eq!(rest, args(&["tag:bug"]));
+
+        let (options, _) = ListOptions::parse(
+            &args(&["--format", "table", "--description"]),
+            ListFormat::Long,
+        )
+        .unwrap();
+        assert_eq!(options.format, ListFormat::Table);
+        assert!(options.description);
+
+        let (options, _) = ListOptions::parse(
+            &args(&["--template={id} {title}", "--theme", "ascii"]),
+            ListFormat::Table,
+        )
+        .unwrap();
+        assert_eq!(options.format, ListFormat::Long);
+        assert_eq!(options.template(), "{id} {title}");
+        assert_eq!(options.theme, Some(Theme::named("ascii").unwrap()));
+
+        assert_eq!(
+            ListOptions::parse(&args(&["--sort", "colour"]), ListFormat::Long),
+            Err("Sort key can only be position, title, status, priority, due, created or updated")
+        );
+        assert_eq!(
+            ListOptions::parse(&args(&["--group-by"]), ListFormat::Long),
+            Err("Option requires a value")
+        );
+    }
+
+    #[test]
+    fn should_sort_with_missing_values_last_and_keep_positions() {
+        let todos = [
+            todo("c", &[]),
+            todo("a", &["priority:B"]),
+            todo("b", &["priority:A"]),
+        ];
+        let (options, _) =
+            ListOptions::parse(&args(&["--sort", "priority"]), ListFormat::Long).unwrap();
+        let mut indexed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        options.sort(&mut indexed);
+        let positions: Vec<usize> = indexed.iter().map(|(index, _)| *index).collect();
+        assert_eq!(positions, vec![2, 1, 0]);
+
+        let (options, _) =
+            ListOptions::parse(&args(&["--sort", "title", "--reverse"]), ListFormat::Long).unwrap();
+        options.sort(&mut indexed);
+        let positions: Vec<usize> = indexed.iter().map(|(index, _)| *index).collect();
+        assert_eq!(positions, vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn should_group_by_due_buckets() {
+        let todos = [
+            todo("later", &["due:2026-12-01"]),
+            todo("none", &[]),
+            todo("overdue", &["due:2026-10-01"]),
+            todo("this week", &["due:2026-10-22"]),
+            todo("today", &["due:2026-10-19"]),
+        ];
+        let (options, _) =
+            ListOptions::parse(&args(&["--group-by", "due"]), ListFormat::Long).unwrap();
+        let indexed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let groups = options.group(indexed, Date::new(2026, 10, 19).unwrap());
+        let groups: Vec<(String, Vec<usize>)> = groups
+            .into_iter()
+            .map(|(group, todos)| {
+                (
+                    group.unwrap().label().to_string(),
+                    todos.iter().map(|(index, _)| *index).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("Overdue".into(), vec![2]),
+                ("Today".into(), vec![4]),
+                ("This week".into(), vec![3]),
+                ("Later".into(), vec![0]),
+                ("No due date".into(), vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_list_items_under_each_of_their_tags() {
+        let todos = [todo("a", &["tags:ui,bug"]), todo("b", &[])];
+        let (options, _) =
+            ListOptions::parse(&args(&["--group-by", "tag"]), ListFormat::Long).unwrap();
+        let indexed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let keys: Vec<Option<String>> = options
+            .group(indexed, Date::today())
+            .into_iter()
+            .map(|(group, _)| group.unwrap().key)
+            .collect();
+        assert_eq!(keys, vec![Some("bug".into()), Some("ui".into()), None]);
+    }
+
+    #[test]
+    fn should_not_mix_a_project_named_like_the_missing_group() {
+        let todos = [todo("a", &[]), todo("b", &["project:No project"])];
+        let (options, _) =
+            ListOptions::parse(&args(&["--group-by", "project"]), ListFormat::Long).unwrap();
+        let indexed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let groups: Vec<(Option<String>, usize)> = options
+            .group(indexed, Date::today())
+            .into_iter()
+            .map(|(group, todos)| (group.unwrap().key, todos.len()))
+            .collect();
+        assert_eq!(groups, vec![(Some("No project".into()), 1), (None, 1)]);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    config::{self, Source},
+    date,
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::{database, Action, Confirm};
+
+impl Action<'_> {
+    pub(super) fn lists(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let current_list = database::current_list();
+
+        let mut lists: Vec<Json> = vec![];
+
+        for name in database.list_names()? {
+            let item_count = database.read_list(&name)?.len();
+            let current = name == current_list;
+
+            self.log_text(&format!(
+                "{} {name} ({item_count})",
+                if current { "*" } else { " " }
+            ));
+
+            lists.push(Json::object(vec![
+                ("name", Json::string(&name)),
+                ("current", Json::Bool(current)),
+                ("items", Json::Number(item_count as i64)),
+            ]));
+        }
+
+        self.log_json(lists, Json::Array)
+    }
+
+    pub(super) fn list_create(&mut self) -> Result<(), &'static str> {
+        let name = match self.arguments.as_slice() {
+            [name] => name.clone(),
+            _ => return Err("list-create needs the name of the new list"),
+        };
+
+        self.database
+            .expect("Database could not be found")
+            .create_list(&name)?;
+
+        self.log_text(&format!("Created the {name} list"));
+        self.log_list_change("list-create", &name, None)
+    }
+
+    pub(super) fn list_rename(&mut self) -> Result<(), &'static str> {
+        let (name, new_name) = match self.arguments.as_slice() {
+            [name, new_name] => (name.clone(), new_name.clone()),
+            _ => return Err("list-rename needs the current and the new name of the list"),
+        };
+
+        self.database
+            .expect("Database could not be found")
+            .rename_list(&name, &new_name)?;
+
+        // Keep using the list under its new name when it was picked with `use`
+        if let Some((current_list, Source::File(path))) = config::current().lookup("database.list")
+        {
+            if current_list == name {
+                config::set_in_file(path, "database.list", &new_name)?;
+            }
+        }
+
+        self.log_text(&format!("Renamed the {name} list to {new_name}"));
+        self.log_list_change("list-rename", &name, Some(&new_name))
+    }
+
+    pub(super) fn list_delete(&mut self) -> Result<(), &'static str> {
+        let name = match self.arguments.as_slice() {
+            [name] => name.clone(),
+            _ => return Err("list-delete needs the name of the list"),
+        };
+
+        if name == database::current_list() {
+            return Err("The list in use cannot be deleted, switch to another one with use first");
+        }
+
+        let database = self.database.expect("Database could not be found");
+        let item_count = database.read_list(&name)?.len();
+
+        if item_count > 0
+            && Confirm::configured()?.asks(item_count)
+            && !self.confirm(&format!(
+                "Delete the {name} list and its {item_count} ToDo item{}?",
+                if item_count == 1 { "" } else { "s" }
+            ))
+        {
+            self.log_text("Nothing was deleted");
+            return Ok(());
+        }
+
+        database.delete_list(&name)?;
+
+        self.log_text(&format!("Deleted the {name} list"));
+        self.log_list_change("list-delete", &name, None)
+    }
+
+    /// Switches the list used by other actions, remembering it in the user
+    /// config file.
+    pub(super) fn use_list(&mut self) -> Result<(), &'static str> {
+        let name = match self.arguments.as_slice() {
+            [name] => name.clone(),
+            _ => return Err("use needs the name of a list"),
+        };
+
+        let database = self.database.expect("Database could not be found");
+
+        if !database.list_names()?.contains(&name) {
+            return Err("List does not exist, create it with list-create");
+        }
+
+        let path = match config::user_config_path() {
+            Some(path) => path,
+            None => return Err("Could not find where to put the config file"),
+        };
+
+        config::set_in_file(&path, "database.list", &name)?;
+
+        self.log_text(&format!("Now using the {name} list"));
+        self.log_list_change("use", &name, None)
+    }
+
+    /// Moves the selected items from the list in use to the end of the one
+    /// given with `--to`.
+    pub(super) fn move_items(&mut self) -> Result<(), &'static str> {
+        let mut target: Option<String> = None;
+        let mut selection: Vec<String> = vec![];
+        let mut arguments = self.arguments.clone().into_iter();
+
+        while let Some(arg) = arguments.next() {
+            match arg.strip_prefix("--to=") {
+                Some(list) => target = Some(list.into()),
+                None if arg == "--to" => target = arguments.next(),
+                None => selection.push(arg),
+            }
+        }
+
+        let target = match target {
+            Some(target) => target,
+            None => return Err("move needs the list to move items to, given with --to <list>"),
+        };
+
+        if target == database::current_list() {
+            return Err("Items are already in that list");
+        }
+
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let mut target_todos: Vec<ToDo> = database.read_list(&target)?;
+
+        self.arguments = selection;
+        let item_indexes: Vec<usize> =
+            self.get_item_indexes_arg(&todos, "Given item index is wrong")?;
+
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for item_index in item_indexes.iter() {
+            self.log_text(&format!(
+                "Moving #{} ToDo item to the {target} list",
+                item_index + 1
+            ));
+
+            let mut todo = todos[*item_index].clone();
+            todo.touch(date::now());
+            target_todos.push(todo);
+
+            changes.push((
+                Some(output::todo_to_json(item_index + 1, &todos[*item_index])),
+                Some(output::todo_to_json(
+                    target_todos.len(),
+                    target_todos.last().unwrap(),
+                )),
+            ));
+        }
+
+        let todos: Vec<ToDo> = todos
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| item_indexes.binary_search(index).is_err())
+            .map(|(_, todo)| todo)
+            .collect();
+
+        // The items are added to the other list before being taken out of this
+        // one, so nothing is lost if storing fails half way
+        database.store_list(&target, target_todos)?;
+        database.store_existing_items(todos)?;
+
+        self.log_changes("move", changes)
+    }
+
+    fn log_list_change(
+        &mut self,
+        action_name: &str,
+        list: &str,
+        new_name: Option<&str>,
+    ) -> Result<(), &'static str> {
+        let mut fields = vec![
+            ("action", Json::string(action_name)),
+            ("list", Json::string(list)),
+        ];
+
+        if let Some(new_name) = new_name {
+            fields.push(("new_name", Json::string(new_name)));
+        }
+
+        self.log_json_value(Json::object(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{action::test_database::TestDatabase, log_wrapper::LogWrapper};
+
+    #[test]
+    fn should_move_items_to_the_end_of_another_list() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b", "c"]).with_list("work", &["d"]);
+        let mut move_action = Action::new("move", &mut logger, &database).unwrap();
+        move_action.arguments = vec!["1".into(), "3".into(), "--to".into(), "work".into()];
+
+        assert_eq!(move_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+        assert_eq!(database.list_titles("work"), vec!["d", "a", "c"]);
+        assert_eq!(
+            logger.std_writer,
+            "Moving #1 ToDo item to the work list\nMoving #3 ToDo item to the work list\n"
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn should_not_move_items_without_a_target_list() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a"]);
+
+        let mut move_action = Action::new("move", &mut logger, &database).unwrap();
+        move_action.arguments = vec!["1".into()];
+        assert_eq!(
+            move_action.execute_action(),
+            Err("move needs the list to move items to, given with --to <list>")
+        );
+
+        let mut move_action = Action::new("move", &mut logger, &database).unwrap();
+        move_action.arguments = vec!["1".into(), "--to=personal".into()];
+        assert_eq!(move_action.execute_action(), Err("List does not exist"));
+        assert_eq!(database.titles(), vec!["a"]);
+    }
+
+    #[test]
+    fn should_list_lists_with_their_item_count() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b"]).with_list("work", &[]);
+        let mut lists_action = Action::new("lists", &mut logger, &database).unwrap();
+
+        assert_eq!(lists_action.execute_action(), Ok(()));
+        assert_eq!(logger.std_writer, "* default (2)\n  work (0)\n".as_bytes());
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    date,
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::Action;
+
+/// The kinds of files a list can be synced with, told apart by their
+/// extension.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Checklist {
+    Markdown,
+    Org,
+}
+
+/// An item of a checklist file, with the line it is on.
+#[derive(PartialEq, Debug)]
+pub struct Entry {
+    pub line: usize,
+    pub title: String,
+    pub done: bool,
+}
+
+/// What syncing a file changed in the list: items checked or unchecked in
+/// the file and items added to it, each with its line and its position in
+/// the list, and the lines whose changes were not taken, with why. Also
+/// which line of the file has which item of the list, and the lines of items
+/// removed from the list since the last sync.
+#[derive(PartialEq, Debug)]
+pub struct Merged {
+    pub todos: Vec<ToDo>,
+    pub updated: Vec<(usize, usize)>,
+    pub created: Vec<(usize, usize)>,
+    pub conflicts: Vec<(usize, String, &'static str)>,
+    pub matched: Vec<(usize, usize)>,
+    pub removed: Vec<usize>,
+}
+
+impl Checklist {
+    pub fn new(path: &Path) -> Result<Self, &'static str> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("md" | "markdown") => Ok(Checklist::Markdown),
+            Some("org") => Ok(Checklist::Org),
+            _ => Err("Only Markdown (.md) and Org (.org) files can be synced"),
+        }
+    }
+
+    /// Writes items as `- [x] title` checkboxes, or `* DONE title` headlines
+    /// in Org files.
+    pub fn render(&self, todos: &[&ToDo]) -> String {
+        todos
+            .iter()
+            .map(|todo| {
+                let title = todo.title.replace('\n', " ");
+                match (self, todo.is_done()) {
+                    (Checklist::Markdown, true) => format!("- [x] {title}\n"),
+                    (Checklist::Markdown, false) => format!("- [ ] {title}\n"),
+                    (Checklist::Org, true) => format!("* DONE {title}\n"),
+                    (Checklist::Org, false) => format!("* TODO {title}\n"),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the list back into `contents`, the file it was merged with.
+    /// Only the lines of items change: those still in the list are checked or
+    /// unchecked the way the list has them, and those removed from it are
+    /// taken out. Items new to the file are added at its end, and every other
+    /// line is kept as it is.
+    pub fn rewrite(&self, contents: &str, merged: &Merged) -> String {
+        let mut rewritten = String::new();
+
+        for (index, line) in contents.split_inclusive('\n').enumerate() {
+            let line_number = index + 1;
+
+            if merged.removed.contains(&line_number) {
+                continue;
+            }
+            match merged
+                .matched
+                .iter()
+                .find(|(matched_line, _)| *matched_line == line_number)
+            {
+                Some((_, todo_index)) => {
+                    rewritten += &self.mark(line, merged.todos[*todo_index].is_done())
+                }
+                None => rewritten += line,
+            }
+        }
+
+        let new_items: Vec<&ToDo> = merged
+            .todos
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !merged.matched.iter().any(|(_, matched)| matched == index))
+            .map(|(_, todo)| todo)
+            .collect();
+
+        if !new_items.is_empty() && !rewritten.is_empty() && !rewritten.ends_with('\n') {
+            rewritten.push('\n');
+        }
+
+        rewritten + &self.render(&new_items)
+    }
+
+    /// Checks or unchecks the item on `line`, a line `parse` read an entry
+    /// from, leaving the rest of it as it is.
+    fn mark(&self, line: &str, done: bool) -> String {
+        let indent = line.len() - line.trim_start().len();
+        let item = &line[indent..];
+
+        let (at, marks) = match (self, item.starts_with('*')) {
+            // After the stars of the headline and a space
+            (Checklist::Org, true) => (
+                indent + item.len() - item.trim_start_matches('*').len() + 1,
+                ("TODO", "DONE"),
+            ),
+            // Inside the brackets after the bullet and a space
+            _ => (indent + 3, (" ", "x")),
+        };
+        let (open, closed) = marks;
+        let current = &line[at..at + open.len()];
+
+        if (current != open) == done {
+            return line.into();
+        }
+
+        let mark = if done { closed } else { open };
+        format!("{}{mark}{}", &line[..at], &line[at + open.len()..])
+    }
+
+    /// Reads the checkboxes of a file, and the `TODO` and `DONE` headlines of
+    /// Org files. Other lines are left out.
+    pub fn parse(&self, contents: &str) -> Vec<Entry> {
+        contents
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let (done, title) = match (self, line.trim_start()) {
+                    (Checklist::Org, headline) if headline.starts_with('*') => {
+                        match headline.trim_start_matches('*').strip_prefix(' ')? {
+                            todo if todo.starts_with("TODO ") => (false, &todo[5..]),
+                            done if done.starts_with("DONE ") => (true, &done[5..]),
+                            _ => return None,
+                        }
+                    }
+                    (_, item) => {
+                        let item = item
+                            .strip_prefix("- ")
+                            .or_else(|| item.strip_prefix("* "))
+                            .or_else(|| item.strip_prefix("+ "))?;
+                        match item.get(..4)? {
+                            "[ ] " => (false, &item[4..]),
+                            "[x] " | "[X] " => (true, &item[4..]),
+                            _ => return None,
+                        }
+                    }
+                };
+
+                let title = title.trim();
+                (!title.is_empty()).then(|| Entry {
+                    line: index + 1,
+                    title: title.into(),
+                    done,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The position of the `occurrence`th item titled `title`, counting from 0.
+fn find(todos: &[ToDo], title: &str, occurrence: usize) -> Option<usize> {
+    todos
+        .iter()
+        .enumerate()
+        .filter(|(_, todo)| todo.title.replace('\n', " ").trim() == title)
+        .nth(occurrence)
+        .map(|(index, _)| index)
+}
+
+/// Takes the changes made to a file into the list. Items are matched by
+/// title, and `base` is the list as it was written to the file when it was
+/// last synced, which tells which side checked or unchecked an item. When
+/// that cannot be told, or the item was renamed or removed in the list since,
+/// the list is kept.
+pub fn merge(todos: &[ToDo], base: Option<&[ToDo]>, entries: &[Entry], now: u64) -> Merged {
+    let mut merged = Merged {
+        todos: todos.to_vec(),
+        updated: vec![],
+        created: vec![],
+        conflicts: vec![],
+        matched: vec![],
+        removed: vec![],
+    };
+    // How many items with each title were gone through, for lists where
+    // several items have the same one
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+
+    for entry in entries {
+        let occurrence = occurrences.entry(&entry.title).or_default();
+        let in_list = find(todos, &entry.title, *occurrence);
+        let in_base = base.and_then(|base| {
+            find(base, &entry.title, *occurrence).map(|index| base[index].is_done())
+        });
+        *occurrence += 1;
+
+        if let Some(index) = in_list {
+            merged.matched.push((entry.line, index));
+        }
+
+        let conflict = match (in_list, in_base) {
+            (Some(index), Some(base_done)) => {
+                let list_done = todos[index].is_done();
+
+                // When both sides changed it, they agree
+                match entry.done != base_done && list_done == base_done {
+                    true => {
+                        let todo = &mut merged.todos[index];
+                        todo.done = if entry.done { "✅" } else { "❌" }.into();
+                        todo.touch(now);
+                        merged.updated.push((entry.line, index));
+                        None
+                    }
+                    false => None,
+                }
+            }
+            // Without knowing how the item was synced, which side changed
+            // cannot be told
+            (Some(index), None) if todos[index].is_done() != entry.done => {
+                Some("is checked in one of the file and the list but not the other")
+            }
+            (Some(_), None) => None,
+            (None, Some(base_done)) if base_done != entry.done => {
+                Some("was checked or unchecked in the file but renamed or removed in the list")
+            }
+            (None, Some(_)) => {
+                merged.removed.push(entry.line);
+                None
+            }
+            (None, None) => {
+                let mut todo = ToDo::new(entry.title.clone(), String::new());
+                if entry.done {
+                    todo.done = "✅".into();
+                }
+                todo.created = Some(now);
+                todo.touch(now);
+
+                merged.todos.push(todo);
+                merged.created.push((entry.line, merged.todos.len() - 1));
+                merged.matched.push((entry.line, merged.todos.len() - 1));
+                None
+            }
+        };
+
+        if let Some(reason) = conflict {
+            merged
+                .conflicts
+                .push((entry.line, entry.title.clone(), reason));
+        }
+    }
+
+    merged
+}
+
+impl Action<'_> {
+    /// `sync <file>` takes the items checked, unchecked or added in a
+    /// Markdown or Org checklist into the list, then writes the list back to
+    /// the file, leaving the lines that are not items as they are.
+    pub(super) fn sync(&mut self) -> Result<(), &'static str> {
+        let path: PathBuf = match self.arguments.as_slice() {
+            [path] => path.into(),
+            _ => return Err("Sync should be used as sync <file>"),
+        };
+        let checklist = Checklist::new(&path)?;
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(_) => return Err("Could not read the file to sync"),
+        };
+        let entries = checklist.parse(&contents);
+
+        let database = self.database.expect("Database could not be found");
+        let todos: Vec<ToDo> = database.read_items()?;
+        let base = database.read_sync_base(&path)?;
+
+        let merged = merge(&todos, base.as_deref(), &entries, date::now());
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for (line, index) in merged.updated.iter() {
+            let todo = &merged.todos[*index];
+            self.log_text(&format!(
+                "Line {line}: `{}` was marked {} in the file",
+                todo.title.replace('\n', " "),
+                if todo.is_done() { "done" } else { "open" }
+            ));
+            changes.push((
+                Some(output::todo_to_json(index + 1, &todos[*index])),
+                Some(output::todo_to_json(index + 1, todo)),
+            ));
+        }
+        for (line, index) in merged.created.iter() {
+            let todo = &merged.todos[*index];
+            self.log_text(&format!(
+                "Line {line}: `{}` was added in the file",
+                todo.title
+            ));
+            changes.push((None, Some(output::todo_to_json(index + 1, todo))));
+        }
+        for (line, title, reason) in merged.conflicts.iter() {
+            self.log_text(&format!(
+                "Line {line}: `{title}` {reason}, the list was kept"
+            ));
+        }
+
+        if !changes.is_empty() {
+            database.store_existing_items(merged.todos.clone())?;
+            self.record_change(todos, &merged.todos)?;
+        }
+
+        if fs::write(&path, checklist.rewrite(&contents, &merged)).is_err() {
+            return Err("Could not write the synced file");
+        }
+        database.store_sync_base(&path, &merged.todos)?;
+
+        self.log_text(&format!(
+            "Synced {} ToDo item{} with {}, {} changed and {} added in the file, {} conflict{}",
+            merged.todos.len(),
+            if merged.todos.len() == 1 { "" } else { "s" },
+            path.display(),
+            merged.updated.len(),
+            merged.created.len(),
+            merged.conflicts.len(),
+            if merged.conflicts.len() == 1 { "" } else { "s" }
+        ));
+
+        self.log_changes("sync", changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, done: bool) -> ToDo {
+        let mut todo = ToDo::new(title.into(), String::new());
+        if done {
+            todo.done = "✅".into();
+        }
+        todo
+    }
+
+    fn entry(line: usize, title: &str, done: bool) -> Entry {
+        Entry {
+            line,
+            title: title.into(),
+            done,
+        }
+    }
+
+    #[test]
+    fn should_read_checklists() {
+        assert_eq!(
+            Checklist::Markdown
+                .parse("# Chores\n- [ ] Call Mom\n  * [X] Pay rent\n- [ ]\n- [-] Maybe\nNotes\n"),
+            vec![entry(2, "Call Mom", false), entry(3, "Pay rent", true)]
+        );
+        assert_eq!(
+            Checklist::Org.parse(
+                "#+TITLE: Chores\n* TODO Call Mom\n** DONE Pay rent\n- [X] Water plants\n* Notes\n"
+            ),
+            vec![
+                entry(2, "Call Mom", false),
+                entry(3, "Pay rent", true),
+                entry(4, "Water plants", true)
+            ]
+        );
+        assert_eq!(
+            Checklist::Org.render(&[&todo("Call Mom", false), &todo("Pay rent", true)]),
+            "* TODO Call Mom\n* DONE Pay rent\n"
+        );
+        assert_eq!(
+            Checklist::new(Path::new("todo.txt")),
+            Err("Only Markdown (.md) and Org (.org) files can be synced")
+        );
+    }
+
+    #[test]
+    fn should_merge_changes_from_both_sides() {
+        let base = vec![
+            todo("Call Mom", false),
+            todo("Pay rent", false),
+            todo("Water plants", false),
+            todo("Old title", false),
+        ];
+        // The list marked Pay rent done, and renamed the last item
+        let todos = vec![
+            todo("Call Mom", false),
+            todo("Pay rent", true),
+            todo("Water plants", false),
+            todo("New title", false),
+        ];
+        // The file checked Call Mom and Old title, and added an item
+        let entries = vec![
+            entry(1, "Call Mom", true),
+            entry(2, "Pay rent", false),
+            entry(3, "Water plants", false),
+            entry(4, "Old title", true),
+            entry(5, "Buy milk", false),
+        ];
+
+        let merged = merge(&todos, Some(&base), &entries, 1_760_000_000);
+
+        assert!(merged.todos[0].is_done());
+        assert_eq!(merged.todos[0].updated, Some(1_760_000_000));
+        assert!(merged.todos[1].is_done());
+        assert_eq!(merged.todos[4].title, "Buy milk");
+        assert_eq!(merged.updated, vec![(1, 0)]);
+        assert_eq!(merged.created, vec![(5, 4)]);
+        assert_eq!(
+            merged.conflicts,
+            vec![(
+                4,
+                "Old title".to_string(),
+                "was checked or unchecked in the file but renamed or removed in the list"
+            )]
+        );
+
+        let merged = merge(&todos, None, &entries[..2], 1_760_000_000);
+        assert_eq!(merged.todos, todos);
+        assert_eq!(merged.conflicts.len(), 2);
+    }
+
+    #[test]
+    fn should_rewrite_only_the_item_lines() {
+        let contents = "# Chores\n- [ ] Call Mom\n  Notes about calling\n- [-] Maybe later\n\
+                        ## Weekend\n  * [X] Pay rent\n- [ ] Old item\nMore notes";
+        let base = vec![
+            todo("Call Mom", false),
+            todo("Pay rent", true),
+            todo("Old item", false),
+        ];
+        // The list checked Call Mom, removed Old item and added New item
+        let todos = vec![
+            todo("Call Mom", true),
+            todo("Pay rent", true),
+            todo("New item", false),
+        ];
+        let checklist = Checklist::Markdown;
+        let entries = checklist.parse(contents);
+
+        let merged = merge(&todos, Some(&base), &entries, 1_760_000_000);
+
+        assert_eq!(
+            checklist.rewrite(contents, &merged),
+            "# Chores\n- [x] Call Mom\n  Notes about calling\n- [-] Maybe later\n\
+             ## Weekend\n  * [X] Pay rent\nMore notes\n- [ ] New item\n"
+        );
+
+        let contents =
+            "* Chores\n** DONE Call Mom\nBody text\n*** TODO Pay rent\n- [ ] Water plants\n";
+        let todos = vec![
+            todo("Call Mom", false),
+            todo("Pay rent", true),
+            todo("Water plants", true),
+        ];
+        let base = vec![
+            todo("Call Mom", true),
+            todo("Pay rent", false),
+            todo("Water plants", false),
+        ];
+        let checklist = Checklist::Org;
+        let entries = checklist.parse(contents);
+
+        let merged = merge(&todos, Some(&base), &entries, 1_760_000_000);
+
+        assert_eq!(
+            checklist.rewrite(contents, &merged),
+            "* Chores\n** TODO Call Mom\nBody text\n*** DONE Pay rent\n- [x] Water plants\n"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use crate::{
+    date::Date,
+    log_wrapper::Style,
+    text::{display_width, truncate},
+    theme::Theme,
+    todo::ToDo,
+};
+
+const SEPARATOR: &str = "  ";
+const MIN_TEXT_WIDTH: usize = 10;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Column {
+    Number,
+    Done,
+    Title,
+    Priority,
+    Due,
+    Project,
+    Tags,
+    Description,
+}
+
+impl Column {
+    fn heading(&self) -> &'static str {
+        match self {
+            Column::Number => "#",
+            Column::Done => "Done",
+            Column::Title => "Title",
+            Column::Priority => "Pri",
+            Column::Due => "Due",
+            Column::Project => "Project",
+            Column::Tags => "Tags",
+            Column::Description => "Description",
+        }
+    }
+
+    fn value(&self, index: usize, todo: &ToDo, theme: &Theme) -> String {
+        match self {
+            Column::Number => (index + 1).to_string(),
+            Column::Done => theme.status(todo).into(),
+            Column::Title => todo.title.replace('\n', ""),
+            Column::Priority => todo.priority.map(String::from).unwrap_or_default(),
+            Column::Due => todo
+                .due
+                .map(|due| due.format(&theme.date_format))
+                .unwrap_or_default(),
+            Column::Project => todo.project.clone().unwrap_or_default(),
+            Column::Tags => todo.tags.join(","),
+            Column::Description => todo.description.replace('\n', ""),
+        }
+    }
+}
+
+/// One-line-per-item layout for `list --format table`. Column widths are
+/// worked out from every item that will be printed so rows line up across
+/// groups, and the title (and description) columns are truncated to fit in
+/// `width` when it is known.
+pub struct Table {
+    columns: Vec<(Column, usize)>,
+    theme: Theme,
+}
+
+impl Table {
+    pub fn new(
+        todos: &[(usize, &ToDo)],
+        show_description: bool,
+        width: Option<usize>,
+        theme: Theme,
+    ) -> Self {
+        let mut columns = vec![Column::Number, Column::Done, Column::Title];
+
+        for optional_column in [Column::Priority, Column::Due, Column::Project, Column::Tags] {
+            if todos
+                .iter()
+                .any(|(index, todo)| !optional_column.value(*index, todo, &theme).is_empty())
+            {
+                columns.push(optional_column);
+            }
+        }
+
+        if show_description {
+            columns.push(Column::Description);
+        }
+
+        let mut columns: Vec<(Column, usize)> = columns
+            .into_iter()
+            .map(|column| {
+                let widest = todos
+                    .iter()
+                    .map(|(index, todo)| display_width(&column.value(*index, todo, &theme)))
+                    .max()
+                    .unwrap_or(0);
+                (column, widest.max(display_width(column.heading())))
+            })
+            .collect();
+
+        if let Some(width) = width {
+            fit_text_columns(&mut columns, width);
+        }
+
+        Table { columns, theme }
+    }
+
+    pub fn header(&self) -> String {
+        join_parts(&self.header_parts())
+    }
+
+    pub fn row(&self, index: usize, todo: &ToDo, today: Date) -> String {
+        join_parts(&self.row_parts(index, todo, today))
+    }
+
+    pub fn header_parts(&self) -> Vec<(String, Style)> {
+        self.render(|column| (column.heading().to_string(), self.theme.header))
+    }
+
+    pub fn row_parts(&self, index: usize, todo: &ToDo, today: Date) -> Vec<(String, Style)> {
+        self.render(|column| {
+            let style = match column {
+                Column::Title if todo.is_done() => self.theme.done,
+                Column::Priority => todo
+                    .priority
+                    .map_or(Style::PLAIN, |priority| self.theme.priority(priority)),
+                Column::Due if !todo.is_done() && todo.due.is_some_and(|due| due < today) => {
+                    self.theme.overdue
+                }
+                Column::Tags => self.theme.tags,
+                _ => Style::PLAIN,
+            };
+            (column.value(index, todo, &self.theme), style)
+        })
+    }
+
+    fn render<F: Fn(&Column) -> (String, Style)>(&self, cell: F) -> Vec<(String, Style)> {
+        let mut parts: Vec<(String, Style)> = vec![];
+
+        for (position, (column, width)) in self.columns.iter().enumerate() {
+            let (value, style) = cell(column);
+            let text = truncate(&value, *width);
+            let padding = " ".repeat(width - display_width(&text));
+
+            if position > 0 {
+                parts.push((SEPARATOR.into(), Style::PLAIN));
+            }
+
+            match column {
+                Column::Number => {
+                    parts.push((padding, Style::PLAIN));
+                    parts.push((text, style));
+                }
+                _ => {
+                    parts.push((text, style));
+                    parts.push((padding, Style::PLAIN));
+                }
+            }
+        }
+
+        // No trailing whitespace after the last column with a value
+        while parts
+            .last()
+            .is_some_and(|(text, _)| text.trim_end().is_empty())
+        {
+            parts.pop();
+        }
+
+        parts
+    }
+}
+
+fn join_parts(parts: &[(String, Style)]) -> String {
+    parts.iter().map(|(text, _)| text.as_str()).collect()
+}
+
+/// Shrinks the title and description columns, which are the only ones that
+/// can get long, until the whole row fits in `width`.
+fn fit_text_columns(columns: &mut [(Column, usize)], width: usize) {
+    let row_width: usize = columns.iter().map(|(_, width)| width).sum::<usize>()
+        + SEPARATOR.len() * (columns.len() - 1);
+
+    if row_width <= width {
+        return;
+    }
+
+    let mut overflow = row_width - width;
+
+    // Take from the description first, then from the title
+    for text_column in [Column::Description, Column::Title] {
+        if let Some((_, column_width)) = columns
+            .iter_mut()
+            .find(|(column, _)| *column == text_column)
+        {
+            let shrink = overflow.min(column_width.saturating_sub(MIN_TEXT_WIDTH));
+            *column_width -= shrink;
+            overflow -= shrink;
+        }
+    }
+}
+
+/// The width to fit table rows in, or `None` when the output is not going to
+/// a terminal and nothing should be truncated. `COLUMNS` overrides the width
+/// the terminal reports.
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+
+    let columns = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .filter(|columns| *columns > 0);
+
+    Some(columns.or_else(window_width).unwrap_or(80))
+}
+
+/// The number of columns of the terminal stdout is connected to.
+#[cfg(unix)]
+fn window_width() -> Option<usize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a winsize into the one it is given
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn window_width() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, fields: &[&str]) -> ToDo {
+        let mut todo = ToDo::new(title.into(), "some description".into());
+        for field in fields {
+            todo.set_field(field).unwrap();
+        }
+        todo
+    }
+
+    #[test]
+    fn should_align_columns_and_only_show_used_ones() {
+        let todos = [todo("Fix login", &["due:2026-11-01"]), todo("Docs", &[])];
+        let listed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let table = Table::new(&listed, false, None, Theme::default());
+
+        let today = Date::new(2026, 10, 19).unwrap();
+
+        assert_eq!(table.header(), "#  Done  Title      Due");
+        assert_eq!(
+            table.row(0, &todos[0], today),
+            "1  ❌    Fix login  2026-11-01"
+        );
+        assert_eq!(table.row(1, &todos[1], today), "2  ❌    Docs");
+    }
+
+    #[test]
+    fn should_truncate_long_text_to_the_terminal_width() {
+        let todos = [todo("A rather long title that will not fit", &[])];
+        let listed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let table = Table::new(&listed, true, Some(40), Theme::default());
+
+        let row = table.row(0, &todos[0], Date::today());
+        assert_eq!(row, "1  ❌    A rather long titl…  some desc…");
+        assert!(display_width(&row) <= 40);
+    }
+
+    #[test]
+    fn should_style_done_overdue_and_priority_cells() {
+        let mut overdue = todo("Overdue", &["due:2026-10-01", "priority:A"]);
+        let mut done = todo("Done", &["due:2026-10-01"]);
+        done.done = "✅".into();
+        overdue.description = "".into();
+        let todos = [overdue, done];
+        let listed: Vec<(usize, &ToDo)> = todos.iter().enumerate().collect();
+        let theme = Theme::default();
+        let table = Table::new(&listed, false, None, theme.clone());
+        let today = Date::new(2026, 10, 19).unwrap();
+
+        let styles = |parts: Vec<(String, Style)>| -> Vec<(String, Style)> {
+            parts
+                .into_iter()
+                .filter(|(text, style)| *style != Style::PLAIN && !text.is_empty())
+                .collect()
+        };
+
+        assert_eq!(
+            styles(table.row_parts(0, &todos[0], today)),
+            vec![
+                ("A".to_string(), theme.priority('A')),
+                ("2026-10-01".to_string(), theme.overdue)
+            ]
+        );
+        assert_eq!(
+            styles(table.row_parts(1, &todos[1], today)),
+            vec![("Done".to_string(), theme.done)]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{date::Date, output::Json, todo::ToDo};
+
+use super::{
+    database,
+    ical::{format_date_time, parse_timestamp},
+    import::{Imported, Unmapped},
+};
+
+/// Annotations are joined into the description with this between them, and
+/// the description is split on it into annotations again.
+const ANNOTATION_SEPARATOR: &str = "; ";
+
+/// Taskwarrior only knows three priorities, which letters A to C stand for.
+/// Lower letters are exported as low.
+fn priority_name(priority: char) -> &'static str {
+    match priority {
+        'A' => "H",
+        'B' => "M",
+        _ => "L",
+    }
+}
+
+/// A uuid for an item that did not come from Taskwarrior, made from its UID
+/// so that it is the same every time the item is exported.
+pub fn uuid(uid: &str) -> String {
+    let high = database::fnv1a(uid.as_bytes());
+    let low = database::fnv1a(format!("{uid}#").as_bytes());
+    // Marked as a version 8 uuid, whose bits are left to the application
+    let high = (high & !0xf000) | 0x8000;
+    let low = (low & !(0b11 << 62)) | (0b10 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// Reads the JSON written by `task export`. Annotations are joined into the
+/// description, and deleted tasks and the templates of recurring ones are
+/// left out.
+pub fn parse(json: &str) -> Result<Imported, &'static str> {
+    let tasks = match Json::parse_array(json) {
+        Ok(tasks) => tasks,
+        Err(_) => return Err("The file is not a Taskwarrior export, which is a JSON array"),
+    };
+    let mut todos: Vec<(usize, ToDo)> = vec![];
+    let mut unmapped: Vec<Unmapped> = vec![];
+
+    for (line, task) in tasks {
+        let fields = match task {
+            Json::Object(fields) => fields,
+            other => {
+                unmapped.push(Unmapped {
+                    line,
+                    text: other.to_string(),
+                    reason: "it is not a task",
+                });
+                continue;
+            }
+        };
+
+        let mut todo = ToDo::new(String::new(), String::new());
+        let mut annotations: Vec<String> = vec![];
+        let mut left_out: Option<&'static str> = None;
+
+        for (key, value) in fields.iter() {
+            let timestamp = |value: &Json| match value {
+                Json::String(value) => parse_timestamp(value),
+                _ => None,
+            };
+
+            let valid = match (key.as_str(), value) {
+                ("uuid", Json::String(uuid)) => {
+                    todo.uuid = Some(uuid.clone());
+                    true
+                }
+                ("description", Json::String(description)) => {
+                    todo.title = description.clone();
+                    true
+                }
+                ("status", Json::String(status)) => match status.as_str() {
+                    "pending" | "waiting" => true,
+                    "completed" => {
+                        todo.done = "✅".into();
+                        true
+                    }
+                    "deleted" => {
+                        left_out = Some("the task was deleted");
+                        true
+                    }
+                    "recurring" => {
+                        left_out = Some("the task is the template of a recurring task");
+                        true
+                    }
+                    _ => false,
+                },
+                ("entry", _) => timestamp(value)
+                    .map(|created| todo.created = Some(created))
+                    .is_some(),
+                ("modified" | "end", _) => timestamp(value)
+                    .map(|updated| todo.updated = todo.updated.max(Some(updated)))
+                    .is_some(),
+                ("due", _) => timestamp(value)
+                    .map(|due| todo.due = Some(Date::from_timestamp(due)))
+                    .is_some(),
+                ("priority", Json::String(priority)) => match priority.as_str() {
+                    "H" => Some('A'),
+                    "M" => Some('B'),
+                    "L" => Some('C'),
+                    _ => None,
+                }
+                .map(|priority| todo.priority = Some(priority))
+                .is_some(),
+                ("project", Json::String(project)) => {
+                    todo.project = Some(project.clone());
+                    true
+                }
+                ("tags", Json::Array(tags)) => tags.iter().all(|tag| match tag {
+                    Json::String(tag) if !tag.contains(char::is_whitespace) => {
+                        todo.tags.push(tag.clone());
+                        true
+                    }
+                    _ => false,
+                }),
+                ("annotations", Json::Array(entries)) => {
+                    entries.iter().all(|entry| match entry.get("description") {
+                        Some(Json::String(annotation)) => {
+                            annotations.push(annotation.replace('\n', " "));
+                            true
+                        }
+                        _ => false,
+                    })
+                }
+                // Worked out by Taskwarrior for each export
+                ("id" | "urgency", _) => true,
+                _ => {
+                    unmapped.push(Unmapped {
+                        line,
+                        text: format!("{key}:{value}"),
+                        reason: "there is no such field",
+                    });
+                    continue;
+                }
+            };
+
+            if !valid {
+                unmapped.push(Unmapped {
+                    line,
+                    text: format!("{key}:{value}"),
+                    reason: "the value is not valid",
+                });
+            }
+        }
+
+        todo.description = annotations.join(ANNOTATION_SEPARATOR);
+
+        let reason = match (left_out, todo.title.is_empty()) {
+            (Some(reason), _) => reason,
+            (None, true) => "there is no text for the title",
+            (None, false) => {
+                todos.push((line, todo));
+                continue;
+            }
+        };
+        unmapped.push(Unmapped {
+            line,
+            text: todo.title,
+            reason,
+        });
+    }
+
+    Ok((todos, unmapped))
+}
+
+/// Writes items the way `task export` does, one task per line, each with
+/// its uuid. The description is written as annotations, split where imported
+/// ones were joined, so that reading it back gives the same description.
+pub fn serialise(todos: &[(String, &ToDo)]) -> String {
+    let tasks: Vec<String> = todos
+        .iter()
+        .map(|(uuid, todo)| {
+            let mut fields: Vec<(&str, Json)> = vec![
+                ("uuid", Json::string(uuid)),
+                ("description", Json::string(&todo.title.replace('\n', " "))),
+                (
+                    "status",
+                    Json::string(if todo.is_done() {
+                        "completed"
+                    } else {
+                        "pending"
+                    }),
+                ),
+            ];
+
+            if let Some(created) = todo.created {
+                fields.push(("entry", Json::string(&format_date_time(created))));
+            }
+            if let Some(updated) = todo.updated {
+                fields.push(("modified", Json::string(&format_date_time(updated))));
+                if todo.is_done() {
+                    fields.push(("end", Json::string(&format_date_time(updated))));
+                }
+            }
+            if let Some(due) = todo.due {
+                let due = format_date_time(due.days().max(0) as u64 * 86_400);
+                fields.push(("due", Json::string(&due)));
+            }
+            if let Some(priority) = todo.priority {
+                fields.push(("priority", Json::string(priority_name(priority))));
+            }
+            if let Some(project) = &todo.project {
+                fields.push(("project", Json::string(project)));
+            }
+            if !todo.tags.is_empty() {
+                let tags = todo.tags.iter().map(|tag| Json::string(tag)).collect();
+                fields.push(("tags", Json::Array(tags)));
+            }
+            if !todo.description.is_empty() {
+                let entry = todo.created.or(todo.updated).unwrap_or_default();
+                // Taskwarrior tells annotations apart by when they were made
+                let annotations = todo
+                    .description
+                    .split(ANNOTATION_SEPARATOR)
+                    .zip(entry..)
+                    .map(|(annotation, entry)| {
+                        Json::object(vec![
+                            ("entry", Json::string(&format_date_time(entry))),
+                            ("description", Json::string(annotation)),
+                        ])
+                    })
+                    .collect();
+                fields.push(("annotations", Json::Array(annotations)));
+            }
+
+            Json::object(fields).to_string()
+        })
+        .collect();
+
+    match tasks.is_empty() {
+        true => "[\n]\n".into(),
+        false => format!("[\n{}\n]\n", tasks.join(",\n")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_back_what_it_writes() {
+        let mut open = ToDo::new("Call Mom".into(), "Ask about the weekend".into());
+        open.priority = Some('A');
+        open.due = Some(Date::new(2026, 10, 1).unwrap());
+        open.tags = vec!["phone".into()];
+        open.project = Some("family".into());
+        open.created = Some(1_788_220_800);
+        open.updated = Some(1_788_224_400);
+        open.uuid = Some("5f1c2a4e-0c4b-4c1e-9a55-1f0a6d2b7e11".into());
+
+        let mut done = ToDo::new("Pay rent".into(), String::new());
+        done.done = "✅".into();
+        done.updated = Some(1_789_862_400);
+        done.uuid = Some(uuid("2-default@terminal-todo"));
+
+        let json = serialise(&[
+            (open.uuid.clone().unwrap(), &open),
+            (done.uuid.clone().unwrap(), &done),
+        ]);
+        assert!(json.contains(
+            r#""due":"20261001T000000Z","priority":"H","project":"family","tags":["phone"],"annotations":[{"entry":"20260901T000000Z","description":"Ask about the weekend"}]"#
+        ));
+
+        assert_eq!(parse(&json), Ok((vec![(2, open), (3, done)], vec![])));
+    }
+
+    #[test]
+    fn should_read_back_descriptions_joined_from_annotations() {
+        let todo = ToDo::new(
+            "Renew passport".into(),
+            "photos first; book an appointment".into(),
+        );
+        let json = serialise(&[(uuid("1-default@terminal-todo"), &todo)]);
+
+        assert!(json.contains(
+            r#""annotations":[{"entry":"19700101T000000Z","description":"photos first"},{"entry":"19700101T000001Z","description":"book an appointment"}]"#
+        ));
+        let (todos, unmapped) = parse(&json).unwrap();
+        assert_eq!(todos[0].1.description, todo.description);
+        assert_eq!(unmapped, vec![]);
+    }
+
+    #[test]
+    fn should_read_task_export() {
+        let (todos, unmapped) = parse(
+            r#"[
+{"id":1,"description":"Renew passport","entry":"20260901T081500Z","modified":"20260902T090000Z","status":"pending","uuid":"a1b2c3d4-0000-4000-8000-000000000001","annotations":[{"entry":"20260901T081600Z","description":"photos first"},{"entry":"20260902T090000Z","description":"book an appointment"}],"scheduled":"20261001T000000Z","priority":"X","urgency":4.9},
+{"id":0,"description":"Old task","status":"deleted","uuid":"a1b2c3d4-0000-4000-8000-000000000002"}
+]"#,
+        )
+        .unwrap();
+
+        assert_eq!(todos.len(), 1);
+        let (line, renew) = &todos[0];
+        assert_eq!(*line, 2);
+        assert_eq!(renew.title, "Renew passport");
+        assert_eq!(renew.description, "photos first; book an appointment");
+        assert_eq!(renew.created, Some(1_788_250_500));
+        assert_eq!(renew.updated, Some(1_788_339_600));
+        assert_eq!(
+            renew.uuid,
+            Some("a1b2c3d4-0000-4000-8000-000000000001".into())
+        );
+
+        let reasons: Vec<(usize, &str)> = unmapped
+            .iter()
+            .map(|unmapped| (unmapped.line, unmapped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (2, "there is no such field"),
+                (2, "the value is not valid"),
+                (3, "the task was deleted")
+            ]
+        );
+
+        assert_eq!(
+            parse("{}"),
+            Err("The file is not a Taskwarrior export, which is a JSON array")
+        );
+    }
+
+    #[test]
+    fn should_make_the_same_uuid_for_the_same_item() {
+        let made = uuid("1-default@terminal-todo");
+
+        assert_eq!(made, uuid("1-default@terminal-todo"));
+        assert_ne!(made, uuid("2-default@terminal-todo"));
+        assert_eq!(made.len(), 36);
+        assert_eq!(&made[14..15], "8");
+    }
+}
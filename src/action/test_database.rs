@@ -0,0 +1,190 @@
+use std::cell::RefCell;
+
+use crate::todo::ToDo;
+
+use super::{
+    database::{Database, DEFAULT_LIST},
+    events::Record,
+    journal::Journal,
+    trash::Trashed,
+};
+
+/// A database kept in memory for the tests of the actions, with the default
+/// list, other lists, the archive, the trash and the undo journal.
+#[derive(Default)]
+pub struct TestDatabase {
+    pub todos: RefCell<Vec<ToDo>>,
+    pub lists: RefCell<Vec<(String, Vec<ToDo>)>>,
+    pub archived: RefCell<Vec<ToDo>>,
+    pub trash: RefCell<Vec<Trashed>>,
+    pub journal: RefCell<Journal>,
+    pub records: Vec<Record>,
+    /// Lines of the default list that cannot be read, after its items.
+    pub unreadable_lines: RefCell<Vec<String>>,
+    pub quarantined: RefCell<Vec<String>>,
+}
+
+impl TestDatabase {
+    pub fn new(titles: &[&str]) -> Self {
+        TestDatabase::with_items(todos(titles))
+    }
+
+    pub fn with_items(todos: Vec<ToDo>) -> Self {
+        TestDatabase {
+            todos: RefCell::new(todos),
+            ..TestDatabase::default()
+        }
+    }
+
+    /// Adds a list other than the default one.
+    pub fn with_list(self, name: &str, titles: &[&str]) -> Self {
+        self.lists.borrow_mut().push((name.into(), todos(titles)));
+        self
+    }
+
+    pub fn titles(&self) -> Vec<String> {
+        titles(&self.todos.borrow())
+    }
+
+    pub fn list_titles(&self, name: &str) -> Vec<String> {
+        titles(&self.read_list(name).unwrap())
+    }
+
+    pub fn archived_titles(&self) -> Vec<String> {
+        titles(&self.archived.borrow())
+    }
+
+    pub fn trashed_titles(&self) -> Vec<String> {
+        self.trash
+            .borrow()
+            .iter()
+            .map(|trashed| trashed.todo.title.clone())
+            .collect()
+    }
+}
+
+pub fn todo(title: &str, done: bool) -> ToDo {
+    let mut todo = ToDo::new(title.into(), "description".into());
+    if done {
+        todo.done = "✅".into();
+    }
+    todo
+}
+
+/// Open items with the given titles.
+pub fn todos(titles: &[&str]) -> Vec<ToDo> {
+    titles.iter().map(|title| todo(title, false)).collect()
+}
+
+fn titles(todos: &[ToDo]) -> Vec<String> {
+    todos.iter().map(|todo| todo.title.clone()).collect()
+}
+
+impl Database for TestDatabase {
+    fn read_items(&self) -> Result<Vec<ToDo>, &'static str> {
+        match self.unreadable_lines.borrow().is_empty() {
+            true => Ok(self.todos.borrow().clone()),
+            false => Err("Database is corrupted, run doctor to find what cannot be read"),
+        }
+    }
+
+    fn store_existing_items(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        *self.todos.borrow_mut() = todos;
+        Ok(())
+    }
+
+    fn store_item(&self, todo: ToDo) -> Result<(), &'static str> {
+        self.todos.borrow_mut().push(todo);
+        Ok(())
+    }
+
+    fn list_names(&self) -> Result<Vec<String>, &'static str> {
+        let mut names: Vec<String> = vec![DEFAULT_LIST.into()];
+        names.extend(self.lists.borrow().iter().map(|(name, _)| name.clone()));
+        Ok(names)
+    }
+
+    fn create_list(&self, name: &str) -> Result<(), &'static str> {
+        self.lists.borrow_mut().push((name.into(), vec![]));
+        Ok(())
+    }
+
+    fn read_list(&self, name: &str) -> Result<Vec<ToDo>, &'static str> {
+        if name == DEFAULT_LIST {
+            return self.read_items();
+        }
+
+        match self.lists.borrow().iter().find(|(list, _)| list == name) {
+            Some((_, todos)) => Ok(todos.clone()),
+            None => Err("List does not exist"),
+        }
+    }
+
+    fn store_list(&self, name: &str, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        if name == DEFAULT_LIST {
+            return self.store_existing_items(todos);
+        }
+
+        match self
+            .lists
+            .borrow_mut()
+            .iter_mut()
+            .find(|(list, _)| list == name)
+        {
+            Some((_, stored)) => {
+                *stored = todos;
+                Ok(())
+            }
+            None => Err("List does not exist"),
+        }
+    }
+
+    fn read_archive(&self) -> Result<Vec<ToDo>, &'static str> {
+        Ok(self.archived.borrow().clone())
+    }
+
+    fn store_archive(&self, todos: Vec<ToDo>) -> Result<(), &'static str> {
+        *self.archived.borrow_mut() = todos;
+        Ok(())
+    }
+
+    fn read_trash(&self) -> Result<Vec<Trashed>, &'static str> {
+        Ok(self.trash.borrow().clone())
+    }
+
+    fn store_trash(&self, trash: Vec<Trashed>) -> Result<(), &'static str> {
+        *self.trash.borrow_mut() = trash;
+        Ok(())
+    }
+
+    fn change_records(&self) -> Result<Vec<Record>, &'static str> {
+        Ok(self.records.clone())
+    }
+
+    fn read_list_lines(&self) -> Result<Vec<String>, &'static str> {
+        let mut lines: Vec<String> = self
+            .todos
+            .borrow()
+            .iter()
+            .map(|todo| todo.serialise().trim_end().to_string())
+            .collect();
+        lines.extend(self.unreadable_lines.borrow().iter().cloned());
+        Ok(lines)
+    }
+
+    fn repair_list(&self, todos: Vec<ToDo>, quarantined: Vec<String>) -> Result<(), &'static str> {
+        *self.todos.borrow_mut() = todos;
+        self.unreadable_lines.borrow_mut().clear();
+        *self.quarantined.borrow_mut() = quarantined;
+        Ok(())
+    }
+
+    fn read_journal(&self) -> Result<Journal, &'static str> {
+        Ok(self.journal.borrow().clone())
+    }
+
+    fn store_journal(&self, journal: &Journal) -> Result<(), &'static str> {
+        *self.journal.borrow_mut() = journal.clone();
+        Ok(())
+    }
+}
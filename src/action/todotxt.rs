@@ -0,0 +1,320 @@
+use crate::{
+    date::{self, Date},
+    todo::ToDo,
+};
+
+use super::import::{Imported, Unmapped};
+
+/// A date written as `YYYY-MM-DD`, which is the only way todo.txt writes
+/// them.
+fn parse_date(token: &str) -> Option<Date> {
+    match token.len() {
+        10 => Date::parse(token).ok(),
+        _ => None,
+    }
+}
+
+/// The priority written as `(A)`.
+fn parse_priority(token: &str) -> Option<char> {
+    token
+        .strip_prefix('(')
+        .and_then(|token| token.strip_suffix(')'))
+        .filter(|priority| priority.len() == 1)
+        .and_then(|priority| priority.chars().next())
+        .filter(char::is_ascii_uppercase)
+}
+
+/// A `key:value` field. Values starting with `/`, like those of URLs, are
+/// left as text.
+fn parse_field(token: &str) -> Option<(&str, &str)> {
+    token
+        .split_once(':')
+        .filter(|(key, value)| !key.is_empty() && !value.is_empty() && !value.starts_with('/'))
+}
+
+/// Puts a `\` before a word of a title that `parse` would read as something
+/// other than text, which it takes off again: a `+project`, an `@context`, a
+/// `key:value` field, and as the first word, the `x` of completed items, a
+/// priority or a date.
+fn escape(word: &str, first: bool) -> String {
+    let special = word.starts_with('\\')
+        || (word.len() > 1 && word.starts_with(['+', '@']))
+        || parse_field(word).is_some()
+        || (first && (word == "x" || parse_priority(word).is_some() || parse_date(word).is_some()));
+
+    match special {
+        true => format!("\\{word}"),
+        false => word.to_string(),
+    }
+}
+
+fn timestamp(date: Date) -> u64 {
+    date.days().max(0) as u64 * 86_400
+}
+
+/// Reads a todo.txt file: `x` marks completed items, followed by the
+/// completion and creation dates, `(A)` the priority, `+word` the project,
+/// `@word` a context, kept as a tag, and `due:` and `pri:` the due date and
+/// priority. What is left makes the title, with the `\\` that `serialise`
+/// puts before words that would be read otherwise taken off.
+pub fn parse(todotxt: &str) -> Imported {
+    let mut todos: Vec<(usize, ToDo)> = vec![];
+    let mut unmapped: Vec<Unmapped> = vec![];
+
+    for (index, line) in todotxt.lines().enumerate() {
+        let number = index + 1;
+        let mut tokens = line.split_whitespace().peekable();
+
+        if tokens.peek().is_none() {
+            continue;
+        }
+
+        let mut todo = ToDo::new(String::new(), String::new());
+        let mut completed: Option<Date> = None;
+
+        let done = tokens.peek() == Some(&"x");
+        if done {
+            tokens.next();
+            todo.done = "✅".into();
+        }
+        // Some apps keep the priority of completed items after the `x`
+        if let Some(priority) = tokens.peek().and_then(|token| parse_priority(token)) {
+            tokens.next();
+            todo.priority = Some(priority);
+        }
+        if done {
+            completed = tokens.peek().and_then(|token| parse_date(token));
+            if completed.is_some() {
+                tokens.next();
+            }
+        }
+
+        if let Some(created) = tokens.peek().and_then(|token| parse_date(token)) {
+            tokens.next();
+            todo.created = Some(timestamp(created));
+        }
+        todo.updated = completed.map(timestamp).or(todo.created);
+
+        let mut words: Vec<&str> = vec![];
+
+        for token in tokens {
+            if let Some(word) = token.strip_prefix('\\') {
+                words.push(word);
+            } else if let Some(project) = token.strip_prefix('+').filter(|name| !name.is_empty()) {
+                match todo.project {
+                    None => todo.project = Some(project.into()),
+                    Some(_) => {
+                        words.push(token);
+                        unmapped.push(Unmapped {
+                            line: number,
+                            text: token.into(),
+                            reason: "only one project is kept",
+                        });
+                    }
+                }
+            } else if let Some(context) = token.strip_prefix('@').filter(|name| !name.is_empty()) {
+                todo.tags.push(context.into());
+            } else if let Some((key, value)) = parse_field(token) {
+                let mapped = match key {
+                    "due" => parse_date(value).map(|due| todo.due = Some(due)),
+                    "pri" => value
+                        .chars()
+                        .next()
+                        .filter(|priority| value.len() == 1 && priority.is_ascii_alphabetic())
+                        .map(|priority| todo.priority = Some(priority.to_ascii_uppercase())),
+                    _ => None,
+                };
+
+                if mapped.is_none() {
+                    words.push(token);
+                    unmapped.push(Unmapped {
+                        line: number,
+                        text: token.into(),
+                        reason: match key {
+                            "due" | "pri" => "the value is not valid",
+                            _ => "there is no such field",
+                        },
+                    });
+                }
+            } else {
+                words.push(token);
+            }
+        }
+
+        if words.is_empty() {
+            unmapped.push(Unmapped {
+                line: number,
+                text: line.trim().into(),
+                reason: "there is no text for the title",
+            });
+            continue;
+        }
+
+        todo.title = words.join(" ");
+        todos.push((number, todo));
+    }
+
+    (todos, unmapped)
+}
+
+/// Writes an item as a todo.txt line, which `parse` reads back. The
+/// description has no place in todo.txt and is left out, and words of the
+/// title that would be read as something else get a `\\` before them.
+pub fn serialise(todo: &ToDo) -> String {
+    let mut tokens: Vec<String> = vec![];
+    let date = |timestamp: u64| Date::from_timestamp(date::local(timestamp)).to_string();
+
+    match todo.is_done() {
+        true => {
+            tokens.push("x".into());
+            if let Some(updated) = todo.updated {
+                tokens.push(date(updated));
+            }
+        }
+        false => {
+            if let Some(priority) = todo.priority {
+                tokens.push(format!("({priority})"));
+            }
+        }
+    }
+    // A creation date on its own would be taken for a completion date
+    if let Some(created) = todo
+        .created
+        .filter(|_| !todo.is_done() || todo.updated.is_some())
+    {
+        tokens.push(date(created));
+    }
+
+    tokens.extend(
+        todo.title
+            .split_whitespace()
+            .enumerate()
+            .map(|(index, word)| escape(word, index == 0)),
+    );
+
+    if let Some(project) = &todo.project {
+        tokens.push(format!("+{project}"));
+    }
+    tokens.extend(todo.tags.iter().map(|tag| format!("@{tag}")));
+    if let Some(due) = todo.due {
+        tokens.push(format!("due:{due}"));
+    }
+    if let Some(priority) = todo.priority.filter(|_| todo.is_done()) {
+        tokens.push(format!("pri:{priority}"));
+    }
+
+    format!("{}\n", tokens.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_todotxt_lines_to_items() {
+        let (todos, unmapped) = parse(
+            "(A) 2026-09-01 Call Mom +family @phone due:2026-10-01\n\
+             \n\
+             x 2026-09-20 2026-09-02 Pay rent +home +bills pri:b\n\
+             Read book url:https://example.com @home\n\
+             x +home\n",
+        );
+
+        assert_eq!(todos.len(), 3);
+
+        let (line, call) = &todos[0];
+        assert_eq!(*line, 1);
+        assert_eq!(call.title, "Call Mom");
+        assert_eq!(call.priority, Some('A'));
+        assert_eq!(call.project, Some("family".into()));
+        assert_eq!(call.tags, vec!["phone".to_string()]);
+        assert_eq!(call.due, Some(Date::new(2026, 10, 1).unwrap()));
+        assert_eq!(call.created, Some(1_788_220_800));
+        assert!(!call.is_done());
+
+        let (_, rent) = &todos[1];
+        assert!(rent.is_done());
+        assert_eq!(rent.title, "Pay rent +bills");
+        assert_eq!(rent.priority, Some('B'));
+        assert_eq!(rent.updated, Some(1_789_862_400));
+
+        assert_eq!(todos[2].1.title, "Read book url:https://example.com");
+
+        let reasons: Vec<(usize, &str)> = unmapped
+            .iter()
+            .map(|unmapped| (unmapped.line, unmapped.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, "only one project is kept"),
+                (4, "there is no such field"),
+                (5, "there is no text for the title")
+            ]
+        );
+    }
+
+    #[test]
+    fn should_read_back_what_it_writes() {
+        let mut open = ToDo::new("Call Mom".into(), String::new());
+        open.priority = Some('A');
+        open.project = Some("family".into());
+        open.tags = vec!["phone".into(), "evening".into()];
+        open.due = Some(Date::new(2026, 10, 1).unwrap());
+        open.created = Some(1_788_220_800);
+        open.updated = open.created;
+
+        let mut done = ToDo::new("Pay rent".into(), String::new());
+        done.done = "✅".into();
+        done.priority = Some('B');
+        done.created = Some(1_788_307_200);
+        done.updated = Some(1_789_862_400);
+
+        let todotxt: String = [&open, &done].into_iter().map(serialise).collect();
+        assert_eq!(
+            todotxt,
+            "(A) 2026-09-01 Call Mom +family @phone @evening due:2026-10-01\n\
+             x 2026-09-20 2026-09-02 Pay rent pri:B\n"
+        );
+
+        let (todos, unmapped) = parse(&todotxt);
+        assert_eq!(todos, vec![(1, open), (2, done)]);
+        assert_eq!(unmapped, vec![]);
+    }
+
+    #[test]
+    fn should_escape_titles_that_would_be_read_otherwise() {
+        let titles = [
+            "x marks the spot",
+            "(A) grade for the essay",
+            "2026-10-01 party",
+            "Email +alice about @home at time:10am",
+            "Read C:\\notes and url:https://example.com",
+        ];
+
+        for title in titles {
+            let mut todo = ToDo::new(title.into(), String::new());
+            todo.project = Some("home".into());
+
+            let (todos, unmapped) = parse(&serialise(&todo));
+            assert_eq!(todos, vec![(1, todo)]);
+            assert_eq!(unmapped, vec![]);
+        }
+
+        let todo = ToDo::new("Email +alice".into(), String::new());
+        assert_eq!(serialise(&todo), "Email \\+alice\n");
+    }
+
+    #[test]
+    fn should_read_the_priority_of_completed_items() {
+        let (todos, unmapped) = parse("x (A) 2026-09-20 2026-09-02 Pay rent\n");
+
+        let (_, rent) = &todos[0];
+        assert!(rent.is_done());
+        assert_eq!(rent.title, "Pay rent");
+        assert_eq!(rent.priority, Some('A'));
+        assert_eq!(rent.created, Some(1_788_307_200));
+        assert_eq!(rent.updated, Some(1_789_862_400));
+        assert_eq!(unmapped, vec![]);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    date::{self, Date},
+    output::{self, Json},
+    todo::ToDo,
+};
+
+use super::{journal::ItemChange, Action, Confirm};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// An item taken out of the list with `delete`, kept with when it was
+/// deleted until the trash is emptied.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Trashed {
+    pub deleted: u64,
+    pub todo: ToDo,
+}
+
+impl Trashed {
+    /// Reads a line written by `serialise`: the deletion timestamp followed
+    /// by the item as it is stored in the list.
+    pub fn deserialise(trashed_str: &str) -> Result<Self, &'static str> {
+        let (deleted, todo_str) = match trashed_str.split_once(' ') {
+            Some(parts) => parts,
+            None => return Err("Trash is corrupted, could not read data"),
+        };
+
+        Ok(Trashed {
+            deleted: deleted
+                .parse::<u64>()
+                .map_err(|_| "Trash is corrupted, could not read data")?,
+            todo: ToDo::deserialise(todo_str)?,
+        })
+    }
+
+    pub fn serialise(&self) -> String {
+        format!("{} {}", self.deleted, self.todo.serialise())
+    }
+}
+
+/// Reads an age like `30d` or `2w` as a number of days, a bare number being
+/// days too.
+fn parse_days(age: &str) -> Result<u64, &'static str> {
+    let (number, days_per_unit) = match age.strip_suffix('w') {
+        Some(weeks) => (weeks, 7),
+        None => (age.strip_suffix('d').unwrap_or(age), 1),
+    };
+
+    match number.parse::<u64>() {
+        Ok(number) => Ok(number * days_per_unit),
+        Err(_) => Err("Age should be a number of days like 30d or weeks like 2w"),
+    }
+}
+
+impl Action<'_> {
+    /// Keeps the items at `item_indexes` in the trash, marked as deleted now.
+    pub(super) fn trash_items(
+        &mut self,
+        todos: &[ToDo],
+        item_indexes: &[usize],
+    ) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let deleted = date::now();
+
+        let mut trash: Vec<Trashed> = database.read_trash()?;
+        trash.extend(item_indexes.iter().map(|index| Trashed {
+            deleted,
+            todo: todos[*index].clone(),
+        }));
+
+        database.store_trash(trash)
+    }
+
+    /// Keeps the trash in step with a deletion or restoration being undone or
+    /// redone: items coming back to the list are taken out of the trash, and
+    /// those leaving it are put back in.
+    pub(super) fn replay_trash(&mut self, changes: &[ItemChange]) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let mut trash: Vec<Trashed> = database.read_trash()?;
+
+        for change in changes {
+            match change {
+                (Some((_, todo)), None) => trash.push(Trashed {
+                    deleted: date::now(),
+                    todo: (*todo).clone(),
+                }),
+                (None, Some((_, todo))) => {
+                    let serialised = todo.serialise();
+                    if let Some(position) = trash
+                        .iter()
+                        .rposition(|trashed| trashed.todo.serialise() == serialised)
+                    {
+                        trash.remove(position);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        database.store_trash(trash)
+    }
+
+    /// `trash list` shows what was deleted and `trash empty` removes it for
+    /// good, only what was deleted a while ago with `--older-than 30d`.
+    pub(super) fn trash(&mut self) -> Result<(), &'static str> {
+        let arguments = self.arguments.clone();
+        let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+        match arguments.as_slice() {
+            ["list"] => self.trash_list(),
+            ["empty"] => self.trash_empty(None),
+            ["empty", "--older-than", age] | ["empty", age, "--older-than"] => {
+                self.trash_empty(Some(parse_days(age)?))
+            }
+            ["empty", flag] if flag.starts_with("--older-than=") => {
+                self.trash_empty(Some(parse_days(&flag["--older-than=".len()..])?))
+            }
+            _ => Err("Trash should be used as trash list or trash empty [--older-than 30d]"),
+        }
+    }
+
+    /// Puts items from the trash back at the end of the list.
+    pub(super) fn restore(&mut self) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let trash: Vec<Trashed> = database.read_trash()?;
+        let original: Vec<ToDo> = database.read_items()?;
+        let mut todos: Vec<ToDo> = original.clone();
+
+        let trashed_todos: Vec<ToDo> = trash.iter().map(|trashed| trashed.todo.clone()).collect();
+        let item_indexes: Vec<usize> =
+            self.get_item_indexes_arg(&trashed_todos, "Given trash item index is wrong")?;
+
+        let mut changes: Vec<(Option<Json>, Option<Json>)> = vec![];
+
+        for item_index in item_indexes.iter() {
+            self.log_text(&format!(
+                "Restoring #{} ToDo item from the trash",
+                item_index + 1
+            ));
+
+            let mut todo = trashed_todos[*item_index].clone();
+            todo.touch(date::now());
+            todos.push(todo);
+
+            changes.push((
+                Some(trashed_to_json(item_index + 1, &trash[*item_index])),
+                Some(output::todo_to_json(todos.len(), todos.last().unwrap())),
+            ));
+        }
+
+        let trash: Vec<Trashed> = trash
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| item_indexes.binary_search(index).is_err())
+            .map(|(_, trashed)| trashed)
+            .collect();
+
+        // Back in the list before leaving the trash, so nothing is lost if
+        // storing fails half way
+        database.store_existing_items(todos.clone())?;
+        database.store_trash(trash)?;
+        self.record_change(original, &todos)?;
+
+        self.log_changes("restore", changes)
+    }
+
+    fn trash_list(&mut self) -> Result<(), &'static str> {
+        let trash: Vec<Trashed> = self
+            .database
+            .expect("Database could not be found")
+            .read_trash()?;
+
+        if trash.is_empty() {
+            self.log_text("The trash is empty");
+        }
+
+        let mut items: Vec<Json> = vec![];
+
+        for (index, trashed) in trash.iter().enumerate() {
+            self.log_text(&format!(
+                "#{} {}, deleted on {}",
+                index + 1,
+                trashed.todo.title.replace('\n', ""),
+                Date::from_timestamp(date::local(trashed.deleted))
+            ));
+
+            items.push(trashed_to_json(index + 1, trashed));
+        }
+
+        self.log_json(items, Json::Array)
+    }
+
+    fn trash_empty(&mut self, older_than_days: Option<u64>) -> Result<(), &'static str> {
+        let database = self.database.expect("Database could not be found");
+        let trash: Vec<Trashed> = database.read_trash()?;
+        let now = date::now();
+
+        let (purged, kept): (Vec<_>, Vec<_>) =
+            trash.into_iter().enumerate().partition(|(_, trashed)| {
+                older_than_days.is_none_or(|days| {
+                    now.saturating_sub(trashed.deleted) / SECONDS_PER_DAY >= days
+                })
+            });
+
+        if purged.is_empty() {
+            self.log_text("Nothing to remove from the trash");
+            return self.log_changes("trash-empty", vec![]);
+        }
+
+        if Confirm::configured()?.asks(purged.len())
+            && !self.confirm(&format!(
+                "Remove {} ToDo item{} from the trash for good?",
+                purged.len(),
+                if purged.len() == 1 { "" } else { "s" }
+            ))
+        {
+            self.log_text("Nothing was removed");
+            return self.log_changes("trash-empty", vec![]);
+        }
+
+        database.store_trash(kept.into_iter().map(|(_, trashed)| trashed).collect())?;
+
+        self.log_text(&format!(
+            "Removed {} ToDo item{} from the trash",
+            purged.len(),
+            if purged.len() == 1 { "" } else { "s" }
+        ));
+
+        let changes: Vec<(Option<Json>, Option<Json>)> = purged
+            .iter()
+            .map(|(index, trashed)| (Some(trashed_to_json(index + 1, trashed)), None))
+            .collect();
+        self.log_changes("trash-empty", changes)
+    }
+}
+
+/// An item in the trash as JSON, like any other item with when it was
+/// deleted.
+fn trashed_to_json(number: usize, trashed: &Trashed) -> Json {
+    match output::todo_to_json(number, &trashed.todo) {
+        Json::Object(mut fields) => {
+            fields.push(("deleted".into(), Json::Number(trashed.deleted as i64)));
+            Json::Object(fields)
+        }
+        item => item,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{action::test_database::TestDatabase, log_wrapper::LogWrapper};
+
+    #[test]
+    fn should_move_deleted_items_to_the_trash_and_restore_them() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&["a", "b", "c"]);
+
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["1".into(), "3".into()];
+        assert_eq!(delete_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b"]);
+        assert_eq!(database.trashed_titles(), vec!["a", "c"]);
+
+        let mut restore_action = Action::new("restore", &mut logger, &database).unwrap();
+        restore_action.arguments = vec!["1".into()];
+        assert_eq!(restore_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["b", "a"]);
+        assert_eq!(database.trashed_titles(), vec!["c"]);
+
+        let mut delete_action = Action::new("delete", &mut logger, &database).unwrap();
+        delete_action.arguments = vec!["--purge".into(), "1".into()];
+        assert_eq!(delete_action.execute_action(), Ok(()));
+        assert_eq!(database.titles(), vec!["a"]);
+        assert_eq!(database.trashed_titles(), vec!["c"]);
+    }
+
+    #[test]
+    fn should_only_empty_old_items_from_the_trash() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        let database = TestDatabase::new(&[]);
+        let now = date::now();
+        *database.trash.borrow_mut() = vec![
+            Trashed {
+                deleted: now - 40 * SECONDS_PER_DAY,
+                todo: ToDo::new("old".into(), "description".into()),
+            },
+            Trashed {
+                deleted: now,
+                todo: ToDo::new("new".into(), "description".into()),
+            },
+        ];
+
+        let mut trash_action = Action::new("trash", &mut logger, &database).unwrap();
+        trash_action.arguments = vec!["empty".into(), "--older-than".into(), "30d".into()];
+        assert_eq!(trash_action.execute_action(), Ok(()));
+        assert_eq!(database.trashed_titles(), vec!["new"]);
+        assert_eq!(
+            logger.std_writer,
+            "Removed 1 ToDo item from the trash\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn should_round_trip_trashed_items() {
+        let trashed = Trashed {
+            deleted: 1_700_000_000,
+            todo: ToDo::new("title, with comma".into(), "description".into()),
+        };
+
+        assert_eq!(
+            Trashed::deserialise(trashed.serialise().trim_end()),
+            Ok(trashed)
+        );
+        assert_eq!(parse_days("2w"), Ok(14));
+        assert_eq!(parse_days("30"), Ok(30));
+        assert!(parse_days("soon").is_err());
+    }
+}
@@ -0,0 +1,664 @@
+use std::{
+    env,
+    fmt::Display,
+    fs::{self, DirBuilder},
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    action::{
+        database::{validate_list_name, Backend, DEFAULT_LIST},
+        listing::ListFormat,
+        ActionType, Confirm,
+    },
+    log_wrapper::{ColorChoice, Style},
+    output::OutputMode,
+    paths,
+    template::Template,
+    theme::Theme,
+};
+
+/// Name of the config file that applies to a directory and everything below
+/// it, found by walking up from the current directory.
+pub const PROJECT_CONFIG_NAME: &str = ".terminal-todo.toml";
+
+/// Every setting with the environment variable that overrides it and its
+/// default value. `alias.<name>` settings come on top of these.
+const SETTINGS: [(&str, Option<&str>, Option<&str>); 28] = [
+    ("database.path", Some("TODO_DB"), None),
+    ("database.list", Some("TODO_LIST"), None),
+    ("database.discover", None, Some("true")),
+    ("database.backend", Some("TODO_BACKEND"), Some("text")),
+    ("database.compact_after", None, Some("100")),
+    ("database.lenient", Some("TODO_LENIENT"), Some("false")),
+    ("encryption.key_file", Some("TODO_KEY_FILE"), None),
+    ("output", Some("TODO_OUTPUT"), Some("text")),
+    ("color", Some("TODO_COLOR"), Some("auto")),
+    ("confirm", Some("TODO_CONFIRM"), Some("never")),
+    ("undo.depth", Some("TODO_UNDO_DEPTH"), Some("20")),
+    ("archive.after_days", Some("TODO_ARCHIVE_AFTER_DAYS"), None),
+    ("backup.keep", Some("TODO_BACKUP_KEEP"), Some("10")),
+    ("backup.daily", Some("TODO_BACKUP_DAILY"), Some("7")),
+    ("date.format", Some("TODO_DATE_FORMAT"), Some("%Y-%m-%d")),
+    ("list.format", Some("TODO_FORMAT"), Some("long")),
+    ("list.template", Some("TODO_TEMPLATE"), None),
+    ("theme.name", Some("TODO_THEME"), Some("default")),
+    ("theme.done_symbol", None, None),
+    ("theme.open_symbol", None, None),
+    ("theme.header", None, None),
+    ("theme.done", None, None),
+    ("theme.overdue", None, None),
+    ("theme.tags", None, None),
+    ("theme.priority_a", None, None),
+    ("theme.priority_b", None, None),
+    ("theme.priority_c", None, None),
+    ("theme.priority_other", None, None),
+];
+
+static CURRENT: OnceLock<Config> = OnceLock::new();
+
+/// Where the value of a setting came from.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Source {
+    CommandLine,
+    Environment(&'static str),
+    File(PathBuf),
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::CommandLine => write!(f, "command line"),
+            Source::Environment(name) => write!(f, "environment variable {name}"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Settings taken, from highest to lowest precedence, from the command line,
+/// environment variables, the project config file, the user config file and
+/// the defaults.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Config {
+    layers: Vec<(Source, Vec<(String, String)>)>,
+}
+
+impl Config {
+    pub fn load(overrides: Vec<(String, String)>) -> Result<Self, String> {
+        let mut layers: Vec<(Source, Vec<(String, String)>)> = vec![];
+
+        for (key, value) in overrides.iter() {
+            if !is_known(key) {
+                return Err(format!("Unknown setting '{key}'"));
+            }
+            validate(key, value).map_err(|err| format!("Setting {key} is not valid, {err}"))?;
+        }
+        layers.push((Source::CommandLine, overrides));
+
+        for (key, env_var, _) in SETTINGS {
+            if let Some(env_var) = env_var {
+                if let Ok(value) = env::var(env_var) {
+                    validate(key, &value).map_err(|err| {
+                        format!("Environment variable {env_var} is not valid, {err}")
+                    })?;
+                    layers.push((Source::Environment(env_var), vec![(key.into(), value)]));
+                }
+            }
+        }
+
+        let project_config = project_config_path();
+        for path in [project_config.clone(), user_config_path()]
+            .into_iter()
+            .flatten()
+        {
+            if let Ok(contents) = fs::read_to_string(&path) {
+                let not_valid = |err| format!("Config file {} is not valid, {err}", path.display());
+                let mut entries = parse(&contents).map_err(not_valid)?;
+
+                if Some(&path) == project_config.as_ref() {
+                    let project_dir = path.parent().unwrap_or(Path::new(""));
+                    entries = in_project(entries, project_dir).map_err(not_valid)?;
+                }
+                layers.push((Source::File(path), entries));
+            }
+        }
+
+        layers.push((Source::Default, defaults()));
+
+        Ok(Config { layers })
+    }
+
+    /// Builds a config out of the given layers only, highest precedence first.
+    pub fn from_layers(layers: Vec<(Source, Vec<(String, String)>)>) -> Self {
+        Config { layers }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lookup(key).map(|(value, _)| value)
+    }
+
+    /// The value of a setting along with where it came from.
+    pub fn lookup(&self, key: &str) -> Option<(&str, &Source)> {
+        self.layers.iter().find_map(|(source, entries)| {
+            entries
+                .iter()
+                .rev()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| (value.as_str(), source))
+        })
+    }
+
+    /// Every setting that has a value, known settings first and then aliases
+    /// in alphabetical order.
+    pub fn entries(&self) -> Vec<(String, &str, &Source)> {
+        let mut aliases: Vec<&str> = self
+            .layers
+            .iter()
+            .flat_map(|(_, entries)| entries.iter())
+            .map(|(key, _)| key.as_str())
+            .filter(|key| key.starts_with("alias."))
+            .collect();
+        aliases.sort();
+        aliases.dedup();
+
+        SETTINGS
+            .iter()
+            .map(|(key, _, _)| *key)
+            .chain(aliases)
+            .filter_map(|key| {
+                self.lookup(key)
+                    .map(|(value, source)| (key.to_string(), value, source))
+            })
+            .collect()
+    }
+}
+
+/// Loads the config used for the rest of the program, with `overrides` given
+/// on the command line taking precedence over everything else.
+pub fn init(overrides: Vec<(String, String)>) -> Result<(), String> {
+    let config = Config::load(overrides)?;
+    let _ = CURRENT.set(config);
+    Ok(())
+}
+
+/// The config loaded by `init`. Tests do not call it and get the defaults
+/// only, so that the environment and config files of whoever runs them are
+/// not picked up.
+pub fn current() -> &'static Config {
+    if cfg!(test) {
+        return CURRENT.get_or_init(|| Config::from_layers(vec![(Source::Default, defaults())]));
+    }
+
+    CURRENT
+        .get()
+        .expect("The config should be loaded with config::init first")
+}
+
+fn defaults() -> Vec<(String, String)> {
+    SETTINGS
+        .iter()
+        .filter_map(|(key, _, default)| default.map(|value| (key.to_string(), value.into())))
+        .collect()
+}
+
+pub fn is_known(key: &str) -> bool {
+    SETTINGS.iter().any(|(setting, _, _)| *setting == key)
+        || key
+            .strip_prefix("alias.")
+            .is_some_and(|name| !name.is_empty() && !name.contains('.'))
+}
+
+/// Checks that `value` is one that the `key` setting can take.
+pub fn validate(key: &str, value: &str) -> Result<(), &'static str> {
+    if !is_known(key) {
+        return Err("Unknown setting");
+    }
+
+    let alias_name = key.strip_prefix("alias.");
+
+    match key {
+        "database.backend" => Backend::new(value).map(drop),
+        "database.compact_after" if value.parse::<usize>().is_err() => {
+            Err("database.compact_after should be a number")
+        }
+        "database.list" if value != DEFAULT_LIST => validate_list_name(value),
+        "database.discover" if value != "true" && value != "false" => {
+            Err("database.discover can only be true or false")
+        }
+        "database.lenient" if value != "true" && value != "false" => {
+            Err("database.lenient can only be true or false")
+        }
+        "output" => OutputMode::new(value).map(drop),
+        "color" => ColorChoice::new(value).map(drop),
+        "confirm" => Confirm::new(value).map(drop),
+        "undo.depth" if value.parse::<usize>().is_err() => Err("undo.depth should be a number"),
+        "backup.keep" | "backup.daily" if value.parse::<usize>().is_err() => {
+            Err("backup.keep and backup.daily should be numbers")
+        }
+        "archive.after_days" if value.parse::<u64>().is_err() => {
+            Err("archive.after_days should be a number of days")
+        }
+        "list.format" => ListFormat::new(value).map(drop),
+        "list.template" => Template::parse(value)
+            .map(drop)
+            .map_err(|_| "Template is not valid, try it with list --template first"),
+        "theme.name" => Theme::named(value).map(drop),
+        "theme.header"
+        | "theme.done"
+        | "theme.overdue"
+        | "theme.tags"
+        | "theme.priority_a"
+        | "theme.priority_b"
+        | "theme.priority_c"
+        | "theme.priority_other" => Style::parse(value).map(drop),
+        _ if alias_name.is_some_and(|name| ActionType::new(name).is_ok()) => {
+            Err("Aliases cannot have the name of an action")
+        }
+        _ if alias_name.is_some() && split_words(value).is_empty() => {
+            Err("Aliases cannot be empty")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The user config file, `config.toml` in the config directory, unless
+/// `TODO_CONFIG` points elsewhere.
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("TODO_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    paths::config_dir()
+        .ok()
+        .map(|config_dir| config_dir.join("config.toml"))
+}
+
+/// The nearest `.terminal-todo.toml` in the current directory or one of its
+/// ancestors.
+pub fn project_config_path() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+
+    current_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Checks a `database.path` set in a project config, which can only be a
+/// relative path inside the project, so that a config file that came with a
+/// project cannot send the list anywhere else.
+pub fn validate_project_path(value: &str) -> Result<(), &'static str> {
+    match Path::new(value)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        true => Ok(()),
+        false => {
+            Err("database.path in a project config should be a relative path inside the project")
+        }
+    }
+}
+
+/// The settings of the project config in `project_dir`, with its
+/// `database.path` taken from there rather than from the current directory.
+fn in_project(
+    entries: Vec<(String, String)>,
+    project_dir: &Path,
+) -> Result<Vec<(String, String)>, String> {
+    entries
+        .into_iter()
+        .map(|(key, value)| match key.as_str() {
+            "database.path" => {
+                validate_project_path(&value)?;
+                let path = project_dir.join(value).to_string_lossy().into_owned();
+                Ok((key, path))
+            }
+            _ => Ok((key, value)),
+        })
+        .collect()
+}
+
+/// Reads the `key = value` lines of a config file, where `[section]` headers
+/// prefix the keys that follow them, e.g. `path` under `[database]` is the
+/// `database.path` setting. Values can be quoted like TOML strings, and are
+/// checked like those given to `config set`.
+pub fn parse(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries: Vec<(String, String)> = vec![];
+    let mut section = String::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            section = match header.split_once(']') {
+                Some((name, rest)) if is_comment(rest) => name.trim().to_string(),
+                _ => return Err(format!("line {line_number} has a broken section header")),
+            };
+            continue;
+        }
+
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => {
+                return Err(format!(
+                    "line {line_number} should be written as key = value"
+                ))
+            }
+        };
+
+        let key = match section.is_empty() {
+            true => name.to_string(),
+            false => format!("{section}.{name}"),
+        };
+
+        if !is_known(&key) {
+            return Err(format!("line {line_number} has an unknown setting '{key}'"));
+        }
+
+        let value = parse_value(value)
+            .ok_or_else(|| format!("line {line_number} has a value that is not valid"))?;
+        validate(&key, &value)
+            .map_err(|err| format!("line {line_number} has a value that is not valid, {err}"))?;
+
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
+
+fn is_comment(rest: &str) -> bool {
+    let rest = rest.trim();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+
+    match chars.next()? {
+        '"' => {
+            let mut parsed = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => return is_comment(chars.as_str()).then_some(parsed),
+                    '\\' => match chars.next()? {
+                        'n' => parsed.push('\n'),
+                        't' => parsed.push('\t'),
+                        escaped => parsed.push(escaped),
+                    },
+                    c => parsed.push(c),
+                }
+            }
+            None
+        }
+        '\'' => {
+            let (literal, rest) = chars.as_str().split_once('\'')?;
+            is_comment(rest).then(|| literal.to_string())
+        }
+        _ => {
+            let bare = value.split('#').next().unwrap_or_default().trim();
+            (!bare.is_empty()).then(|| bare.to_string())
+        }
+    }
+}
+
+/// Sets `key` in the config file at `path`, keeping everything else in the
+/// file as it is.
+pub fn set_in_file(path: &Path, key: &str, value: &str) -> Result<(), &'static str> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+
+    if parse(&contents).is_err() {
+        return Err("Config file is not valid, fix it before changing it");
+    }
+
+    if let Some(parent) = path.parent() {
+        if DirBuilder::new().recursive(true).create(parent).is_err() {
+            return Err("Could not create the config directory");
+        }
+    }
+
+    match fs::write(path, set_in_contents(&contents, key, value)) {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Could not write the config file"),
+    }
+}
+
+fn set_in_contents(contents: &str, key: &str, value: &str) -> String {
+    let (section, name) = key.split_once('.').unwrap_or(("", key));
+    let new_line = format!(
+        "{name} = \"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    );
+
+    let mut lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
+    let mut current_section = String::new();
+    // Where a new line for the key would go: after the last setting of its
+    // section, top level settings being the ones before any section header
+    let mut insert_at: Option<usize> = section.is_empty().then_some(0);
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some(header) = trimmed.strip_prefix('[') {
+            current_section = header.split(']').next().unwrap_or_default().trim().into();
+            if current_section == section {
+                insert_at = Some(index + 1);
+            }
+            continue;
+        }
+
+        if current_section != section || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if trimmed.split('=').next().map(str::trim) == Some(name) {
+            lines[index] = new_line;
+            return lines.join("\n") + "\n";
+        }
+
+        insert_at = Some(index + 1);
+    }
+
+    match insert_at {
+        Some(index) => lines.insert(index, new_line),
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{section}]"));
+            lines.push(new_line);
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Splits an alias into words, keeping quoted parts together.
+pub fn split_words(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match (c, quote) {
+            (c, Some(open)) if c == open => quote = None,
+            (c, Some(_)) => word.get_or_insert_with(String::new).push(c),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (c, None) if c.is_whitespace() => words.extend(word.take()),
+            (c, None) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    words.extend(word);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_config_files() {
+        let contents = "# terminal-todo settings
+output = json
+confirm = 'bulk' # ask before deleting many items
+
+[database]
+path = \"/home/me/todos\"
+
+[alias]
+ls = \"list --format table \\\"status:open\\\"\"
+";
+        assert_eq!(
+            parse(contents),
+            Ok(entries(&[
+                ("output", "json"),
+                ("confirm", "bulk"),
+                ("database.path", "/home/me/todos"),
+                ("alias.ls", "list --format table \"status:open\""),
+            ]))
+        );
+
+        assert_eq!(
+            parse("[list]\nformat = \"table"),
+            Err("line 2 has a value that is not valid".into())
+        );
+        assert_eq!(
+            parse("colour = always"),
+            Err("line 1 has an unknown setting 'colour'".into())
+        );
+        assert_eq!(
+            parse("[list\n"),
+            Err("line 1 has a broken section header".into())
+        );
+        assert_eq!(
+            parse("[database]\nbackend = sqlite\n"),
+            Err(
+                "line 2 has a value that is not valid, Database backend can only be text or events"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn should_keep_project_databases_inside_the_project() {
+        let project_dir = Path::new("/work");
+
+        assert_eq!(
+            in_project(
+                entries(&[("database.path", "todo/list"), ("output", "json")]),
+                project_dir
+            ),
+            Ok(entries(&[
+                ("database.path", "/work/todo/list"),
+                ("output", "json")
+            ]))
+        );
+        for path in ["/home/me/todos", "../todos", "todo/../../todos"] {
+            assert_eq!(
+                in_project(entries(&[("database.path", path)]), project_dir),
+                Err("database.path in a project config should be a relative path inside the project".into())
+            );
+        }
+    }
+
+    #[test]
+    fn should_follow_the_precedence_of_layers() {
+        let project = PathBuf::from("/work/.terminal-todo.toml");
+        let config = Config::from_layers(vec![
+            (Source::CommandLine, entries(&[("output", "ndjson")])),
+            (
+                Source::Environment("TODO_FORMAT"),
+                entries(&[("list.format", "table")]),
+            ),
+            (
+                Source::File(project.clone()),
+                entries(&[("list.format", "long"), ("confirm", "always")]),
+            ),
+            (
+                Source::Default,
+                entries(&[("output", "text"), ("confirm", "never")]),
+            ),
+        ]);
+
+        assert_eq!(config.get("output"), Some("ndjson"));
+        assert_eq!(
+            config.lookup("list.format"),
+            Some(("table", &Source::Environment("TODO_FORMAT")))
+        );
+        assert_eq!(
+            config.lookup("confirm"),
+            Some(("always", &Source::File(project)))
+        );
+        assert_eq!(config.get("date.format"), None);
+        assert_eq!(config.entries().len(), 3);
+    }
+
+    #[test]
+    fn should_set_values_in_place() {
+        let contents =
+            "output = json\n\n[list]\n# how to list\nformat = long\n\n[alias]\nls = list\n";
+
+        assert_eq!(
+            set_in_contents(contents, "list.format", "table"),
+            "output = json\n\n[list]\n# how to list\nformat = \"table\"\n\n[alias]\nls = list\n"
+        );
+        assert_eq!(
+            set_in_contents(contents, "list.template", "{id} {title}"),
+            "output = json\n\n[list]\n# how to list\nformat = long\ntemplate = \"{id} {title}\"\n\n[alias]\nls = list\n"
+        );
+        assert_eq!(
+            set_in_contents(contents, "color", "never"),
+            "output = json\ncolor = \"never\"\n\n[list]\n# how to list\nformat = long\n\n[alias]\nls = list\n"
+        );
+        assert_eq!(
+            set_in_contents("", "database.path", "C:\\todos"),
+            "[database]\npath = \"C:\\\\todos\"\n"
+        );
+        assert_eq!(set_in_contents("", "output", "json"), "output = \"json\"\n");
+    }
+
+    #[test]
+    fn should_validate_values() {
+        assert_eq!(validate("list.format", "table"), Ok(()));
+        assert_eq!(
+            validate("list.format", "grid"),
+            Err("Format can only be table or long")
+        );
+        assert_eq!(validate("theme.overdue", "bold magenta"), Ok(()));
+        assert_eq!(validate("alias.ls", "list --format table"), Ok(()));
+        assert_eq!(
+            validate("alias.list", "list --format table"),
+            Err("Aliases cannot have the name of an action")
+        );
+        assert_eq!(validate("colour", "never"), Err("Unknown setting"));
+    }
+
+    #[test]
+    fn should_split_aliases_into_words() {
+        assert_eq!(
+            split_words("list --format table \"tag:bug or tag:ui\" ''"),
+            vec!["list", "--format", "table", "tag:bug or tag:ui", ""]
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date (proleptic Gregorian, no time zone) such as a due date.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Self, &'static str> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err("Date is not valid");
+        }
+
+        Ok(Date { year, month, day })
+    }
+
+    /// Parses dates written as `YYYY-MM-DD`, plus the `today`, `tomorrow` and
+    /// `yesterday` shorthands.
+    pub fn parse(date_str: &str) -> Result<Self, &'static str> {
+        match date_str {
+            "today" => return Ok(Date::today()),
+            "tomorrow" => return Ok(Date::today().add_days(1)),
+            "yesterday" => return Ok(Date::today().add_days(-1)),
+            _ => {}
+        }
+
+        let parts: Vec<&str> = date_str.split('-').collect();
+
+        if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+            return Err("Date should be written as YYYY-MM-DD");
+        }
+
+        let year = parts[0].parse::<i32>();
+        let month = parts[1].parse::<u32>();
+        let day = parts[2].parse::<u32>();
+
+        match (year, month, day) {
+            (Ok(year), Ok(month), Ok(day)) => Date::new(year, month, day),
+            _ => Err("Date should be written as YYYY-MM-DD"),
+        }
+    }
+
+    /// The date on the user's calendar, which `due:today` and the overdue
+    /// and this week groups go by.
+    pub fn today() -> Self {
+        Date::from_timestamp(local(now()))
+    }
+
+    /// The UTC date of a unix timestamp in seconds.
+    pub fn from_timestamp(seconds: u64) -> Self {
+        Date::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Builds a date from the number of days since 1970-01-01.
+    pub fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+        Date { year, month, day }
+    }
+
+    /// The number of days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+
+    pub fn add_days(&self, days: i64) -> Self {
+        Date::from_days(self.days() + days)
+    }
+
+    /// ISO weekday, from 1 for Monday to 7 for Sunday.
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday
+        ((self.days() + 3).rem_euclid(7) + 1) as u32
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+impl Date {
+    /// Writes the date following a strftime-like `pattern`, where `%Y`, `%m`
+    /// and `%d` are the numeric parts, `%e` the unpadded day, `%b`/`%B` the
+    /// short or full month name and `%a`/`%A` the short or full weekday, and `%%` a percent sign.
+    pub fn format(&self, pattern: &str) -> String {
+        let mut formatted = String::new();
+        let mut chars = pattern.chars();
+
+        let month = MONTHS[self.month as usize - 1];
+        let weekday = WEEKDAYS[self.weekday() as usize - 1];
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('Y') => formatted += &format!("{:04}", self.year),
+                Some('m') => formatted += &format!("{:02}", self.month),
+                Some('d') => formatted += &format!("{:02}", self.day),
+                Some('e') => formatted += &self.day.to_string(),
+                Some('b') => formatted += &month[..3],
+                Some('B') => formatted += month,
+                Some('a') => formatted += &weekday[..3],
+                Some('A') => formatted += weekday,
+                Some('%') => formatted.push('%'),
+                Some(other) => {
+                    formatted.push('%');
+                    formatted.push(other);
+                }
+                None => formatted.push('%'),
+            }
+        }
+
+        formatted
+    }
+}
+
+/// The current unix timestamp in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// The timestamp moved by the offset of the local time zone from UTC, so that
+/// the date and time of day read from it are those of the user's clock.
+/// Stored timestamps stay in UTC, this is only for what is shown.
+pub fn local(seconds: u64) -> u64 {
+    seconds.saturating_add_signed(utc_offset(seconds))
+}
+
+/// The offset of local time from UTC at a timestamp, in seconds. Tests run
+/// in UTC whatever the time zone of whoever runs them, like they run with
+/// the default settings.
+#[cfg(unix)]
+fn utc_offset(seconds: u64) -> i64 {
+    if cfg!(test) {
+        return 0;
+    }
+
+    let time = seconds as libc::time_t;
+    // SAFETY: tm is plain data, and localtime_r only writes to it
+    let mut local: libc::tm = unsafe { std::mem::zeroed() };
+    match unsafe { libc::localtime_r(&time, &mut local) }.is_null() {
+        true => 0,
+        false => local.tm_gmtoff as i64,
+    }
+}
+
+#[cfg(not(unix))]
+fn utc_offset(_seconds: u64) -> i64 {
+    0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_dates_with_a_pattern() {
+        let date = Date::new(2026, 11, 1).unwrap();
+        assert_eq!(date.format("%Y-%m-%d"), "2026-11-01");
+        assert_eq!(date.format("%a %e %b %Y"), "Sun 1 Nov 2026");
+        assert_eq!(date.format("%d/%m/%Y 100%%"), "01/11/2026 100%");
+    }
+
+    #[test]
+    fn should_parse_and_print_dates() {
+        let date = Date::parse("2026-11-01").unwrap();
+        assert_eq!(date, Date::new(2026, 11, 1).unwrap());
+        assert_eq!(date.to_string(), "2026-11-01");
+    }
+
+    #[test]
+    fn should_reject_invalid_dates() {
+        assert_eq!(Date::parse("2026-13-01"), Err("Date is not valid"));
+        assert_eq!(Date::parse("2025-02-29"), Err("Date is not valid"));
+        assert_eq!(
+            Date::parse("1/11/2026"),
+            Err("Date should be written as YYYY-MM-DD")
+        );
+    }
+
+    #[test]
+    fn should_convert_between_days_and_dates() {
+        assert_eq!(Date::from_days(0), Date::new(1970, 1, 1).unwrap());
+        assert_eq!(
+            Date::new(2024, 2, 29).unwrap().add_days(1).to_string(),
+            "2024-03-01"
+        );
+        assert_eq!(
+            Date::new(2026, 1, 1).unwrap().add_days(-1).to_string(),
+            "2025-12-31"
+        );
+        assert_eq!(
+            Date::from_timestamp(1_700_000_000).to_string(),
+            "2023-11-14"
+        );
+        assert_eq!(Date::new(2026, 10, 19).unwrap().weekday(), 1);
+        assert_eq!(Date::new(2026, 10, 25).unwrap().weekday(), 7);
+    }
+}
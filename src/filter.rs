@@ -0,0 +1,591 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::{date::Date, todo::ToDo};
+
+const FIELDS: &str = "status, title, description, tag, project, priority and due";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Field {
+    Status,
+    Title,
+    Description,
+    Tag,
+    Project,
+    Priority,
+    Due,
+}
+
+impl Field {
+    fn new(field_name: &str) -> Option<Self> {
+        match field_name.to_lowercase().as_str() {
+            "status" => Some(Field::Status),
+            "title" => Some(Field::Title),
+            "description" | "desc" => Some(Field::Description),
+            "tag" | "tags" => Some(Field::Tag),
+            "project" => Some(Field::Project),
+            "priority" => Some(Field::Priority),
+            "due" => Some(Field::Due),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Matches,
+}
+
+#[derive(Debug)]
+pub enum Value {
+    Text(String),
+    Pattern(Regex),
+    Date(Date),
+    Priority(char),
+    Done(bool),
+    Missing,
+}
+
+/// A parsed `list` filter expression such as
+/// `status:open tag:bug due<2026-11-01 "login"`.
+///
+/// Terms next to each other are combined with AND; `or`, `not` (or a leading
+/// `-`) and parentheses can be used for anything else. Bare words and quoted
+/// strings match the title or description case-insensitively and `/pattern/`
+/// matches them with a regular expression.
+#[derive(Debug)]
+pub enum Filter {
+    All,
+    Text(String),
+    Pattern(Regex),
+    Condition(Field, Comparison, Value),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let tokens = tokenise(expression)?;
+
+        if tokens.is_empty() {
+            return Ok(Filter::All);
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let filter = parser.parse_or()?;
+
+        match parser.tokens.get(parser.position) {
+            Some(token) => Err(format!("Unexpected ')' at position {}", token.position)),
+            None => Ok(filter),
+        }
+    }
+
+    pub fn matches(&self, todo: &ToDo) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Text(text) => contains(&todo.title, text) || contains(&todo.description, text),
+            Filter::Pattern(pattern) => {
+                pattern.is_match(&todo.title) || pattern.is_match(&todo.description)
+            }
+            Filter::Condition(field, comparison, value) => {
+                matches_condition(todo, *field, *comparison, value)
+            }
+            Filter::Not(filter) => !filter.matches(todo),
+            Filter::And(left, right) => left.matches(todo) && right.matches(todo),
+            Filter::Or(left, right) => left.matches(todo) || right.matches(todo),
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+fn matches_condition(todo: &ToDo, field: Field, comparison: Comparison, value: &Value) -> bool {
+    match (field, value) {
+        (Field::Status, Value::Done(done)) => (todo.is_done() == *done) == is_positive(comparison),
+        (Field::Title, _) => matches_text(Some(&todo.title), comparison, value),
+        (Field::Description, _) => matches_text(Some(&todo.description), comparison, value),
+        (Field::Project, _) => matches_text(todo.project.as_deref(), comparison, value),
+        (Field::Tag, Value::Missing) => todo.tags.is_empty() == is_positive(comparison),
+        (Field::Tag, _) => {
+            let any_tag = todo.tags.iter().any(|tag| match value {
+                Value::Pattern(pattern) => pattern.is_match(tag),
+                Value::Text(text) => tag.eq_ignore_ascii_case(text),
+                _ => false,
+            });
+            any_tag == is_positive(comparison)
+        }
+        (Field::Priority, Value::Priority(priority)) => {
+            matches_order(todo.priority.as_ref(), comparison, priority)
+        }
+        (Field::Priority, Value::Missing) => todo.priority.is_none() == is_positive(comparison),
+        (Field::Due, Value::Date(date)) => matches_order(todo.due.as_ref(), comparison, date),
+        (Field::Due, Value::Missing) => todo.due.is_none() == is_positive(comparison),
+        _ => false,
+    }
+}
+
+fn is_positive(comparison: Comparison) -> bool {
+    comparison != Comparison::NotEqual
+}
+
+fn matches_text(field: Option<&str>, comparison: Comparison, value: &Value) -> bool {
+    let found = match (field, value) {
+        (None, Value::Missing) => true,
+        (Some(_), Value::Missing) | (None, _) => false,
+        (Some(field), Value::Pattern(pattern)) => pattern.is_match(field),
+        (Some(field), Value::Text(text)) => contains(field, text),
+        _ => false,
+    };
+
+    found == is_positive(comparison)
+}
+
+fn matches_order<T: Ord>(field: Option<&T>, comparison: Comparison, value: &T) -> bool {
+    let field = match field {
+        Some(field) => field,
+        None => return comparison == Comparison::NotEqual,
+    };
+
+    match comparison {
+        Comparison::Equal | Comparison::Matches => field == value,
+        Comparison::NotEqual => field != value,
+        Comparison::Less => field < value,
+        Comparison::LessOrEqual => field <= value,
+        Comparison::Greater => field > value,
+        Comparison::GreaterOrEqual => field >= value,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    OpenParen,
+    CloseParen,
+    And,
+    Or,
+    Not,
+    Term {
+        text: String,
+        // Index in `text` where the first quoted part starts
+        quoted_from: Option<usize>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    // 1-based character position in the expression, for error messages
+    position: usize,
+}
+
+fn tokenise(expression: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let position = index + 1;
+
+        match chars[index] {
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '(' => {
+                tokens.push(Token {
+                    kind: TokenKind::OpenParen,
+                    position,
+                });
+                index += 1;
+                continue;
+            }
+            ')' => {
+                tokens.push(Token {
+                    kind: TokenKind::CloseParen,
+                    position,
+                });
+                index += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut text = String::new();
+        let mut quoted_from: Option<usize> = None;
+        let mut in_quotes = false;
+        let mut in_pattern = false;
+
+        while index < chars.len() {
+            let c = chars[index];
+
+            match c {
+                '"' if !in_pattern => {
+                    in_quotes = !in_quotes;
+                    quoted_from.get_or_insert(text.len());
+                }
+                '\\' if in_quotes || in_pattern => {
+                    if let Some(escaped) = chars.get(index + 1) {
+                        if in_pattern && *escaped != '/' {
+                            text.push(c);
+                        }
+                        text.push(*escaped);
+                        index += 1;
+                    }
+                }
+                '/' if !in_quotes && (in_pattern || text.is_empty() || text.ends_with('~')) => {
+                    in_pattern = !in_pattern;
+                    text.push(c);
+                }
+                c if !in_quotes && !in_pattern && (c.is_whitespace() || c == '(' || c == ')') => {
+                    break
+                }
+                _ => text.push(c),
+            }
+
+            index += 1;
+        }
+
+        if in_quotes {
+            return Err(format!(
+                "Missing closing quote for the term at position {position}"
+            ));
+        }
+        if in_pattern {
+            return Err(format!(
+                "Missing closing '/' for the pattern at position {position}"
+            ));
+        }
+
+        let kind = match (text.as_str(), quoted_from) {
+            ("and" | "AND" | "&&", None) => TokenKind::And,
+            ("or" | "OR" | "||", None) => TokenKind::Or,
+            ("not" | "NOT" | "!", None) => TokenKind::Not,
+            _ if quoted_from != Some(0) && text.len() > 1 && text.starts_with('-') => {
+                tokens.push(Token {
+                    kind: TokenKind::Not,
+                    position,
+                });
+                TokenKind::Term {
+                    text: text[1..].into(),
+                    quoted_from: quoted_from.map(|quoted_from| quoted_from - 1),
+                }
+            }
+            _ => TokenKind::Term { text, quoted_from },
+        };
+
+        tokens.push(Token { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.parse_and()?;
+
+        while let Some(TokenKind::Or) = self.peek() {
+            self.position += 1;
+            let right = self.parse_and()?;
+            filter = Filter::Or(Box::new(filter), Box::new(right));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.parse_not()?;
+
+        loop {
+            match self.peek() {
+                Some(TokenKind::And) => self.position += 1,
+                Some(TokenKind::Term { .. } | TokenKind::Not | TokenKind::OpenParen) => {}
+                _ => break,
+            }
+
+            let right = self.parse_not()?;
+            filter = Filter::And(Box::new(filter), Box::new(right));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_not(&mut self) -> Result<Filter, String> {
+        if let Some(TokenKind::Not) = self.peek() {
+            self.position += 1;
+            return Ok(Filter::Not(Box::new(self.parse_not()?)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Filter, String> {
+        let token = match self.tokens.get(self.position) {
+            Some(token) => token,
+            None => return Err("Expected a condition at the end of the filter".into()),
+        };
+        let position = token.position;
+        self.position += 1;
+
+        match &token.kind {
+            TokenKind::OpenParen => {
+                let filter = self.parse_or()?;
+
+                match self.peek() {
+                    Some(TokenKind::CloseParen) => {
+                        self.position += 1;
+                        Ok(filter)
+                    }
+                    _ => Err(format!(
+                        "Missing closing parenthesis for '(' at position {position}"
+                    )),
+                }
+            }
+            TokenKind::CloseParen => Err(format!("Unexpected ')' at position {position}")),
+            TokenKind::And | TokenKind::Or => Err(format!(
+                "Expected a condition before the operator at position {position}"
+            )),
+            TokenKind::Not => Err(format!(
+                "Expected a condition after 'not' at position {position}"
+            )),
+            TokenKind::Term { text, quoted_from } => parse_term(text, *quoted_from, position),
+        }
+    }
+}
+
+fn parse_term(text: &str, quoted_from: Option<usize>, position: usize) -> Result<Filter, String> {
+    if quoted_from == Some(0) {
+        return Ok(Filter::Text(text.into()));
+    }
+
+    if let Some(pattern) = text.strip_prefix('/') {
+        let pattern = pattern.strip_suffix('/').unwrap_or(pattern);
+        return Ok(Filter::Pattern(build_pattern(pattern, position)?));
+    }
+
+    let field_end = text
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .filter(|field_end| *field_end > 0 && quoted_from.is_none_or(|from| *field_end < from));
+
+    let (field_name, rest) = match field_end {
+        Some(field_end) => text.split_at(field_end),
+        None => return Ok(Filter::Text(text.into())),
+    };
+
+    let (comparison, value) = match rest {
+        _ if rest.starts_with("!=") => (Comparison::NotEqual, &rest[2..]),
+        _ if rest.starts_with("<=") => (Comparison::LessOrEqual, &rest[2..]),
+        _ if rest.starts_with(">=") => (Comparison::GreaterOrEqual, &rest[2..]),
+        _ if rest.starts_with(':') || rest.starts_with('=') => (Comparison::Equal, &rest[1..]),
+        _ if rest.starts_with('<') => (Comparison::Less, &rest[1..]),
+        _ if rest.starts_with('>') => (Comparison::Greater, &rest[1..]),
+        _ if rest.starts_with('~') => (Comparison::Matches, &rest[1..]),
+        _ => return Ok(Filter::Text(text.into())),
+    };
+
+    let field = match Field::new(field_name) {
+        Some(field) => field,
+        None => {
+            return Err(format!(
+                "Unknown field '{field_name}' at position {position}, fields are {FIELDS}"
+            ))
+        }
+    };
+
+    if value.is_empty() {
+        return Err(format!(
+            "Missing value for '{field_name}' at position {position}"
+        ));
+    }
+
+    let ordered = matches!(
+        comparison,
+        Comparison::Less
+            | Comparison::LessOrEqual
+            | Comparison::Greater
+            | Comparison::GreaterOrEqual
+    );
+    let value_is_quoted = quoted_from.is_some();
+
+    let value = match field {
+        _ if comparison == Comparison::Matches => {
+            if matches!(field, Field::Status | Field::Priority | Field::Due) {
+                return Err(format!(
+                    "'{field_name}' cannot be matched with '~' at position {position}"
+                ));
+            }
+            let pattern = value.trim_start_matches('/').trim_end_matches('/');
+            Value::Pattern(build_pattern(pattern, position)?)
+        }
+        _ if value == "none" && !value_is_quoted && !ordered => Value::Missing,
+        Field::Status | Field::Title | Field::Description | Field::Tag | Field::Project
+            if ordered =>
+        {
+            return Err(format!(
+                "'{field_name}' can only be compared with ':', '=', '!=' or '~' at position {position}"
+            ))
+        }
+        Field::Status => match value.to_lowercase().as_str() {
+            "open" | "pending" | "todo" | "undone" => Value::Done(false),
+            "done" | "completed" | "closed" => Value::Done(true),
+            _ => {
+                return Err(format!(
+                    "Unknown status '{value}' at position {position}, use open or done"
+                ))
+            }
+        },
+        Field::Title | Field::Description | Field::Tag | Field::Project => {
+            Value::Text(value.into())
+        }
+        Field::Priority => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(priority), None) if priority.is_ascii_alphabetic() => {
+                    Value::Priority(priority.to_ascii_uppercase())
+                }
+                _ => {
+                    return Err(format!(
+                        "Priority should be a letter from A to Z at position {position}"
+                    ))
+                }
+            }
+        }
+        Field::Due => match Date::parse(value) {
+            Ok(date) => Value::Date(date),
+            Err(err) => return Err(format!("{err} at position {position}")),
+        },
+    };
+
+    Ok(Filter::Condition(field, comparison, value))
+}
+
+fn build_pattern(pattern: &str, position: usize) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|_| format!("Invalid regular expression '{pattern}' at position {position}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, done: bool, fields: &[&str]) -> ToDo {
+        let mut todo = ToDo::new(title.into(), "description".into());
+        if done {
+            todo.done = "✅".into();
+        }
+        for field in fields {
+            todo.set_field(field).unwrap();
+        }
+        todo
+    }
+
+    fn matching_titles(expression: &str, todos: &[ToDo]) -> Vec<String> {
+        let filter = Filter::parse(expression).unwrap();
+        todos
+            .iter()
+            .filter(|todo| filter.matches(todo))
+            .map(|todo| todo.title.clone())
+            .collect()
+    }
+
+    fn sample_todos() -> Vec<ToDo> {
+        vec![
+            todo("Fix login page", false, &["tags:bug", "due:2026-10-20"]),
+            todo("Write docs", false, &["tags:docs", "priority:B"]),
+            todo(
+                "Login audit",
+                true,
+                &["tags:bug,security", "due:2026-12-01"],
+            ),
+            todo("Release", false, &["project:web", "priority:A"]),
+        ]
+    }
+
+    #[test]
+    fn should_match_everything_with_an_empty_filter() {
+        assert_eq!(matching_titles("", &sample_todos()).len(), 4);
+    }
+
+    #[test]
+    fn should_combine_field_predicates_with_implicit_and() {
+        let titles = matching_titles(
+            "status:open tag:bug due<2026-11-01 \"login\"",
+            &sample_todos(),
+        );
+        assert_eq!(titles, vec!["Fix login page"]);
+    }
+
+    #[test]
+    fn should_support_or_not_and_parentheses() {
+        let titles = matching_titles(
+            "(tag:docs OR project:web) and not priority:A",
+            &sample_todos(),
+        );
+        assert_eq!(titles, vec!["Write docs"]);
+
+        let titles = matching_titles("-tag:bug priority<=B", &sample_todos());
+        assert_eq!(titles, vec!["Write docs", "Release"]);
+
+        let titles = matching_titles("due:none", &sample_todos());
+        assert_eq!(titles, vec!["Write docs", "Release"]);
+    }
+
+    #[test]
+    fn should_match_text_case_insensitively_and_with_patterns() {
+        let titles = matching_titles("LOGIN", &sample_todos());
+        assert_eq!(titles, vec!["Fix login page", "Login audit"]);
+
+        let titles = matching_titles("/^login/ or title~^rel", &sample_todos());
+        assert_eq!(titles, vec!["Login audit", "Release"]);
+    }
+
+    #[test]
+    fn should_report_clear_errors() {
+        let err = Filter::parse("status:open colour:red").unwrap_err();
+        assert_eq!(
+            err,
+            "Unknown field 'colour' at position 13, fields are status, title, description, tag, project, priority and due"
+        );
+
+        let err = Filter::parse("(tag:bug or tag:docs").unwrap_err();
+        assert_eq!(err, "Missing closing parenthesis for '(' at position 1");
+
+        let err = Filter::parse("tag:bug or").unwrap_err();
+        assert_eq!(err, "Expected a condition at the end of the filter");
+
+        let err = Filter::parse("due<tomorow").unwrap_err();
+        assert_eq!(err, "Date should be written as YYYY-MM-DD at position 1");
+
+        let err = Filter::parse("title<b").unwrap_err();
+        assert_eq!(
+            err,
+            "'title' can only be compared with ':', '=', '!=' or '~' at position 1"
+        );
+
+        let err = Filter::parse("\"login").unwrap_err();
+        assert_eq!(err, "Missing closing quote for the term at position 1");
+
+        let err = Filter::parse("/[a/").unwrap_err();
+        assert_eq!(err, "Invalid regular expression '[a' at position 1");
+    }
+}
@@ -1,4 +1,12 @@
 pub mod action;
+pub mod config;
+pub mod date;
+pub mod filter;
 pub mod log_wrapper;
+pub mod output;
+pub mod paths;
+pub mod template;
+pub mod text;
+pub mod theme;
 pub mod todo;
 pub mod validator;
@@ -1,12 +1,163 @@
 use std::any::Any;
-use std::{error::Error, io::Write};
+use std::{
+    env,
+    error::Error,
+    io::{self, IsTerminal, Write},
+};
+
+use crate::config;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+/// How a piece of output looks on terminals that support colours.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub color: Option<Color>,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style {
+        bold: false,
+        dim: false,
+        italic: false,
+        underline: false,
+        strikethrough: false,
+        color: None,
+    };
+
+    /// Parses space separated attributes and a colour, like `bold red` or
+    /// `dim strikethrough`. `plain` or an empty string gives no styling.
+    pub fn parse(style_str: &str) -> Result<Self, &'static str> {
+        let mut style = Style::PLAIN;
+
+        for word in style_str.split_whitespace() {
+            match word {
+                "plain" => {}
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "strikethrough" | "strike" => style.strikethrough = true,
+                "black" => style.color = Some(Color::Black),
+                "red" => style.color = Some(Color::Red),
+                "green" => style.color = Some(Color::Green),
+                "yellow" => style.color = Some(Color::Yellow),
+                "blue" => style.color = Some(Color::Blue),
+                "magenta" => style.color = Some(Color::Magenta),
+                "cyan" => style.color = Some(Color::Cyan),
+                "white" => style.color = Some(Color::White),
+                _ => return Err("Style can only be made of bold, dim, italic, underline, strikethrough and a colour"),
+            }
+        }
+
+        Ok(style)
+    }
+
+    fn ansi_codes(&self) -> String {
+        let mut codes: Vec<String> = vec![];
+
+        for (enabled, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+            (self.strikethrough, "9"),
+        ] {
+            if enabled {
+                codes.push(code.into());
+            }
+        }
+
+        if let Some(color) = self.color {
+            codes.push((30 + color as u8).to_string());
+        }
+
+        codes.join(";")
+    }
+}
 
 pub trait Logger: Any {
-    fn log_errln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>>;
-    fn log_stdln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>>;
-    fn log_err<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>>;
-    fn log_std<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>>;
+    fn log_errln(&mut self, msg: &str) -> Result<(), Box<dyn Error>>;
+    fn log_stdln(&mut self, msg: &str) -> Result<(), Box<dyn Error>>;
+    fn log_err(&mut self, msg: &str) -> Result<(), Box<dyn Error>>;
+    fn log_std(&mut self, msg: &str) -> Result<(), Box<dyn Error>>;
     fn as_any(&self) -> &dyn Any;
+
+    /// Logs a line made of styled parts. Loggers without colours log the
+    /// plain text.
+    fn log_styled_stdln(&mut self, parts: &[(String, Style)]) -> Result<(), Box<dyn Error>> {
+        let line: String = parts.iter().map(|(text, _)| text.as_str()).collect();
+        self.log_stdln(&line)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ColorChoice {
+    Always,
+    Never,
+    Auto,
+}
+
+impl ColorChoice {
+    pub fn new(choice_name: &str) -> Result<Self, &'static str> {
+        match choice_name {
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err("Color can only be always, never or auto"),
+        }
+    }
+
+    /// The choice used when `--color` is not given, from the `color` setting.
+    pub fn configured() -> Result<Self, &'static str> {
+        ColorChoice::new(config::current().get("color").unwrap_or("auto"))
+    }
+
+    /// Whether to colour stdout, looking at `NO_COLOR`, `CLICOLOR_FORCE` and
+    /// whether stdout is a terminal when the choice is `auto`.
+    pub fn enabled(&self) -> bool {
+        self.enabled_with(
+            env::var("NO_COLOR").ok(),
+            env::var("CLICOLOR_FORCE").ok(),
+            io::stdout().is_terminal(),
+        )
+    }
+
+    fn enabled_with(
+        &self,
+        no_color: Option<String>,
+        clicolor_force: Option<String>,
+        is_terminal: bool,
+    ) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if no_color.is_some_and(|no_color| !no_color.is_empty()) {
+                    return false;
+                }
+                if clicolor_force.is_some_and(|force| !force.is_empty() && force != "0") {
+                    return true;
+                }
+                is_terminal
+            }
+        }
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -17,6 +168,7 @@ where
 {
     pub err_writer: ERRW,
     pub std_writer: STDW,
+    pub color: bool,
 }
 
 impl<ERRW, STDW> LogWrapper<ERRW, STDW>
@@ -28,6 +180,7 @@ where
         LogWrapper {
             err_writer,
             std_writer,
+            color: false,
         }
     }
 }
@@ -37,24 +190,85 @@ where
     ERRW: Write,
     STDW: Write,
 {
-    fn log_errln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>> {
-        writeln!(self.err_writer, "{}", &msg)?;
+    fn log_errln(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        writeln!(self.err_writer, "{}", msg)?;
         Ok(())
     }
-    fn log_stdln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>> {
-        writeln!(self.std_writer, "{}", &msg)?;
+    fn log_stdln(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        writeln!(self.std_writer, "{}", msg)?;
         Ok(())
     }
-    fn log_err<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>> {
-        write!(self.err_writer, "{}", &msg)?;
+    fn log_err(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        write!(self.err_writer, "{}", msg)?;
         Ok(())
     }
-    fn log_std<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn Error>> {
-        write!(self.std_writer, "{}", &msg)?;
+    fn log_std(&mut self, msg: &str) -> Result<(), Box<dyn Error>> {
+        write!(self.std_writer, "{}", msg)?;
         Ok(())
     }
 
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn log_styled_stdln(&mut self, parts: &[(String, Style)]) -> Result<(), Box<dyn Error>> {
+        for (text, style) in parts {
+            match style.ansi_codes() {
+                codes if self.color && !codes.is_empty() && !text.is_empty() => {
+                    write!(self.std_writer, "\x1b[{codes}m{text}\x1b[0m")?
+                }
+                _ => write!(self.std_writer, "{text}")?,
+            }
+        }
+        writeln!(self.std_writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_style_output_when_colour_is_enabled() {
+        let parts = vec![
+            ("# 1 ".to_string(), Style::PLAIN),
+            (
+                "title".to_string(),
+                Style::parse("dim strikethrough").unwrap(),
+            ),
+        ];
+
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        logger.log_styled_stdln(&parts).unwrap();
+        assert_eq!(logger.std_writer, "# 1 title\n".as_bytes());
+
+        logger.std_writer.clear();
+        logger.color = true;
+        logger.log_styled_stdln(&parts).unwrap();
+        assert_eq!(logger.std_writer, "# 1 \x1b[2;9mtitle\x1b[0m\n".as_bytes());
+    }
+
+    #[test]
+    fn should_parse_styles() {
+        let style = Style::parse("bold red").unwrap();
+        assert_eq!(style.ansi_codes(), "1;31");
+        assert_eq!(Style::parse("plain"), Ok(Style::PLAIN));
+        assert!(Style::parse("sparkly").is_err());
+    }
+
+    #[test]
+    fn should_detect_when_to_use_colours() {
+        let auto = ColorChoice::Auto;
+        assert!(auto.enabled_with(None, None, true));
+        assert!(!auto.enabled_with(None, None, false));
+        assert!(!auto.enabled_with(Some("1".into()), None, true));
+        assert!(auto.enabled_with(Some("".into()), None, true));
+        assert!(auto.enabled_with(None, Some("1".into()), false));
+        assert!(!auto.enabled_with(None, Some("0".into()), false));
+        assert!(!auto.enabled_with(Some("1".into()), Some("1".into()), true));
+
+        assert!(ColorChoice::Always.enabled_with(Some("1".into()), None, false));
+        assert!(!ColorChoice::Never.enabled_with(None, Some("1".into()), true));
+    }
 }
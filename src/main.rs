@@ -4,8 +4,10 @@ use std::{
     process,
 };
 use terminal_todo::{
-    action::{database::DatabaseAgent, Action},
-    log_wrapper::LogWrapper,
+    action::database::DatabaseAgent,
+    config,
+    log_wrapper::{ColorChoice, LogWrapper},
+    output::{self, OutputMode},
     validator::Validator,
 };
 
@@ -13,14 +15,36 @@ fn main() {
     let mut logger: LogWrapper<Stderr, Stdout> = LogWrapper::new(io::stderr(), io::stdout());
     let database = DatabaseAgent {};
 
-    let mut valid_action: Action = Validator::validate_input(env::args(), &mut logger, &database)
+    let (global_options, user_input) = Validator::extract_global_options(env::args())
         .unwrap_or_else(|err| {
-            eprintln!("{err}");
+            output::report_error(&mut logger, OutputMode::Text, err);
             process::exit(1);
         });
 
-    valid_action.execute_action().unwrap_or_else(|err| {
-        eprintln!("{err}");
+    let given_output_mode = global_options.output_mode();
+
+    if let Err(err) = config::init(global_options.settings) {
+        output::report_error(&mut logger, given_output_mode, &err);
+        process::exit(1);
+    }
+
+    let (output_mode, color) = OutputMode::configured()
+        .and_then(|output_mode| Ok((output_mode, ColorChoice::configured()?)))
+        .unwrap_or_else(|err| {
+            output::report_error(&mut logger, given_output_mode, err);
+            process::exit(1);
+        });
+
+    logger.color = color.enabled() && !output_mode.is_json();
+
+    let result = Validator::validate_input(user_input.into_iter(), &mut logger, &database)
+        .and_then(|mut valid_action| {
+            valid_action.output = output_mode;
+            valid_action.execute_action()
+        });
+
+    if let Err(err) = result {
+        output::report_error(&mut logger, output_mode, err);
         process::exit(1);
-    });
+    }
 }
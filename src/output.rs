@@ -0,0 +1,446 @@
+use std::fmt::{Display, Write};
+
+use crate::{config, log_wrapper::Logger, todo::ToDo};
+
+/// How actions report back: human readable text, one JSON document, or
+/// newline-delimited JSON with one object per item.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum OutputMode {
+    Text,
+    Json,
+    Ndjson,
+}
+
+impl OutputMode {
+    pub fn new(mode_name: &str) -> Result<Self, &'static str> {
+        match mode_name {
+            "text" => Ok(OutputMode::Text),
+            "json" => Ok(OutputMode::Json),
+            "ndjson" => Ok(OutputMode::Ndjson),
+            _ => Err("Output can only be text, json or ndjson"),
+        }
+    }
+
+    /// The output used when `--output` is not given, from the `output`
+    /// setting.
+    pub fn configured() -> Result<Self, &'static str> {
+        OutputMode::new(config::current().get("output").unwrap_or("text"))
+    }
+
+    pub fn is_json(&self) -> bool {
+        *self != OutputMode::Text
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(value: &str) -> Self {
+        Json::String(value.into())
+    }
+
+    pub fn optional<T: ToString>(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Json::String(value.to_string()),
+            None => Json::Null,
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_json_string(f, value),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (position, value) in values.iter().enumerate() {
+                    if position > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (position, (key, value)) in fields.iter().enumerate() {
+                    if position > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+const INVALID_JSON: &str = "The JSON could not be read";
+
+impl Json {
+    /// Reads a JSON document. Numbers with a fraction or an exponent are cut
+    /// down to whole numbers, as nothing read needs more.
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut parser = JsonParser { text, position: 0 };
+        let value = parser.value()?;
+
+        match parser.skip_whitespace() {
+            None => Ok(value),
+            Some(_) => Err(INVALID_JSON),
+        }
+    }
+
+    /// Reads a JSON array, with the line each of its values starts on.
+    pub fn parse_array(text: &str) -> Result<Vec<(usize, Json)>, &'static str> {
+        let mut parser = JsonParser { text, position: 0 };
+        let mut values: Vec<(usize, Json)> = vec![];
+
+        parser.expect(b'[')?;
+        if parser.skip_whitespace() == Some(b']') {
+            parser.position += 1;
+        } else {
+            loop {
+                parser.skip_whitespace();
+                values.push((parser.line(), parser.value()?));
+
+                match parser.skip_whitespace() {
+                    Some(b',') => parser.position += 1,
+                    Some(b']') => {
+                        parser.position += 1;
+                        break;
+                    }
+                    _ => return Err(INVALID_JSON),
+                }
+            }
+        }
+
+        match parser.skip_whitespace() {
+            None => Ok(values),
+            Some(_) => Err(INVALID_JSON),
+        }
+    }
+
+    /// The value of `key` in an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// The four hex digits of a `\\u` escape.
+fn code_unit(chars: &mut std::str::CharIndices) -> Result<u16, &'static str> {
+    let digits: String = chars.take(4).map(|(_, c)| c).collect();
+    u16::from_str_radix(&digits, 16).map_err(|_| INVALID_JSON)
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl JsonParser<'_> {
+    /// Skips whitespace, giving the byte that follows it.
+    fn skip_whitespace(&mut self) -> Option<u8> {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), &'static str> {
+        match self.skip_whitespace() {
+            Some(next) if next == byte => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(INVALID_JSON),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.text[..self.position].matches('\n').count() + 1
+    }
+
+    fn value(&mut self) -> Result<Json, &'static str> {
+        match self.skip_whitespace() {
+            Some(b'{') => self.object(),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values: Vec<Json> = vec![];
+                if self.skip_whitespace() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    match self.skip_whitespace() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => return Err(INVALID_JSON),
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => self.literal(),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, &'static str> {
+        self.expect(b'{')?;
+        let mut fields: Vec<(String, Json)> = vec![];
+
+        if self.skip_whitespace() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            if self.skip_whitespace() != Some(b'"') {
+                return Err(INVALID_JSON);
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+
+            match self.skip_whitespace() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(INVALID_JSON),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        let mut string = String::new();
+        // Skips the opening quote
+        let mut chars = self.text[self.position + 1..].char_indices();
+
+        let end = loop {
+            match chars.next() {
+                Some((index, '"')) => break index,
+                Some((_, '\\')) => match chars.next().map(|(_, c)| c) {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let mut code_units = vec![code_unit(&mut chars)?];
+                        // Characters outside the basic plane take a pair
+                        if (0xd800..0xdc00).contains(&code_units[0]) {
+                            match (chars.next(), chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => {
+                                    code_units.push(code_unit(&mut chars)?)
+                                }
+                                _ => return Err(INVALID_JSON),
+                            }
+                        }
+                        string
+                            .push_str(&String::from_utf16(&code_units).map_err(|_| INVALID_JSON)?);
+                    }
+                    _ => return Err(INVALID_JSON),
+                },
+                Some((_, c)) if (c as u32) < 0x20 => return Err(INVALID_JSON),
+                Some((_, c)) => string.push(c),
+                None => return Err(INVALID_JSON),
+            }
+        };
+
+        self.position += end + 2;
+        Ok(string)
+    }
+
+    fn number(&mut self) -> Result<Json, &'static str> {
+        let rest = &self.text[self.position..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        self.position += length;
+
+        match rest[..length].parse::<i64>() {
+            Ok(number) => Ok(Json::Number(number)),
+            Err(_) => match rest[..length].parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(Json::Number(number as i64)),
+                _ => Err(INVALID_JSON),
+            },
+        }
+    }
+
+    fn literal(&mut self) -> Result<Json, &'static str> {
+        let rest = &self.text[self.position..];
+
+        let (word, value) = [
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+            ("null", Json::Null),
+        ]
+        .into_iter()
+        .find(|(word, _)| rest.starts_with(word))
+        .ok_or(INVALID_JSON)?;
+
+        self.position += word.len();
+        Ok(value)
+    }
+}
+
+/// The JSON shape of an item, with `number` being its position in the list
+/// as used by `done`, `edit` and `delete`.
+pub fn todo_to_json(number: usize, todo: &ToDo) -> Json {
+    Json::object(vec![
+        ("number", Json::Number(number as i64)),
+        ("title", Json::string(&todo.title.replace('\n', ""))),
+        (
+            "description",
+            Json::string(&todo.description.replace('\n', "")),
+        ),
+        ("done", Json::Bool(todo.is_done())),
+        ("priority", Json::optional(todo.priority)),
+        ("due", Json::optional(todo.due)),
+        (
+            "tags",
+            Json::Array(todo.tags.iter().map(|tag| Json::string(tag)).collect()),
+        ),
+        ("project", Json::optional(todo.project.as_ref())),
+        ("created", timestamp_to_json(todo.created)),
+        ("updated", timestamp_to_json(todo.updated)),
+    ])
+}
+
+fn timestamp_to_json(timestamp: Option<u64>) -> Json {
+    match timestamp {
+        Some(timestamp) => Json::Number(timestamp as i64),
+        None => Json::Null,
+    }
+}
+
+/// Reports an error that stopped the program, as `{"error": ...}` when JSON
+/// output was asked for.
+pub fn report_error(logger: &mut dyn Logger, output: OutputMode, err: &str) {
+    let message = match output {
+        OutputMode::Text => err.to_string(),
+        OutputMode::Json | OutputMode::Ndjson => {
+            Json::object(vec![("error", Json::string(err))]).to_string()
+        }
+    };
+
+    logger.log_errln(&message).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_wrapper::LogWrapper;
+
+    #[test]
+    fn should_serialise_json_values() {
+        let json = Json::object(vec![
+            ("title", Json::string("say \"hi\"\n\\")),
+            ("done", Json::Bool(false)),
+            ("tags", Json::Array(vec![Json::string("a"), Json::Null])),
+            ("number", Json::Number(3)),
+            ("bell", Json::string("\u{7}")),
+        ]);
+        assert_eq!(
+            json.to_string(),
+            r#"{"title":"say \"hi\"\n\\","done":false,"tags":["a",null],"number":3,"bell":"\u0007"}"#
+        );
+    }
+
+    #[test]
+    fn should_read_json_values() {
+        assert_eq!(
+            Json::parse(
+                r#" {"title":"say \"hi\"\n\u00e9\ud83d\ude00","tags":["a",null],"urgency":-4.5e0,"done":true} "#
+            ),
+            Ok(Json::object(vec![
+                ("title", Json::string("say \"hi\"\né😀")),
+                ("tags", Json::Array(vec![Json::string("a"), Json::Null])),
+                ("urgency", Json::Number(-4)),
+                ("done", Json::Bool(true)),
+            ]))
+        );
+        assert_eq!(Json::parse(r#"{"title":"a",}"#), Err(INVALID_JSON));
+        assert_eq!(Json::parse("[1] 2"), Err(INVALID_JSON));
+
+        assert_eq!(
+            Json::parse_array("[\n{\"id\":1},\n\n{\"id\":2}\n]\n"),
+            Ok(vec![
+                (2, Json::object(vec![("id", Json::Number(1))])),
+                (4, Json::object(vec![("id", Json::Number(2))]))
+            ])
+        );
+    }
+
+    #[test]
+    fn should_turn_todos_into_json() {
+        let mut todo = ToDo::new("title\n".into(), "description\n".into());
+        todo.set_field("due:2026-11-01").unwrap();
+        assert_eq!(
+            todo_to_json(1, &todo).to_string(),
+            r#"{"number":1,"title":"title","description":"description","done":false,"priority":null,"due":"2026-11-01","tags":[],"project":null,"created":null,"updated":null}"#
+        );
+    }
+
+    #[test]
+    fn should_report_errors_as_json() {
+        let mut logger = LogWrapper::new(Vec::<u8>::new(), Vec::<u8>::new());
+        report_error(&mut logger, OutputMode::Json, "Given item index is wrong");
+        report_error(&mut logger, OutputMode::Text, "Given item index is wrong");
+        assert_eq!(
+            logger.err_writer,
+            "{\"error\":\"Given item index is wrong\"}\nGiven item index is wrong\n".as_bytes()
+        );
+        assert!(logger.std_writer.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "terminal-todo";
+
+/// Where the lists are kept, `$XDG_DATA_HOME/terminal-todo` or
+/// `~/.local/share/terminal-todo`.
+pub fn data_dir() -> Result<PathBuf, &'static str> {
+    app_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Where the user config file is, `$XDG_CONFIG_HOME/terminal-todo` or
+/// `~/.config/terminal-todo`.
+pub fn config_dir() -> Result<PathBuf, &'static str> {
+    app_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Where history that is not worth syncing is kept, like the undo journal,
+/// `$XDG_STATE_HOME/terminal-todo` or `~/.local/state/terminal-todo`.
+pub fn state_dir() -> Result<PathBuf, &'static str> {
+    app_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Where the database used to be kept before following the XDG directories.
+pub fn legacy_dir() -> Option<PathBuf> {
+    match cfg!(target_os = "windows") {
+        true => absolute_var("AppData").map(|app_data| app_data.join(".terminal-todo")),
+        false => absolute_var("HOME").map(|home| home.join(".terminal-todo")),
+    }
+}
+
+fn app_dir(xdg_var: &str, home_fallback: &str) -> Result<PathBuf, &'static str> {
+    if cfg!(target_os = "windows") {
+        return match absolute_var("AppData") {
+            Some(app_data) => Ok(app_data.join(APP_NAME)),
+            None => Err("Could not find the AppData directory, set database.path instead"),
+        };
+    }
+
+    match xdg_dir(absolute_var(xdg_var), absolute_var("HOME"), home_fallback) {
+        Some(dir) => Ok(dir),
+        None => Err("Could not find a home directory, set HOME or database.path"),
+    }
+}
+
+fn xdg_dir(xdg: Option<PathBuf>, home: Option<PathBuf>, home_fallback: &str) -> Option<PathBuf> {
+    xdg.or_else(|| home.map(|home| home.join(home_fallback)))
+        .map(|dir| dir.join(APP_NAME))
+}
+
+/// The value of an environment variable holding a path, ignored when empty or
+/// relative as the XDG specification asks.
+fn absolute_var(name: &str) -> Option<PathBuf> {
+    env::var(name)
+        .ok()
+        .filter(|value| Path::new(value).is_absolute())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefer_xdg_directories_over_home() {
+        assert_eq!(
+            xdg_dir(
+                Some("/xdg/data".into()),
+                Some("/home/me".into()),
+                ".local/share"
+            ),
+            Some(PathBuf::from("/xdg/data/terminal-todo"))
+        );
+        assert_eq!(
+            xdg_dir(None, Some("/home/me".into()), ".local/state"),
+            Some(PathBuf::from("/home/me/.local/state/terminal-todo"))
+        );
+        assert_eq!(xdg_dir(None, None, ".config"), None);
+    }
+}
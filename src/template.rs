@@ -0,0 +1,438 @@
+use crate::{
+    date::{self, Date},
+    log_wrapper::Style,
+    text::{display_width, truncate},
+    theme::Theme,
+    todo::ToDo,
+};
+
+const FIELDS: &str =
+    "id, status, title, description, priority, due, tags, project, created and updated";
+
+/// The layout of `list` when no `--format table` is asked for.
+pub const LONG_TEMPLATE: &str = "===============
+# {id}
+Title: {title}
+Description: {description}
+Done: {status}
+Priority: {priority}
+Due: {due}
+Tags: {tags}
+Project: {project}
+===============
+";
+
+/// The layout of a single item on its own, used by its `Display`.
+pub const ITEM_TEMPLATE: &str = "Title: {title}
+Description: {description}
+Done: {status}
+Priority: {priority}
+Due: {due}
+Tags: {tags}
+Project: {project}";
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Field {
+    Id,
+    Status,
+    Title,
+    Description,
+    Priority,
+    Due,
+    Tags,
+    Project,
+    Created,
+    Updated,
+}
+
+impl Field {
+    fn new(field_name: &str) -> Option<Self> {
+        match field_name {
+            "id" | "number" => Some(Field::Id),
+            "status" | "done" => Some(Field::Status),
+            "title" => Some(Field::Title),
+            "description" | "desc" => Some(Field::Description),
+            "priority" => Some(Field::Priority),
+            "due" => Some(Field::Due),
+            "tags" => Some(Field::Tags),
+            "project" => Some(Field::Project),
+            "created" => Some(Field::Created),
+            "updated" => Some(Field::Updated),
+            _ => None,
+        }
+    }
+
+    fn value(&self, index: usize, todo: &ToDo, theme: &Theme) -> String {
+        match self {
+            Field::Id => (index + 1).to_string(),
+            Field::Status => theme.status(todo).into(),
+            Field::Title => todo.title.replace('\n', ""),
+            Field::Description => todo.description.replace('\n', ""),
+            Field::Priority => todo.priority.map(String::from).unwrap_or_default(),
+            Field::Due => todo
+                .due
+                .map(|due| due.format(&theme.date_format))
+                .unwrap_or_default(),
+            Field::Tags => todo.tags.join(", "),
+            Field::Project => todo.project.clone().unwrap_or_default(),
+            Field::Created => timestamp_date(todo.created, theme),
+            Field::Updated => timestamp_date(todo.updated, theme),
+        }
+    }
+
+    fn style(&self, todo: &ToDo, theme: &Theme, today: Date) -> Style {
+        match self {
+            Field::Title if todo.is_done() => theme.done,
+            Field::Priority => todo.priority.map_or(Style::PLAIN, |p| theme.priority(p)),
+            Field::Due if !todo.is_done() && todo.due.is_some_and(|due| due < today) => {
+                theme.overdue
+            }
+            Field::Tags => theme.tags,
+            _ => Style::PLAIN,
+        }
+    }
+}
+
+fn timestamp_date(timestamp: Option<u64>, theme: &Theme) -> String {
+    timestamp
+        .map(|timestamp| Date::from_timestamp(date::local(timestamp)).format(&theme.date_format))
+        .unwrap_or_default()
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// The `[[fill]align][width][.precision]` part of a `{field:spec}`
+/// placeholder, following Rust's own format strings.
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Spec {
+    fill: char,
+    align: Align,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec_str: &str) -> Option<Self> {
+        let mut spec = Spec {
+            fill: ' ',
+            align: Align::Left,
+            width: 0,
+            precision: None,
+        };
+
+        let align = |c: char| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+
+        let chars: Vec<char> = spec_str.chars().collect();
+        let mut rest: &[char] = &chars;
+
+        if let Some(aligned) = chars.get(1).and_then(|c| align(*c)) {
+            spec.fill = chars[0];
+            spec.align = aligned;
+            rest = &chars[2..];
+        } else if let Some(aligned) = chars.first().and_then(|c| align(*c)) {
+            spec.align = aligned;
+            rest = &chars[1..];
+        }
+
+        let rest: String = rest.iter().collect();
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest.as_str(), None),
+        };
+
+        if !width.is_empty() {
+            spec.width = width.parse().ok()?;
+        }
+        if let Some(precision) = precision {
+            spec.precision = Some(precision.parse().ok()?);
+        }
+
+        Some(spec)
+    }
+
+    fn apply(&self, value: String, style: Style, parts: &mut Vec<(String, Style)>) {
+        let value = match self.precision {
+            Some(precision) => truncate(&value, precision),
+            None => value,
+        };
+
+        let padding = self.width.saturating_sub(display_width(&value));
+        let (before, after) = match self.align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = |count: usize| (self.fill.to_string().repeat(count), Style::PLAIN);
+
+        if before > 0 {
+            parts.push(fill(before));
+        }
+        parts.push((value, style));
+        if after > 0 {
+            parts.push(fill(after));
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum Segment {
+    Text(String),
+    Field(Field, Spec),
+}
+
+/// How an item is laid out, as text with `{field}` or `{field:spec}`
+/// placeholders, e.g. `{id:>3} {status} {title} {tags} {due}`. `{{` and `}}`
+/// stand for literal braces and `\n` for a new line. Lines whose fields are all
+/// empty are left out, so optional fields can get a line of their own.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Template {
+    lines: Vec<Vec<Segment>>,
+}
+
+impl Template {
+    pub fn parse(template_str: &str) -> Result<Self, String> {
+        let mut lines: Vec<Vec<Segment>> = vec![vec![]];
+        let mut text = String::new();
+        let mut chars = template_str.chars().enumerate().peekable();
+
+        let flush = |text: &mut String, lines: &mut Vec<Vec<Segment>>| {
+            if !text.is_empty() {
+                lines
+                    .last_mut()
+                    .unwrap()
+                    .push(Segment::Text(std::mem::take(text)));
+            }
+        };
+
+        while let Some((index, c)) = chars.next() {
+            // 1-based character position in the template, for error messages
+            let position = index + 1;
+
+            match c {
+                '\n' => {
+                    flush(&mut text, &mut lines);
+                    lines.push(vec![]);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => {
+                        flush(&mut text, &mut lines);
+                        lines.push(vec![]);
+                    }
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, escaped)) => text.push(escaped),
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek().is_some_and(|(_, next)| *next == '{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(format!("Unexpected '}}' at position {position}")),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => placeholder.push(c),
+                            None => {
+                                return Err(format!(
+                                    "Missing closing '}}' for the field at position {position}"
+                                ))
+                            }
+                        }
+                    }
+
+                    let (field_name, spec_str) = match placeholder.split_once(':') {
+                        Some((field_name, spec_str)) => (field_name.trim(), spec_str),
+                        None => (placeholder.trim(), ""),
+                    };
+
+                    let field = match Field::new(field_name) {
+                        Some(field) => field,
+                        None => {
+                            return Err(format!(
+                                "Unknown field '{field_name}' at position {position}, fields are {FIELDS}"
+                            ))
+                        }
+                    };
+
+                    let spec = match Spec::parse(spec_str) {
+                        Some(spec) => spec,
+                        None => {
+                            return Err(format!(
+                            "Invalid format '{spec_str}' for '{field_name}' at position {position}"
+                        ))
+                        }
+                    };
+
+                    flush(&mut text, &mut lines);
+                    lines.last_mut().unwrap().push(Segment::Field(field, spec));
+                }
+                _ => text.push(c),
+            }
+        }
+
+        flush(&mut text, &mut lines);
+
+        Ok(Template { lines })
+    }
+
+    /// The styled text of the item at `index` in the list.
+    pub fn render(
+        &self,
+        index: usize,
+        todo: &ToDo,
+        theme: &Theme,
+        today: Date,
+    ) -> Vec<(String, Style)> {
+        let mut parts: Vec<(String, Style)> = vec![];
+        let mut first_line = true;
+
+        for line in &self.lines {
+            let mut line_parts: Vec<(String, Style)> = vec![];
+            let mut has_fields = false;
+            let mut has_values = false;
+
+            for segment in line {
+                match segment {
+                    Segment::Text(text) => line_parts.push((text.clone(), Style::PLAIN)),
+                    Segment::Field(field, spec) => {
+                        let value = field.value(index, todo, theme);
+                        has_fields = true;
+                        has_values |= !value.is_empty();
+                        spec.apply(value, field.style(todo, theme, today), &mut line_parts);
+                    }
+                }
+            }
+
+            if has_fields && !has_values {
+                continue;
+            }
+
+            if !first_line {
+                parts.push(("\n".into(), Style::PLAIN));
+            }
+            first_line = false;
+            parts.append(&mut line_parts);
+        }
+
+        parts
+    }
+
+    /// The item at `index` rendered as plain text.
+    pub fn render_text(&self, index: usize, todo: &ToDo, theme: &Theme, today: Date) -> String {
+        self.render(index, todo, theme, today)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, fields: &[&str]) -> ToDo {
+        let mut todo = ToDo::new(title.into(), "description".into());
+        for field in fields {
+            todo.set_field(field).unwrap();
+        }
+        todo
+    }
+
+    #[test]
+    fn should_render_fields_with_alignment_and_precision() {
+        let template = Template::parse("{id:>3} {status} {title:.8} {tags} {due}").unwrap();
+        let todo = todo("Fix the login page", &["tags:bug,ui", "due:2026-11-01"]);
+        let today = Date::new(2026, 10, 19).unwrap();
+
+        assert_eq!(
+            template.render_text(4, &todo, &Theme::default(), today),
+            "  5 ❌ Fix the… bug, ui 2026-11-01"
+        );
+
+        let template = Template::parse("[{priority:*^5}] {{{project:-<6}}}").unwrap();
+        let todo = self::todo("title", &["priority:B", "project:web"]);
+        assert_eq!(
+            template.render_text(0, &todo, &Theme::named("ascii").unwrap(), today),
+            "[**B**] {web---}"
+        );
+    }
+
+    #[test]
+    fn should_drop_lines_with_only_empty_fields() {
+        let template = Template::parse("{title}\\nDue: {due}\\n  {tags}\\n--").unwrap();
+        let todo = todo("title", &["tags:a"]);
+        let today = Date::new(2026, 10, 19).unwrap();
+
+        assert_eq!(
+            template.render_text(0, &todo, &Theme::default(), today),
+            "title\n  a\n--"
+        );
+    }
+
+    #[test]
+    fn should_style_fields_from_the_theme() {
+        let mut todo = todo("title", &["due:2026-10-01", "priority:A"]);
+        let theme = Theme::default();
+        let today = Date::new(2026, 10, 19).unwrap();
+        let template = Template::parse("{title} {priority} {due}").unwrap();
+
+        let styled = |parts: Vec<(String, Style)>| -> Vec<(String, Style)> {
+            parts
+                .into_iter()
+                .filter(|(_, style)| *style != Style::PLAIN)
+                .collect()
+        };
+
+        assert_eq!(
+            styled(template.render(0, &todo, &theme, today)),
+            vec![
+                ("A".to_string(), theme.priority('A')),
+                ("2026-10-01".to_string(), theme.overdue)
+            ]
+        );
+
+        todo.done = "✅".into();
+        assert_eq!(
+            styled(template.render(0, &todo, &theme, today)),
+            vec![
+                ("title".to_string(), theme.done),
+                ("A".to_string(), theme.priority('A'))
+            ]
+        );
+    }
+
+    #[test]
+    fn should_point_at_template_errors() {
+        assert_eq!(
+            Template::parse("{id} {colour}"),
+            Err("Unknown field 'colour' at position 6, fields are id, status, title, description, priority, due, tags, project, created and updated".into())
+        );
+        assert_eq!(
+            Template::parse("{title"),
+            Err("Missing closing '}' for the field at position 1".into())
+        );
+        assert_eq!(
+            Template::parse("title}"),
+            Err("Unexpected '}' at position 6".into())
+        );
+        assert_eq!(
+            Template::parse("{due:>x}"),
+            Err("Invalid format '>x' for 'due' at position 1".into())
+        );
+    }
+}
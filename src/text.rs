@@ -0,0 +1,63 @@
+/// Cuts `text` to fit in `width` cells, ending it with an ellipsis when it
+/// does not.
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.into();
+    }
+
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+
+    for c in text.chars() {
+        let char_width = char_width(c);
+        if truncated_width + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        truncated_width += char_width;
+    }
+
+    truncated + "…"
+}
+
+/// Approximate number of terminal cells `text` takes, counting emoji and
+/// East Asian wide characters as two cells.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2705
+        | 0x270A..=0x270B
+        | 0x2728
+        | 0x274C
+        | 0x274E
+        | 0x2753..=0x2755
+        | 0x2757
+        | 0x2795..=0x2797
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_count_wide_characters_as_two_cells() {
+        assert_eq!(display_width("✅ done"), 7);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+    }
+}
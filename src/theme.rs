@@ -0,0 +1,142 @@
+use crate::{config, log_wrapper::Style, todo::ToDo};
+
+/// The symbols and colours `list` prints items with. Styles only show on
+/// terminals with colours enabled, symbols show everywhere.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Theme {
+    pub done_symbol: String,
+    pub open_symbol: String,
+    pub header: Style,
+    pub done: Style,
+    pub overdue: Style,
+    pub tags: Style,
+    /// Styles of priorities A, B, C, and of every priority below C.
+    pub priorities: [Style; 4],
+    /// How dates are written, see `Date::format`.
+    pub date_format: String,
+}
+
+impl Theme {
+    pub fn named(theme_name: &str) -> Result<Self, &'static str> {
+        let style = |style_str: &str| Style::parse(style_str).unwrap();
+
+        let default_theme = Theme {
+            done_symbol: "✅".into(),
+            open_symbol: "❌".into(),
+            header: style("bold"),
+            done: style("dim strikethrough"),
+            overdue: style("red"),
+            tags: Style::PLAIN,
+            priorities: [
+                style("bold red"),
+                style("yellow"),
+                style("green"),
+                style("cyan"),
+            ],
+            date_format: "%Y-%m-%d".into(),
+        };
+
+        match theme_name {
+            "default" => Ok(default_theme),
+            "ascii" => Ok(Theme {
+                done_symbol: "[x]".into(),
+                open_symbol: "[ ]".into(),
+                ..default_theme
+            }),
+            "mono" => Ok(Theme {
+                overdue: style("bold"),
+                priorities: [style("bold"), Style::PLAIN, Style::PLAIN, Style::PLAIN],
+                ..default_theme
+            }),
+            "plain" => Ok(Theme {
+                done_symbol: "[x]".into(),
+                open_symbol: "[ ]".into(),
+                header: Style::PLAIN,
+                done: Style::PLAIN,
+                overdue: Style::PLAIN,
+                tags: Style::PLAIN,
+                priorities: [Style::PLAIN; 4],
+                ..default_theme
+            }),
+            _ => Err("Theme can only be default, ascii, mono or plain"),
+        }
+    }
+
+    /// The theme used when `--theme` is not given: the `theme.name` setting
+    /// with the symbols, styles and date format changed by other settings.
+    pub fn configured() -> Result<Self, &'static str> {
+        let config = config::current();
+        let mut theme = Theme::named(config.get("theme.name").unwrap_or("default"))?;
+
+        if let Some(symbol) = config.get("theme.done_symbol") {
+            theme.done_symbol = symbol.into();
+        }
+        if let Some(symbol) = config.get("theme.open_symbol") {
+            theme.open_symbol = symbol.into();
+        }
+        if let Some(date_format) = config.get("date.format") {
+            theme.date_format = date_format.into();
+        }
+
+        let [priority_a, priority_b, priority_c, priority_other] = &mut theme.priorities;
+        for (key, style) in [
+            ("theme.header", &mut theme.header),
+            ("theme.done", &mut theme.done),
+            ("theme.overdue", &mut theme.overdue),
+            ("theme.tags", &mut theme.tags),
+            ("theme.priority_a", priority_a),
+            ("theme.priority_b", priority_b),
+            ("theme.priority_c", priority_c),
+            ("theme.priority_other", priority_other),
+        ] {
+            if let Some(style_str) = config.get(key) {
+                *style = Style::parse(style_str)?;
+            }
+        }
+
+        Ok(theme)
+    }
+
+    pub fn status(&self, todo: &ToDo) -> &str {
+        match todo.is_done() {
+            true => &self.done_symbol,
+            false => &self.open_symbol,
+        }
+    }
+
+    pub fn priority(&self, priority: char) -> Style {
+        let rank = (priority as usize).saturating_sub('A' as usize);
+        self.priorities[rank.min(3)]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::named("default").unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_symbols_and_styles_from_the_theme() {
+        let mut todo = ToDo::new("title".into(), "description".into());
+        let ascii = Theme::named("ascii").unwrap();
+        assert_eq!(ascii.status(&todo), "[ ]");
+
+        todo.done = "✅".into();
+        assert_eq!(ascii.status(&todo), "[x]");
+        assert_eq!(Theme::default().status(&todo), "✅");
+
+        assert_eq!(ascii.priority('A'), Style::parse("bold red").unwrap());
+        assert_eq!(ascii.priority('Z'), Style::parse("cyan").unwrap());
+        assert_eq!(Theme::named("plain").unwrap().priority('A'), Style::PLAIN);
+
+        assert_eq!(
+            Theme::named("neon"),
+            Err("Theme can only be default, ascii, mono or plain")
+        );
+    }
+}
@@ -1,10 +1,25 @@
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq)]
+use crate::{
+    date::Date,
+    template::{Template, ITEM_TEMPLATE},
+    theme::Theme,
+};
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct ToDo {
     pub title: String,
     pub description: String,
     pub done: String,
+    pub priority: Option<char>,
+    pub due: Option<Date>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub created: Option<u64>,
+    pub updated: Option<u64>,
+    /// The uuid of a task imported from Taskwarrior, which it is exported
+    /// back with.
+    pub uuid: Option<String>,
 }
 
 impl ToDo {
@@ -13,41 +28,201 @@ impl ToDo {
             title,
             description,
             done: "❌".into(),
+            priority: None,
+            due: None,
+            tags: vec![],
+            project: None,
+            created: None,
+            updated: None,
+            uuid: None,
+        }
+    }
+
+    /// Records the time of a change made to the item.
+    pub fn touch(&mut self, timestamp: u64) {
+        self.updated = Some(timestamp);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done == "✅"
+    }
+
+    /// Applies a `field:value` assignment such as `due:2026-11-01`, `priority:A`,
+    /// `project:web` or `tags:bug,ui`. An empty value clears the field.
+    pub fn set_field(&mut self, assignment: &str) -> Result<(), &'static str> {
+        let (field, value) = match assignment.split_once(':') {
+            Some(field_value) => field_value,
+            None => return Err("Fields should be given as field:value"),
+        };
+
+        match field {
+            "priority" => self.priority = parse_priority(value)?,
+            "due" if value.is_empty() => self.due = None,
+            "due" => self.due = Some(Date::parse(value)?),
+            "project" if value.is_empty() => self.project = None,
+            "project" => self.project = Some(value.into()),
+            "tags" => {
+                let tags: Vec<&str> = value
+                    .split(',')
+                    .map(|tag| tag.trim())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+
+                if tags.iter().any(|tag| tag.contains(char::is_whitespace)) {
+                    return Err("Tags cannot contain spaces");
+                }
+
+                self.tags = tags.into_iter().map(|tag| tag.into()).collect();
+            }
+            _ => return Err("Field can only be priority, due, project or tags"),
         }
+
+        Ok(())
     }
 
     pub fn deserialise(todo_str: &str) -> Result<Self, &'static str> {
-        let props: Vec<&str> = todo_str.split(',').collect();
+        match ToDo::salvage(todo_str) {
+            (Some(todo), problems) if problems.is_empty() => Ok(todo),
+            _ => Err("Database is corrupted, could not read data"),
+        }
+    }
+
+    /// Reads what it can of a serialised item, leaving out the fields it
+    /// cannot make sense of, with why each of them was left out. Nothing is
+    /// left of an item missing its title, description or status.
+    pub fn salvage(todo_str: &str) -> (Option<Self>, Vec<&'static str>) {
+        if todo_str.trim().is_empty() {
+            return (None, vec!["is empty"]);
+        }
+
+        let props: Vec<String> = split_escaped(todo_str);
+
+        if props.len() < 3 {
+            return (None, vec!["is missing the title, description or status"]);
+        }
 
-        if props.len() != 3 {
-            return Err("Database is corrupted, could not read data");
+        let mut todo = ToDo {
+            title: props[0].clone(),
+            description: props[1].clone(),
+            done: props[2].clone(),
+            ..ToDo::new(String::new(), String::new())
+        };
+        let mut problems: Vec<&'static str> = vec![];
+
+        for prop in props.iter().skip(3) {
+            let (key, value) = match prop.split_once(':') {
+                Some(key_value) => key_value,
+                None => {
+                    problems.push("has a field without a name");
+                    continue;
+                }
+            };
+
+            match key {
+                "priority" => match parse_priority(value) {
+                    Ok(priority) => todo.priority = priority,
+                    Err(_) => problems.push("has a priority that is not a single letter"),
+                },
+                "due" => match Date::parse(value) {
+                    Ok(due) => todo.due = Some(due),
+                    Err(_) => problems.push("has a due date that is not YYYY-MM-DD"),
+                },
+                "tags" => todo.tags = value.split(' ').map(|tag| tag.into()).collect(),
+                "project" => todo.project = Some(value.into()),
+                "uuid" => todo.uuid = Some(value.into()),
+                "created" | "updated" => match value.parse::<u64>() {
+                    Ok(timestamp) if key == "created" => todo.created = Some(timestamp),
+                    Ok(timestamp) => todo.updated = Some(timestamp),
+                    Err(_) => {
+                        problems.push("has a created or updated time that is not a timestamp")
+                    }
+                },
+                _ => problems.push("has an unknown field"),
+            }
         }
 
-        Ok(ToDo {
-            title: props.get(0).unwrap().to_string(),
-            description: props.get(1).unwrap().to_string(),
-            done: props.get(2).unwrap().to_string(),
-        })
+        (Some(todo), problems)
     }
 
     pub fn serialise(&self) -> String {
-        format!(
-            "{},{},{}\n",
-            self.title.replace('\n', ""),
-            self.description.replace('\n', ""),
-            self.done
-        )
+        let mut props: Vec<String> = vec![
+            escape(&self.title.replace('\n', "")),
+            escape(&self.description.replace('\n', "")),
+            self.done.clone(),
+        ];
+
+        if let Some(priority) = self.priority {
+            props.push(format!("priority:{priority}"));
+        }
+        if let Some(due) = self.due {
+            props.push(format!("due:{due}"));
+        }
+        if !self.tags.is_empty() {
+            props.push(format!("tags:{}", escape(&self.tags.join(" "))));
+        }
+        if let Some(project) = &self.project {
+            props.push(format!("project:{}", escape(project)));
+        }
+        if let Some(created) = self.created {
+            props.push(format!("created:{created}"));
+        }
+        if let Some(updated) = self.updated {
+            props.push(format!("updated:{updated}"));
+        }
+        if let Some(uuid) = &self.uuid {
+            props.push(format!("uuid:{}", escape(uuid)));
+        }
+
+        format!("{}\n", props.join(","))
+    }
+}
+
+fn parse_priority(value: &str) -> Result<Option<char>, &'static str> {
+    let mut chars = value.chars();
+
+    match (chars.next(), chars.next()) {
+        (None, _) => Ok(None),
+        (Some(priority), None) if priority.is_ascii_alphabetic() => {
+            Ok(Some(priority.to_ascii_uppercase()))
+        }
+        _ => Err("Priority should be a single letter from A to Z"),
+    }
+}
+
+/// Commas separate the serialised fields, so they (and the escape character
+/// itself) are prefixed with a backslash inside values.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(',', "\\,")
+}
+
+/// Splits a line into its fields. Only `\,` and `\\` are escapes, so any other
+/// backslash, like those of lines written before values were escaped, is
+/// kept as it is.
+fn split_escaped(todo_str: &str) -> Vec<String> {
+    let mut props: Vec<String> = vec![String::new()];
+    let mut chars = todo_str.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some(',' | '\\')) => {
+                props.last_mut().unwrap().push(chars.next().unwrap());
+            }
+            ',' => props.push(String::new()),
+            _ => props.last_mut().unwrap().push(c),
+        }
     }
+
+    props
 }
 
 impl Display for ToDo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let template = Template::parse(ITEM_TEMPLATE).unwrap();
+
         write!(
             f,
-            "Title: {}\nDescription: {}\nDone: {}",
-            self.title.replace('\n', ""),
-            self.description.replace('\n', ""),
-            self.done,
+            "{}",
+            template.render_text(0, self, &Theme::default(), Date::today())
         )
     }
 }
@@ -62,6 +237,13 @@ mod tests {
             title: "title".into(),
             description: "description".into(),
             done: "❌".into(),
+            priority: None,
+            due: None,
+            tags: vec![],
+            project: None,
+            created: None,
+            updated: None,
+            uuid: None,
         };
         let todo: ToDo = ToDo::new("title".into(), "description".into());
         assert_eq!(todo, expected);
@@ -84,6 +266,13 @@ mod tests {
             title: "title".into(),
             description: "description".into(),
             done: "✅".into(),
+            priority: None,
+            due: None,
+            tags: vec![],
+            project: None,
+            created: None,
+            updated: None,
+            uuid: None,
         };
         assert_eq!(expected_todo, deserialised_todo,);
     }
@@ -96,7 +285,77 @@ mod tests {
             let expected_todo = "Database is corrupted, could not read data";
             assert_eq!(expected_todo, err);
         } else {
-            assert!(false);
+            panic!("A corrupted item should not be read");
         }
     }
+
+    #[test]
+    fn should_salvage_what_can_be_read() {
+        let (todo, problems) =
+            ToDo::salvage("title,description,✅,priority:AB,colour:red,project:web");
+        let mut expected_todo: ToDo = ToDo::new("title".into(), "description".into());
+        expected_todo.done = "✅".into();
+        expected_todo.project = Some("web".into());
+
+        assert_eq!(todo, Some(expected_todo));
+        assert_eq!(
+            problems,
+            vec![
+                "has a priority that is not a single letter",
+                "has an unknown field"
+            ]
+        );
+        assert_eq!(
+            ToDo::salvage("fsjl,dsj"),
+            (None, vec!["is missing the title, description or status"])
+        );
+    }
+
+    #[test]
+    fn should_round_trip_fields_and_commas() {
+        let mut todo: ToDo = ToDo::new("fix login, again".into(), "see C:\\logs".into());
+        todo.set_field("priority:b").unwrap();
+        todo.set_field("due:2026-11-01").unwrap();
+        todo.set_field("tags:bug,ui").unwrap();
+        todo.set_field("project:web").unwrap();
+        todo.created = Some(1_760_000_000);
+        todo.touch(1_760_000_000);
+
+        let serialised_todo: String = todo.serialise();
+        assert_eq!(
+            serialised_todo,
+            "fix login\\, again,see C:\\\\logs,❌,priority:B,due:2026-11-01,tags:bug ui,project:web,created:1760000000,updated:1760000000\n"
+        );
+        assert_eq!(ToDo::deserialise(serialised_todo.trim_end()), Ok(todo));
+    }
+
+    #[test]
+    fn should_keep_lone_backslashes_of_older_lines() {
+        let todo = ToDo::deserialise("copy to C:\\tmp,see \\d+ in D:\\new,❌").unwrap();
+        assert_eq!(todo.title, "copy to C:\\tmp");
+        assert_eq!(todo.description, "see \\d+ in D:\\new");
+
+        let serialised_todo: String = todo.serialise();
+        assert_eq!(
+            ToDo::deserialise(serialised_todo.trim_end()).unwrap().title,
+            "copy to C:\\tmp"
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_fields() {
+        let mut todo: ToDo = ToDo::new("title".into(), "description".into());
+        assert_eq!(
+            todo.set_field("colour:red"),
+            Err("Field can only be priority, due, project or tags")
+        );
+        assert_eq!(
+            todo.set_field("priority:AB"),
+            Err("Priority should be a single letter from A to Z")
+        );
+        assert_eq!(
+            todo.set_field("due:soon"),
+            Err("Date should be written as YYYY-MM-DD")
+        );
+    }
 }
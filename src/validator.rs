@@ -1,57 +1,235 @@
 use crate::{
-    action::{database::Database, Action},
-    log_wrapper::Logger,
+    action::{database::Database, Action, ActionType},
+    config,
+    log_wrapper::{ColorChoice, Logger},
+    output::OutputMode,
 };
-use std::env::Args;
+
+const USAGE: &str = "An action needs to be provided
+create
+list [filter] [--sort key[,key...]] [--reverse] [--group-by status|project|tag|due] [--format table|long] [--template template] [--theme default|ascii|mono|plain] [--description]
+edit [index] [field:value...]
+done [index...]
+undone [index...]
+delete [index...] [--purge]
+trash list|empty [--older-than 30d]
+restore index...
+backup list|restore timestamp
+history index
+doctor [--repair]
+encrypt
+change-passphrase
+import --from todotxt|csv|ical|taskwarrior file
+export --format todotxt|markdown|csv|ical|taskwarrior [--output|-o file]
+       [filter]
+sync file
+undo
+redo
+archive [index...]|list [filter]|search filter
+unarchive index...
+config list|get key|set key value [--project]
+init
+lists
+list-create|list-delete name
+list-rename name new-name
+use name
+move [index...] --to list
+
+indexes can be positions (3), ranges (2-5), all or completed
+fields are priority:A, due:2026-11-01, project:web and tags:bug,ui
+filters look like status:open tag:bug due<2026-11-01 \"login\"
+templates look like \"{id:>3} {status} {title} {tags} {due}\"
+
+options for every action:
+--output text|json|ndjson, given before the action for export
+--color always|never|auto
+--list name
+--global
+--set key=value
+
+settings are read from .terminal-todo.toml in the current directory or above,
+then from ~/.config/terminal-todo/config.toml, and a database.path in
+.terminal-todo.toml is a relative path inside the project
+a .terminal-todo file or directory in the current directory or above is used
+as the database instead of the global one, unless --global is given
+the last undo.depth changes made to the list in use, archiving included, can
+be undone
+changes to items are kept next to the list, see them with history
+with database.backend=events changes are appended to a log next to the list
+instead of rewriting it, with a snapshot every database.compact_after changes
+the list is backed up before every change, keeping the last backup.keep copies
+and the last one of each of the last backup.daily days
+with database.lenient=true lines of the list that cannot be read are skipped,
+and set aside in a quarantine file when it is next written
+a list changed outside of terminal-todo since it was last written is reported,
+and changing it asks first
+an encrypted list needs its passphrase, from TODO_PASSPHRASE, the file at
+encryption.key_file or asked for, and TODO_NEW_PASSPHRASE for change-passphrase
+Taskwarrior fields such as wait, scheduled, depends and recur are not imported
+but reported, and annotations are joined into the description with \"; \"
+words of titles that todo.txt would read as +projects, @contexts, key:value
+fields, or at the start as x, (A) or a date, are exported with a \\ before them
+sync keeps a Markdown (- [ ] title) or Org (* TODO title) file in step with the
+list, taking in items checked, unchecked or added in it, then updating its
+items in place and adding those new to the list at its end
+completed items done more than archive.after_days days ago are archived when
+listing";
+
+/// Options that apply to every action and can be given anywhere on the
+/// command line, like `--output json` or `--set list.format=table`, as the
+/// settings they override.
+#[derive(PartialEq, Debug)]
+pub struct GlobalOptions {
+    pub settings: Vec<(String, String)>,
+}
+
+impl GlobalOptions {
+    /// The output asked for on the command line, for reporting errors before
+    /// the configuration is loaded.
+    pub fn output_mode(&self) -> OutputMode {
+        self.settings
+            .iter()
+            .rev()
+            .find(|(key, _)| key == "output")
+            .and_then(|(_, value)| OutputMode::new(value).ok())
+            .unwrap_or(OutputMode::Text)
+    }
+}
 
 pub struct Validator {}
 
 impl<'a> Validator {
-    pub fn validate_input<L: Logger + 'a>(
-        mut user_input: Args,
+    pub fn validate_input<T, L: Logger + 'a>(
+        mut user_input: T,
         logger: &'a mut L,
         database: &'a dyn Database,
-    ) -> Result<Action<'a>, &'static str> {
+    ) -> Result<Action<'a>, &'static str>
+    where
+        T: ExactSizeIterator<Item = String>,
+    {
         if user_input.len() < 2 {
-            return Err("An action needs to be provided\ncreate\nlist\nedit [index]\ndone [index]\nundone [index]\ndelete [index]");
+            return Err(USAGE);
         }
 
         // Skipping the first arg since it the program name
         user_input.next();
 
-        // Get the action
-        let mut valid_action: Action = match user_input.next() {
-            Some(op) => Action::new(&op, logger, database)?,
+        // Get the action, which can be an alias standing for an action and
+        // some of its arguments
+        let (action_name, alias_arguments) = match user_input.next() {
+            Some(op) => Validator::expand_alias(op),
             None => return Err("Error parsing action"),
         };
+        let mut valid_action: Action = Action::new(&action_name, logger, database)?;
 
         // Get the action arguments
-        Validator::validate_arguments::<Args, L>(user_input, &mut valid_action)?;
+        Validator::validate_arguments::<_, L>(
+            alias_arguments.into_iter().chain(user_input),
+            &mut valid_action,
+        )?;
 
         Ok(valid_action)
     }
 
-    fn validate_arguments<'b, T, L: Logger>(
-        mut user_args: T,
-        valid_action: &mut Action<'a>,
-    ) -> Result<(), &'static str>
+    /// Takes the global options out of the user input, returning them along
+    /// with the rest of the input.
+    pub fn extract_global_options<T>(
+        user_input: T,
+    ) -> Result<(GlobalOptions, Vec<String>), &'static str>
     where
         T: Iterator<Item = String>,
     {
-        let mut arguments: Vec<String> = vec![];
+        let mut global_options = GlobalOptions { settings: vec![] };
+        let mut rest: Vec<String> = vec![];
+        let mut user_input = user_input;
+
+        while let Some(arg) = user_input.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) => (option, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            match option {
+                // After export, --output names the file to export to
+                "--output" if rest.get(1).is_some_and(|action| action == "export") => {
+                    rest.push(arg)
+                }
+                "--output" | "--color" | "--list" | "--set" => {
+                    let value = match inline_value.or_else(|| user_input.next()) {
+                        Some(value) => value,
+                        None => return Err("Option requires a value"),
+                    };
+
+                    let setting = match option {
+                        "--output" => {
+                            OutputMode::new(&value)?;
+                            ("output".to_string(), value)
+                        }
+                        "--color" => {
+                            ColorChoice::new(&value)?;
+                            ("color".to_string(), value)
+                        }
+                        "--list" => {
+                            config::validate("database.list", &value)?;
+                            ("database.list".to_string(), value)
+                        }
+                        _ => match value.split_once('=') {
+                            Some((key, value)) => {
+                                config::validate(key, value)?;
+                                (key.to_string(), value.to_string())
+                            }
+                            None => return Err("Settings should be given as key=value"),
+                        },
+                    };
+
+                    global_options.settings.push(setting);
+                }
+                "--global" => global_options
+                    .settings
+                    .push(("database.discover".into(), "false".into())),
+                _ => rest.push(arg),
+            }
+        }
+
+        Ok((global_options, rest))
+    }
 
-        while let Some(arg) = user_args.next() {
-            arguments.push(arg);
+    fn expand_alias(action_name: String) -> (String, Vec<String>) {
+        if ActionType::new(&action_name).is_ok() {
+            return (action_name, vec![]);
         }
 
+        let alias = match config::current().get(&format!("alias.{action_name}")) {
+            Some(alias) => alias,
+            None => return (action_name, vec![]),
+        };
+
+        let mut words = config::split_words(alias).into_iter();
+        match words.next() {
+            Some(aliased_action) => (aliased_action, words.collect()),
+            None => (action_name, vec![]),
+        }
+    }
+
+    // The logger type is named by callers, as the tests do
+    #[allow(clippy::extra_unused_type_parameters)]
+    fn validate_arguments<T, L: Logger>(
+        user_args: T,
+        valid_action: &mut Action<'a>,
+    ) -> Result<(), &'static str>
+    where
+        T: Iterator<Item = String>,
+    {
+        let arguments: Vec<String> = user_args.collect();
+
         match valid_action.requires_arguments {
-            x if x && arguments.len() <= 0 => return Err("Action requires arguments"),
-            x if !x && arguments.len() > 0 => {
+            true if arguments.is_empty() => Err("Action requires arguments"),
+            false if !arguments.is_empty() && !valid_action.action_type.accepts_arguments() => {
                 valid_action
                     .logger
                     .as_mut()
                     .unwrap()
-                    .log_errln(&"Action does not take arguments")
+                    .log_errln("Action does not take arguments")
                     .unwrap();
                 valid_action.arguments = arguments;
                 Ok(())
@@ -66,10 +244,7 @@ impl<'a> Validator {
 
 #[cfg(test)]
 mod tests {
-    use std::{
-        error::Error,
-        io::{self, ErrorKind},
-    };
+    use std::{error::Error, io};
 
     use super::*;
     use crate::{action::database::Database, log_wrapper::Logger};
@@ -79,20 +254,20 @@ mod tests {
     }
 
     impl Logger for MockErrorLogger {
-        fn log_errln<'a>(&mut self, msg: &'a str) -> Result<(), Box<dyn std::error::Error>> {
-            if msg == "" {
-                return Err(Box::new(io::Error::new(ErrorKind::Other, "oh no!")));
+        fn log_errln(&mut self, msg: &str) -> Result<(), Box<dyn std::error::Error>> {
+            if msg.is_empty() {
+                return Err(Box::new(io::Error::other("oh no!")));
             }
             self.was_called = true;
             Ok(())
         }
-        fn log_stdln<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_stdln(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
-        fn log_err<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_err(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
-        fn log_std<'a>(&mut self, _msg: &'a str) -> Result<(), Box<dyn Error>> {
+        fn log_std(&mut self, _msg: &str) -> Result<(), Box<dyn Error>> {
             Ok(())
         }
 
@@ -123,7 +298,7 @@ mod tests {
         let database = MockDatabase {};
 
         let mut action_with_args: Action =
-            Action::new(&"create", &mut mock_logger, &database).unwrap();
+            Action::new("create", &mut mock_logger, &database).unwrap();
 
         let args = vec![String::from("test"), String::from("test")];
 
@@ -141,7 +316,7 @@ mod tests {
         let database = MockDatabase {};
 
         let mut action_with_no_required_args: Action =
-            Action::new(&"edit", &mut mock_logger, &database).unwrap();
+            Action::new("edit", &mut mock_logger, &database).unwrap();
 
         assert_eq!(
             Validator::validate_arguments::<std::vec::IntoIter<String>, MockErrorLogger>(
@@ -158,7 +333,7 @@ mod tests {
         let database = MockDatabase {};
 
         let mut action_with_no_required_args: Action =
-            Action::new(&"create", &mut mock_logger, &database).unwrap();
+            Action::new("create", &mut mock_logger, &database).unwrap();
 
         let args = vec![String::from("test"), String::from("test")];
 
@@ -171,4 +346,59 @@ mod tests {
 
         assert!(mock_logger.was_called);
     }
+
+    #[test]
+    fn extracts_global_options_from_anywhere() {
+        let args = ["todo", "list", "--output", "json", "tag:bug"]
+            .into_iter()
+            .map(String::from);
+        let (global_options, rest) = Validator::extract_global_options(args).unwrap();
+        assert_eq!(
+            global_options.settings,
+            vec![("output".to_string(), "json".to_string())]
+        );
+        assert_eq!(rest, vec!["todo", "list", "tag:bug"]);
+
+        let args = [
+            "todo",
+            "--output=ndjson",
+            "list",
+            "--set",
+            "list.format=table",
+        ]
+        .into_iter()
+        .map(String::from);
+        let (global_options, rest) = Validator::extract_global_options(args).unwrap();
+        assert_eq!(
+            global_options.settings,
+            vec![
+                ("output".to_string(), "ndjson".to_string()),
+                ("list.format".to_string(), "table".to_string())
+            ]
+        );
+        assert_eq!(rest, vec!["todo", "list"]);
+
+        let args = ["todo", "--set", "list.colour=red"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(
+            Validator::extract_global_options(args),
+            Err("Unknown setting")
+        );
+
+        let args = ["todo", "--output=json", "export", "--output", "out.csv"]
+            .into_iter()
+            .map(String::from);
+        let (global_options, rest) = Validator::extract_global_options(args).unwrap();
+        assert_eq!(global_options.output_mode(), OutputMode::Json);
+        assert_eq!(rest, vec!["todo", "export", "--output", "out.csv"]);
+
+        let args = ["todo", "list", "--output", "xml"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(
+            Validator::extract_global_options(args),
+            Err("Output can only be text, json or ndjson")
+        );
+    }
 }